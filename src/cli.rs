@@ -0,0 +1,193 @@
+use std::path::PathBuf;
+
+use rayman::config::Config;
+use rayman::vec3::Vec3;
+
+pub const HELP: &str = "\
+Rayman, a ray tracer.
+
+USAGE:
+    rayman [OPTIONS]
+
+The image is written to standard output, unless --output is given. Options override the settings
+of the scene file, which in turn override the defaults.
+
+OPTIONS:
+    -s, --scene <FILE>            Scene description file (JSON) to render
+    -o, --output <FILE>           Write the image to a .png or .ppm file instead of standard output
+    -w, --width <PIXELS>          Image width [default: 1200]
+    -a, --aspect-ratio <RATIO>    Image aspect ratio, as a number or WIDTH:HEIGHT [default: 3:2]
+    -n, --samples <COUNT>         Samples per pixel [default: 500]
+    -d, --max-depth <COUNT>       Maximum number of times a ray can bounce [default: 50]
+        --look-from <X,Y,Z>       Point the camera looks from [default: 13,2,3]
+        --look-at <X,Y,Z>         Point the camera looks at [default: 0,0,0]
+        --fov <DEGREES>           Vertical field of view [default: 20]
+        --aperture <SIZE>         Camera aperture, 0 disables depth of field [default: 0.1]
+        --focus-distance <UNITS>  Distance to the plane in perfect focus [default: 10]
+        --shutter <OPEN:CLOSE>    Times the shutter opens and closes, for motion blur [default: 0:1]
+        --seed <NUMBER>           Seed for the random numbers [default: 0]
+    -j, --threads <COUNT>         Number of render threads [default: number of CPU cores]
+    -h, --help                    Print this help
+";
+
+pub enum Command {
+    Help,
+    Render(Box<Arguments>), // RUST: Boxed, since the arguments are much larger than Help.
+}
+
+// Every setting is optional. None means "keep the value from the scene file or the defaults".
+#[derive(Default)]
+pub struct Arguments {
+    pub output: Option<PathBuf>,
+    pub scene: Option<PathBuf>,
+    aperture: Option<f64>,
+    aspect_ratio: Option<f64>,
+    focus_distance: Option<f64>,
+    look_at: Option<Vec3>,
+    look_from: Option<Vec3>,
+    maximum_ray_bounce_depth: Option<u8>,
+    samples_per_pixel: Option<u16>,
+    seed: Option<u64>,
+    shutter: Option<(f64, f64)>,
+    threads: Option<usize>,
+    vertical_field_of_view: Option<f64>,
+    width: Option<u16>,
+}
+
+impl Arguments {
+    pub fn apply_to(&self, config: &mut Config) {
+        if let Some(aperture) = self.aperture {
+            config.camera_aperture = aperture;
+        }
+        if let Some(aspect_ratio) = self.aspect_ratio {
+            config.aspect_ratio = aspect_ratio;
+        }
+        if let Some(focus_distance) = self.focus_distance {
+            config.camera_focus_distance = focus_distance;
+        }
+        if let Some(look_at) = self.look_at {
+            config.camera_look_at = look_at;
+        }
+        if let Some(look_from) = self.look_from {
+            config.camera_look_from = look_from;
+        }
+        if let Some(maximum_ray_bounce_depth) = self.maximum_ray_bounce_depth {
+            config.maximum_ray_bounce_depth = maximum_ray_bounce_depth;
+        }
+        if let Some(samples_per_pixel) = self.samples_per_pixel {
+            config.samples_per_pixel = samples_per_pixel;
+        }
+        if let Some(seed) = self.seed {
+            config.seed = seed;
+        }
+        if let Some((shutter_open, shutter_close)) = self.shutter {
+            config.camera_shutter_open = shutter_open;
+            config.camera_shutter_close = shutter_close;
+        }
+        if let Some(threads) = self.threads {
+            config.threads = threads;
+        }
+        if let Some(vertical_field_of_view) = self.vertical_field_of_view {
+            config.camera_vertical_field_of_view = vertical_field_of_view;
+        }
+        if let Some(width) = self.width {
+            config.image_width = width;
+        }
+    }
+}
+
+// Options are written as "--name value" or "--name=value". The first argument (the name of the
+// program) must already be skipped.
+pub fn parse_arguments(arguments: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut parsed = Arguments::default();
+    let mut arguments = arguments;
+
+    while let Some(argument) = arguments.next() {
+        // RUST: split_once() returns None if there is no '='. Then the value is the next argument.
+        let (name, inline_value) = match argument.split_once('=') {
+            Some((name, value)) if name.starts_with('-') => {
+                (name.to_string(), Some(value.to_string()))
+            }
+            _ => (argument.clone(), None),
+        };
+
+        if name == "-h" || name == "--help" {
+            return Ok(Command::Help);
+        }
+        if !name.starts_with('-') {
+            return Err(format!("unexpected argument \"{}\"", argument));
+        }
+
+        let value = match inline_value.or_else(|| arguments.next()) {
+            Some(value) => value,
+            None => return Err(format!("{} needs a value", name)),
+        };
+
+        match name.as_str() {
+            "-s" | "--scene" => parsed.scene = Some(PathBuf::from(value)),
+            "-o" | "--output" => parsed.output = Some(PathBuf::from(value)),
+            "-w" | "--width" => parsed.width = Some(parse_number(&name, &value)?),
+            "-a" | "--aspect-ratio" => parsed.aspect_ratio = Some(parse_aspect_ratio(&value)?),
+            "-n" | "--samples" => parsed.samples_per_pixel = Some(parse_number(&name, &value)?),
+            "-d" | "--max-depth" => {
+                parsed.maximum_ray_bounce_depth = Some(parse_number(&name, &value)?)
+            }
+            "--look-from" => parsed.look_from = Some(parse_vector(&name, &value)?),
+            "--look-at" => parsed.look_at = Some(parse_vector(&name, &value)?),
+            "--fov" => parsed.vertical_field_of_view = Some(parse_number(&name, &value)?),
+            "--aperture" => parsed.aperture = Some(parse_number(&name, &value)?),
+            "--focus-distance" => parsed.focus_distance = Some(parse_number(&name, &value)?),
+            "--seed" => parsed.seed = Some(parse_number(&name, &value)?),
+            "--shutter" => parsed.shutter = Some(parse_shutter(&value)?),
+            "-j" | "--threads" => parsed.threads = Some(parse_number(&name, &value)?),
+            _ => return Err(format!("unknown option \"{}\"", name)),
+        }
+    }
+
+    Ok(Command::Render(Box::new(parsed)))
+}
+
+// RUST: Any type that can be parsed from a string (integers, floats, etc.) implements FromStr.
+fn parse_number<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value
+        .parse::<T>()
+        .map_err(|_| format!("invalid value \"{}\" for {}", value, name))
+}
+
+// Either a number (1.5) or a ratio (3:2).
+fn parse_aspect_ratio(value: &str) -> Result<f64, String> {
+    let name = "--aspect-ratio";
+    match value.split_once(':') {
+        Some((width, height)) => {
+            Ok(parse_number::<f64>(name, width)? / parse_number::<f64>(name, height)?)
+        }
+        None => parse_number(name, value),
+    }
+}
+
+// Two times, OPEN:CLOSE.
+fn parse_shutter(value: &str) -> Result<(f64, f64), String> {
+    let name = "--shutter";
+    match value.split_once(':') {
+        Some((open, close)) => Ok((parse_number(name, open)?, parse_number(name, close)?)),
+        None => Err(format!(
+            "{} needs two times (OPEN:CLOSE), got \"{}\"",
+            name, value
+        )),
+    }
+}
+
+fn parse_vector(name: &str, value: &str) -> Result<Vec3, String> {
+    let components = value
+        .split(',')
+        .map(|component| parse_number::<f64>(name, component.trim()))
+        .collect::<Result<Vec<f64>, String>>()?;
+
+    match components[..] {
+        [x, y, z] => Ok(Vec3::new(x, y, z)),
+        _ => Err(format!(
+            "{} needs three comma separated numbers (X,Y,Z), got \"{}\"",
+            name, value
+        )),
+    }
+}
@@ -1,27 +1,136 @@
+use crate::scene::background::Background;
+use crate::scene::light::light::Light;
+use crate::scene::medium::homogeneous::HomogeneousMedium;
+use crate::tracer::camera::Camera;
 use crate::vec3::Point3;
 
 pub struct Config {
     pub aspect_ratio: f64,                  // Image / camera aspect ratio.
+    pub background: Background,             // Color of rays that don't hit anything.
     pub camera_aperture: f64,               // To simulate depth of field.
     pub camera_focus_distance: f64,         // To simulate depth of field.
     pub camera_look_at: Point3,             // The point that the camera will look at.
     pub camera_look_from: Point3,           // The point that the camera will look from.
+    pub camera_shutter_close: f64,          // Time the shutter closes. For motion blur.
+    pub camera_shutter_open: f64,           // Time the shutter opens. For motion blur.
     pub camera_vertical_field_of_view: f64, // In degrees.
+    pub fog: Option<HomogeneousMedium>,     // Fills the whole scene. Outside of it, too.
     pub image_width: u16,                   // In pixels.
-    pub samples_per_pixel: u16,             // For anti-aliasing.
+    pub lights: Vec<Box<dyn Light>>, // Point, spot and directional lights. Next to the world.
+    pub maximum_ray_bounce_depth: u8, // Times a ray can bounce off objects.
+    pub samples_per_pixel: u16,      // For anti-aliasing.
+    pub seed: u64,                   // The same seed always renders the same image.
+    pub threads: usize,              // Number of threads rendering tiles in parallel.
 }
 
 impl Default for Config {
     fn default() -> Config {
         Config {
             aspect_ratio: 3.0 / 2.0,
+            background: Background::default(),
             camera_aperture: 0.1,
             camera_focus_distance: 10.0,
             camera_look_at: Point3::new(0.0, 0.0, 0.0),
             camera_look_from: Point3::new(13.0, 2.0, 3.0),
+            camera_shutter_close: 1.0,
+            camera_shutter_open: 0.0,
             camera_vertical_field_of_view: 20.0,
+            fog: None,
             image_width: 1200,
+            lights: Vec::new(),
+            maximum_ray_bounce_depth: 50,
             samples_per_pixel: 500,
+            seed: 0,
+            // RUST: available_parallelism() is an estimate of how many threads can run at once
+            // (usually the number of CPU cores). It can fail, so fall back to a single thread.
+            threads: std::thread::available_parallelism().map_or(1, |threads| threads.get()),
         }
     }
 }
+
+impl Config {
+    // In pixels. Follows from the width and the aspect ratio.
+    pub fn image_height(&self) -> u16 {
+        (self.image_width as f64 / self.aspect_ratio) as u16
+    }
+
+    // Settings that would crash the renderer, or render nonsense, are rejected with a message
+    // explaining why.
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.aspect_ratio > 0.0 && self.aspect_ratio.is_finite()) {
+            return Err(format!(
+                "aspect ratio must be a positive number, got {}",
+                self.aspect_ratio
+            ));
+        }
+        // Pixel positions are divided by (width - 1) and (height - 1).
+        if self.image_width < 2 {
+            return Err(format!(
+                "image width must be at least 2 pixels, got {}",
+                self.image_width
+            ));
+        }
+        if self.image_height() < 2 {
+            return Err(format!(
+                "image height must be at least 2 pixels, got {} (width {} / aspect ratio {})",
+                self.image_height(),
+                self.image_width,
+                self.aspect_ratio
+            ));
+        }
+        if self.samples_per_pixel == 0 {
+            return Err("samples per pixel must be at least 1".to_string());
+        }
+        if self.maximum_ray_bounce_depth == 0 {
+            return Err("maximum ray bounce depth must be at least 1".to_string());
+        }
+        if self.threads == 0 {
+            return Err("threads must be at least 1".to_string());
+        }
+        if !(self.camera_vertical_field_of_view > 0.0 && self.camera_vertical_field_of_view < 180.0)
+        {
+            return Err(format!(
+                "vertical field of view must be between 0 and 180 degrees, got {}",
+                self.camera_vertical_field_of_view
+            ));
+        }
+        if self.camera_aperture < 0.0 {
+            return Err(format!(
+                "camera aperture can't be negative, got {}",
+                self.camera_aperture
+            ));
+        }
+        if self.camera_focus_distance <= 0.0 {
+            return Err(format!(
+                "camera focus distance must be positive, got {}",
+                self.camera_focus_distance
+            ));
+        }
+        // The camera needs a direction to look in.
+        if (self.camera_look_from - self.camera_look_at).length_squared() == 0.0 {
+            return Err("camera can't look at the point it looks from".to_string());
+        }
+        let (shutter_open, shutter_close) = (self.camera_shutter_open, self.camera_shutter_close);
+        if !(shutter_open.is_finite() && shutter_close.is_finite() && shutter_open <= shutter_close)
+        {
+            return Err(format!(
+                "camera shutter can't close ({}) before it opens ({})",
+                self.camera_shutter_close, self.camera_shutter_open
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn camera(&self) -> Camera {
+        Camera::new(
+            self.camera_look_from,
+            self.camera_look_at,
+            self.camera_vertical_field_of_view,
+            self.aspect_ratio,
+            self.camera_aperture,
+            self.camera_focus_distance,
+            (self.camera_shutter_open, self.camera_shutter_close),
+        )
+    }
+}
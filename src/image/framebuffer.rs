@@ -0,0 +1,67 @@
+use crate::vec3::Color;
+
+// The framebuffer holds the rendered image in memory. For every pixel it keeps the sum of the
+// light (radiance) of all samples taken, and how many samples there were. Radiance is linear and
+// isn't clamped, so it can be brighter than 1.0. Turning it into something displayable (averaging,
+// gamma correction, etc.) is left to whoever writes or shows the image.
+//
+// Pixels are stored in rows, left to right, and the rows top to bottom. (x,y) = (0,0) is the top
+// left pixel.
+pub struct Framebuffer {
+    height: u16,
+    radiance: Vec<Color>,    // Sum of the radiance of all samples.
+    sample_counts: Vec<u32>, // Number of samples.
+    width: u16,
+}
+
+impl Framebuffer {
+    pub fn new(width: u16, height: u16) -> Framebuffer {
+        let pixel_count = width as usize * height as usize;
+        Framebuffer {
+            height,
+            radiance: vec![Color::zero(); pixel_count],
+            sample_counts: vec![0; pixel_count],
+            width,
+        }
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    fn index(&self, x: u16, y: u16) -> usize {
+        (y as usize * self.width as usize) + x as usize
+    }
+
+    // Add the radiance of a number of samples, summed up, to the pixel.
+    pub fn add_samples(&mut self, x: u16, y: u16, radiance: Color, sample_count: u32) {
+        let index = self.index(x, y);
+        self.radiance[index] = self.radiance[index] + radiance;
+        self.sample_counts[index] += sample_count;
+    }
+
+    pub fn sample_count(&self, x: u16, y: u16) -> u32 {
+        self.sample_counts[self.index(x, y)]
+    }
+
+    // The average radiance of the samples of the pixel. Black if there are none (yet).
+    pub fn pixel(&self, x: u16, y: u16) -> Color {
+        let index = self.index(x, y);
+        match self.sample_counts[index] {
+            0 => Color::zero(),
+            sample_count => self.radiance[index] / sample_count as f64,
+        }
+    }
+
+    // The average radiance of every pixel, in the order they are stored.
+    pub fn pixels(&self) -> Vec<Color> {
+        (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .map(|(x, y)| self.pixel(x, y))
+            .collect()
+    }
+}
@@ -0,0 +1,220 @@
+use crate::image::framebuffer::Framebuffer;
+use crate::vec3::Color;
+
+// Radiance HDR (.hdr, also .pic) is the most common format for high dynamic range images, like
+// environment maps. The colors are linear (no gamma correction) and can be far brighter than 1.0.
+//
+// Every pixel is four bytes, "RGBE": a mantissa for red, green and blue, and a shared exponent.
+// Like a floating point number, but with one exponent for all three colors. That's enough, since
+// the colors of a pixel are usually about as bright as each other.
+//
+// The file starts with a text header: "#?RADIANCE", lines of settings, and an empty line. Then the
+// size, as "-Y height +X width" (rows top to bottom, pixels left to right). The rows (scanlines)
+// are either plain pixels, or "run-length encoded" (RLE).
+pub fn read_hdr(bytes: &[u8]) -> Result<Framebuffer, String> {
+    let mut position = 0;
+
+    let magic_number = read_line(bytes, &mut position)?;
+    if !magic_number.starts_with("#?") {
+        return Err("not a Radiance HDR file".to_string());
+    }
+    loop {
+        let line = read_line(bytes, &mut position)?;
+        if line.is_empty() {
+            break;
+        }
+        if let Some(format) = line.strip_prefix("FORMAT=") {
+            if format != "32-bit_rle_rgbe" {
+                return Err(format!("unsupported HDR pixel format {}", format));
+            }
+        }
+    }
+
+    // Other orientations exist, but are rarely used.
+    let resolution = read_line(bytes, &mut position)?;
+    let parts: Vec<&str> = resolution.split_whitespace().collect();
+    let (height, width) = match parts.as_slice() {
+        ["-Y", height, "+X", width] => (height.parse::<u16>(), width.parse::<u16>()),
+        _ => return Err(format!("unsupported HDR orientation \"{}\"", resolution)),
+    };
+    let (Ok(height), Ok(width)) = (height, width) else {
+        return Err(format!("invalid HDR size \"{}\"", resolution));
+    };
+    if width == 0 || height == 0 {
+        return Err("HDR image has no pixels".to_string());
+    }
+
+    let mut framebuffer = Framebuffer::new(width, height);
+    let mut scanline = vec![[0u8; 4]; width as usize];
+    for y in 0..height {
+        read_scanline(bytes, &mut position, &mut scanline)?;
+        for (x, rgbe) in scanline.iter().enumerate() {
+            framebuffer.add_samples(x as u16, y, rgbe_to_color(*rgbe), 1);
+        }
+    }
+
+    Ok(framebuffer)
+}
+
+// The mantissas are fractions (of 256), scaled by 2 to the power of the exponent. The exponent is
+// stored with 128 added, so it can be negative. An exponent of 0 is black.
+fn rgbe_to_color([r, g, b, e]: [u8; 4]) -> Color {
+    if e == 0 {
+        return Color::zero();
+    }
+    let scale = 2.0f64.powi(e as i32 - (128 + 8));
+    Color::new(r as f64, g as f64, b as f64) * scale
+}
+
+// A line of the text header, without the newline.
+fn read_line(bytes: &[u8], position: &mut usize) -> Result<String, String> {
+    let rest = &bytes[*position..];
+    let Some(length) = rest.iter().position(|&byte| byte == b'\n') else {
+        return Err("unexpected end of the HDR header".to_string());
+    };
+    *position += length + 1;
+    Ok(String::from_utf8_lossy(&rest[..length])
+        .trim_end()
+        .to_string())
+}
+
+fn read_byte(bytes: &[u8], position: &mut usize) -> Result<u8, String> {
+    let byte = *bytes
+        .get(*position)
+        .ok_or("unexpected end of the HDR pixels")?;
+    *position += 1;
+    Ok(byte)
+}
+
+// A run-length encoded scanline starts with the bytes 2, 2 and its width (two bytes). Then come
+// all the red mantissas, all the green ones, the blue ones and finally the exponents. Each of the
+// four is a sequence of "runs": a count above 128 means the next byte repeated (count - 128)
+// times, otherwise count bytes follow as they are. Neighbouring pixels often have the same
+// exponent, so that compresses well.
+//
+// Scanlines shorter than 8 or longer than 32767 pixels can't be encoded, so they are plain.
+fn read_scanline(
+    bytes: &[u8],
+    position: &mut usize,
+    scanline: &mut [[u8; 4]],
+) -> Result<(), String> {
+    let width = scanline.len();
+    let is_encoded = (8..=0x7fff).contains(&width)
+        && bytes.get(*position..*position + 2) == Some(&[2, 2])
+        && bytes
+            .get(*position + 2)
+            .is_some_and(|byte| byte & 0x80 == 0);
+
+    if !is_encoded {
+        for pixel in scanline.iter_mut() {
+            for component in pixel.iter_mut() {
+                *component = read_byte(bytes, position)?;
+            }
+        }
+        return Ok(());
+    }
+
+    *position += 2;
+    let encoded_width =
+        ((read_byte(bytes, position)? as usize) << 8) | read_byte(bytes, position)? as usize;
+    if encoded_width != width {
+        return Err(format!(
+            "HDR scanline is {} pixels wide, expected {}",
+            encoded_width, width
+        ));
+    }
+
+    for component in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let count = read_byte(bytes, position)? as usize;
+            let (run_length, is_run) = if count > 128 {
+                (count - 128, true)
+            } else {
+                (count, false)
+            };
+            if run_length == 0 || x + run_length > width {
+                return Err("invalid run in an HDR scanline".to_string());
+            }
+
+            let repeated = if is_run {
+                read_byte(bytes, position)?
+            } else {
+                0
+            };
+            for pixel in &mut scanline[x..(x + run_length)] {
+                pixel[component] = if is_run {
+                    repeated
+                } else {
+                    read_byte(bytes, position)?
+                };
+            }
+            x += run_length;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &[u8] = b"#?RADIANCE\n# A comment.\nFORMAT=32-bit_rle_rgbe\n\n";
+
+    fn rgb(color: Color) -> [f64; 3] {
+        [color.x(), color.y(), color.z()]
+    }
+
+    #[test]
+    fn reads_plain_scanlines() {
+        let mut file = HEADER.to_vec();
+        file.extend_from_slice(b"-Y 2 +X 1\n");
+        file.extend_from_slice(&[128, 64, 32, 129]); // 2^(129 - 136) = 1/128, so 1, 0.5, 0.25.
+        file.extend_from_slice(&[128, 128, 128, 133]); // 128 * 2^-3 = 16, brighter than 1.0.
+
+        let framebuffer = read_hdr(&file).unwrap();
+        assert_eq!((framebuffer.width(), framebuffer.height()), (1, 2));
+        assert_eq!(rgb(framebuffer.pixel(0, 0)), [1.0, 0.5, 0.25]);
+        assert_eq!(rgb(framebuffer.pixel(0, 1)), [16.0, 16.0, 16.0]);
+    }
+
+    #[test]
+    fn reads_run_length_encoded_scanlines() {
+        let mut file = HEADER.to_vec();
+        file.extend_from_slice(b"-Y 1 +X 8\n");
+        file.extend_from_slice(&[2, 2, 0, 8]);
+        file.extend_from_slice(&[128 + 8, 128]); // Red: a run of eight 128s.
+        file.extend_from_slice(&[8, 0, 16, 32, 48, 64, 80, 96, 112]); // Green: eight as they are.
+        file.extend_from_slice(&[128 + 8, 0]); // Blue: a run of zeros.
+        file.extend_from_slice(&[128 + 3, 129, 5, 129, 129, 129, 129, 129]); // Exponents: both.
+
+        let framebuffer = read_hdr(&file).unwrap();
+        for x in 0..8 {
+            assert_eq!(rgb(framebuffer.pixel(x, 0)), [1.0, x as f64 / 8.0, 0.0]);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_files() {
+        let error = |file: &[u8]| read_hdr(file).err().unwrap();
+        assert_eq!(error(b"P6\n"), "not a Radiance HDR file");
+        assert_eq!(
+            error(b"#?RADIANCE\nFORMAT=32-bit_rle_xyze\n\n"),
+            "unsupported HDR pixel format 32-bit_rle_xyze"
+        );
+        assert_eq!(
+            error(b"#?RADIANCE\n\n+Y 1 +X 1\n"),
+            "unsupported HDR orientation \"+Y 1 +X 1\""
+        );
+        assert_eq!(
+            error(b"#?RADIANCE\n\n-Y 1 +X 2\n\x80\x80\x80\x81"),
+            "unexpected end of the HDR pixels"
+        );
+
+        let mut file = HEADER.to_vec();
+        file.extend_from_slice(b"-Y 1 +X 8\n");
+        file.extend_from_slice(&[2, 2, 0, 8, 128 + 9, 0]);
+        assert_eq!(error(&file), "invalid run in an HDR scanline");
+    }
+}
@@ -1,2 +1,9 @@
+pub mod framebuffer;
+pub mod hdr;
+pub mod pfm;
+pub mod png;
 pub mod ppm;
+pub mod reader;
 pub mod render;
+pub mod writer;
+pub mod zlib;
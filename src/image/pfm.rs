@@ -0,0 +1,133 @@
+use crate::image::framebuffer::Framebuffer;
+use crate::vec3::Color;
+
+// Portable FloatMap (.pfm) is the floating point sibling of PPM. Every color is a 32 bit floating
+// point number, linear and not clamped, so it can hold high dynamic range images.
+//
+// The header is text: "PF" (color) or "Pf" (grayscale), the width and height, and a scale. Its
+// sign tells the byte order of the numbers: negative is little-endian, positive is big-endian.
+// Unlike PPM, the rows go from the bottom of the image to the top.
+pub fn read_pfm(bytes: &[u8]) -> Result<Framebuffer, String> {
+    let mut position = 0;
+
+    let magic_number = next_token(bytes, &mut position)?;
+    let channels = match magic_number.as_str() {
+        "PF" => 3,
+        "Pf" => 1,
+        _ => return Err("not a PFM file (PF or Pf)".to_string()),
+    };
+    let width = next_token(bytes, &mut position)?
+        .parse::<u16>()
+        .map_err(|_| "invalid PFM width")?;
+    let height = next_token(bytes, &mut position)?
+        .parse::<u16>()
+        .map_err(|_| "invalid PFM height")?;
+    let scale = next_token(bytes, &mut position)?
+        .parse::<f64>()
+        .map_err(|_| "invalid PFM scale")?;
+    if width == 0 || height == 0 || scale == 0.0 || !scale.is_finite() {
+        return Err("invalid PFM header".to_string());
+    }
+    let is_little_endian = scale < 0.0;
+
+    // The numbers start after a single whitespace character.
+    position += 1;
+    let value_count = width as usize * height as usize * channels;
+    let Some(data) = bytes.get(position..(position + (value_count * 4))) else {
+        return Err("PFM file is too short for its size".to_string());
+    };
+
+    let values: Vec<f64> = data
+        .chunks_exact(4)
+        .map(|chunk| {
+            let chunk = [chunk[0], chunk[1], chunk[2], chunk[3]];
+            if is_little_endian {
+                f32::from_le_bytes(chunk) as f64
+            } else {
+                f32::from_be_bytes(chunk) as f64
+            }
+        })
+        .collect();
+
+    let mut framebuffer = Framebuffer::new(width, height);
+    for (index, pixel) in values.chunks_exact(channels).enumerate() {
+        let x = (index % width as usize) as u16;
+        let y = height - 1 - (index / width as usize) as u16; // Flip, bottom to top.
+        let color = match *pixel {
+            [gray] => Color::new(gray, gray, gray),
+            [r, g, b] => Color::new(r, g, b),
+            _ => unreachable!(),
+        };
+        framebuffer.add_samples(x, y, color, 1);
+    }
+
+    Ok(framebuffer)
+}
+
+// Header values are separated by whitespace.
+fn next_token(bytes: &[u8], position: &mut usize) -> Result<String, String> {
+    while bytes
+        .get(*position)
+        .is_some_and(|byte| byte.is_ascii_whitespace())
+    {
+        *position += 1;
+    }
+    let start = *position;
+    while bytes
+        .get(*position)
+        .is_some_and(|byte| !byte.is_ascii_whitespace())
+    {
+        *position += 1;
+    }
+    if start == *position {
+        return Err("unexpected end of the PFM header".to_string());
+    }
+    Ok(String::from_utf8_lossy(&bytes[start..*position]).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgb(color: Color) -> [f64; 3] {
+        [color.x(), color.y(), color.z()]
+    }
+
+    #[test]
+    fn reads_little_endian_color_bottom_row_first() {
+        let mut file = b"PF\n1 2\n-1.0\n".to_vec();
+        for value in [1.0f32, 2.0, 3.0, 4.0, 5.0, 6.5] {
+            file.extend_from_slice(&value.to_le_bytes());
+        }
+
+        let framebuffer = read_pfm(&file).unwrap();
+        assert_eq!((framebuffer.width(), framebuffer.height()), (1, 2));
+        assert_eq!(rgb(framebuffer.pixel(0, 1)), [1.0, 2.0, 3.0]);
+        assert_eq!(rgb(framebuffer.pixel(0, 0)), [4.0, 5.0, 6.5]);
+    }
+
+    #[test]
+    fn reads_big_endian_grayscale() {
+        let mut file = b"Pf 2 1 1.0\n".to_vec();
+        for value in [0.25f32, 100.0] {
+            file.extend_from_slice(&value.to_be_bytes());
+        }
+
+        let framebuffer = read_pfm(&file).unwrap();
+        assert_eq!(rgb(framebuffer.pixel(0, 0)), [0.25, 0.25, 0.25]);
+        assert_eq!(rgb(framebuffer.pixel(1, 0)), [100.0, 100.0, 100.0]);
+    }
+
+    #[test]
+    fn rejects_invalid_files() {
+        let error = |file: &[u8]| read_pfm(file).err().unwrap();
+        assert_eq!(error(b"P6 1 1 255\n"), "not a PFM file (PF or Pf)");
+        assert_eq!(error(b"PF 1 1 0\n"), "invalid PFM header");
+        assert_eq!(error(b"PF 1 -1 -1\n"), "invalid PFM height");
+        assert_eq!(error(b"PF 1 1"), "unexpected end of the PFM header");
+        assert_eq!(
+            error(b"PF 1 1 -1\n\0\0\0\0"),
+            "PFM file is too short for its size"
+        );
+    }
+}
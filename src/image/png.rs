@@ -0,0 +1,445 @@
+use std::io::{self, Write};
+
+use crate::image::framebuffer::Framebuffer;
+use crate::image::reader::undo_gamma_correction;
+use crate::image::writer::{gamma_corrected_bytes, ImageWriter};
+use crate::image::zlib;
+use crate::vec3::Color;
+
+// PNG is a compressed, lossless image format that every image viewer can open. A PNG file is a
+// signature followed by "chunks". Every chunk has a length, a four letter type, its data and a
+// checksum (CRC-32) of the type and data. We only need three chunks:
+// - IHDR: The image header (size, color type, etc.).
+// - IDAT: The compressed pixels.
+// - IEND: Marks the end of the file.
+pub struct PngWriter;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+const BYTES_PER_PIXEL: usize = 3; // Red, green and blue. 8 bits each.
+
+impl ImageWriter for PngWriter {
+    fn write_image(&self, output: &mut dyn Write, framebuffer: &Framebuffer) -> io::Result<()> {
+        let image_width = framebuffer.width();
+        let image_height = framebuffer.height();
+        output.write_all(&SIGNATURE)?;
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&(image_width as u32).to_be_bytes());
+        header.extend_from_slice(&(image_height as u32).to_be_bytes());
+        header.push(8); // Bit depth.
+        header.push(2); // Color type: RGB (truecolor).
+        header.push(0); // Compression method: DEFLATE.
+        header.push(0); // Filter method: adaptive filtering.
+        header.push(0); // Interlace method: none.
+        write_chunk(output, b"IHDR", &header)?;
+
+        let bytes: Vec<u8> = framebuffer
+            .pixels()
+            .into_iter()
+            .flat_map(gamma_corrected_bytes)
+            .collect();
+        let filtered = filter_scanlines(&bytes, image_width as usize * BYTES_PER_PIXEL);
+        write_chunk(output, b"IDAT", &zlib::compress(&filtered))?;
+
+        write_chunk(output, b"IEND", &[])
+    }
+}
+
+fn write_chunk(output: &mut dyn Write, chunk_type: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    output.write_all(&(data.len() as u32).to_be_bytes())?;
+    output.write_all(chunk_type)?;
+    output.write_all(data)?;
+
+    let crc = crc32(&[chunk_type.as_slice(), data]);
+    output.write_all(&crc.to_be_bytes())
+}
+
+// Before compression, every row (scanline) of bytes is "filtered". A filter replaces every byte
+// with its difference to a prediction made from its neighbours to the left and above. In smooth
+// images the differences are mostly small numbers, which compress a lot better than the original
+// bytes. Every row can use a different filter, stored as a byte in front of the row. We try them
+// all and pick the one with the smallest sum of differences, a common heuristic.
+fn filter_scanlines(bytes: &[u8], row_length: usize) -> Vec<u8> {
+    let mut filtered = Vec::with_capacity(bytes.len() + (bytes.len() / row_length));
+    let empty_row = vec![0; row_length];
+    let mut candidate = vec![0; row_length];
+    let mut best = vec![0; row_length];
+
+    for (index, row) in bytes.chunks(row_length).enumerate() {
+        let previous_row = if index == 0 {
+            &empty_row[..]
+        } else {
+            &bytes[(index - 1) * row_length..index * row_length]
+        };
+
+        let mut best_filter = 0;
+        let mut best_sum = u64::MAX;
+        for filter in 0..5 {
+            for i in 0..row_length {
+                let left = if i >= BYTES_PER_PIXEL {
+                    row[i - BYTES_PER_PIXEL]
+                } else {
+                    0
+                };
+                let above = previous_row[i];
+                let above_left = if i >= BYTES_PER_PIXEL {
+                    previous_row[i - BYTES_PER_PIXEL]
+                } else {
+                    0
+                };
+
+                let prediction = match filter {
+                    0 => 0,                                        // None.
+                    1 => left,                                     // Sub.
+                    2 => above,                                    // Up.
+                    3 => ((left as u16 + above as u16) / 2) as u8, // Average.
+                    _ => paeth_predictor(left, above, above_left), // Paeth.
+                };
+                candidate[i] = row[i].wrapping_sub(prediction);
+            }
+
+            // Treat the bytes as signed, so that small negative differences also count as small.
+            let sum: u64 = candidate
+                .iter()
+                .map(|byte| (*byte as i8).unsigned_abs() as u64)
+                .sum();
+            if sum < best_sum {
+                best_sum = sum;
+                best_filter = filter;
+                best.copy_from_slice(&candidate);
+            }
+        }
+
+        filtered.push(best_filter);
+        filtered.extend_from_slice(&best);
+    }
+
+    filtered
+}
+
+// Predicts with whichever neighbour (left, above or above left) is closest to left + above -
+// above left.
+fn paeth_predictor(left: u8, above: u8, above_left: u8) -> u8 {
+    let estimate = left as i16 + above as i16 - above_left as i16;
+    let distance_left = (estimate - left as i16).abs();
+    let distance_above = (estimate - above as i16).abs();
+    let distance_above_left = (estimate - above_left as i16).abs();
+
+    if distance_left <= distance_above && distance_left <= distance_above_left {
+        left
+    } else if distance_above <= distance_above_left {
+        above
+    } else {
+        above_left
+    }
+}
+
+// The cyclic redundancy check (CRC) is a checksum that detects corrupted data. The table holds the
+// CRC of every possible byte, so the checksum can be computed a byte at a time instead of a bit at
+// a time.
+//
+// RUST: A const fn can run at compile time. The table is computed once, by the compiler.
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut crc = n as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                0xEDB88320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[n] = crc;
+        n += 1;
+    }
+    table
+}
+
+fn crc32(parts: &[&[u8]]) -> u32 {
+    let mut crc = 0xFFFFFFFFu32;
+    for part in parts {
+        for byte in *part {
+            crc = CRC_TABLE[((crc ^ *byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+        }
+    }
+    crc ^ 0xFFFFFFFF
+}
+
+// Reads a PNG file, for image textures. All color types and bit depths are supported, but not
+// interlaced images. Transparency (alpha) is ignored.
+pub fn read_png(bytes: &[u8]) -> Result<Framebuffer, String> {
+    if !bytes.starts_with(&SIGNATURE) {
+        return Err("not a PNG file".to_string());
+    }
+
+    let mut header = None;
+    let mut palette: &[u8] = &[];
+    let mut compressed = Vec::new();
+    let mut position = SIGNATURE.len();
+    loop {
+        let (chunk_type, data) = read_chunk(bytes, &mut position)?;
+        match &chunk_type {
+            b"IHDR" => header = Some(PngHeader::parse(data)?),
+            b"PLTE" => palette = data,
+            b"IDAT" => compressed.extend_from_slice(data),
+            b"IEND" => break,
+            _ => {} // Other chunks (gamma, text, etc.) don't matter to us.
+        }
+    }
+    let header = header.ok_or("missing IHDR chunk")?;
+
+    let filtered = zlib::decompress(&compressed)?;
+    let row_length = (header.width as usize * header.bits_per_pixel()).div_ceil(8);
+    if filtered.len() < (row_length + 1) * header.height as usize {
+        return Err("image data is cut short".to_string());
+    }
+    let bytes_per_pixel = header.bits_per_pixel().div_ceil(8);
+    let rows = unfilter_scanlines(&filtered, row_length, bytes_per_pixel, header.height)?;
+
+    let mut framebuffer = Framebuffer::new(header.width, header.height);
+    let maximum = ((1u32 << header.bit_depth) - 1) as f64;
+    for y in 0..header.height {
+        let row = &rows[(y as usize * row_length)..((y as usize + 1) * row_length)];
+        for x in 0..header.width {
+            let sample = |channel: usize| {
+                read_sample(
+                    row,
+                    (x as usize * header.channels()) + channel,
+                    header.bit_depth,
+                )
+            };
+
+            let [red, green, blue] = match header.color_type {
+                0 | 4 => [sample(0); 3], // Grayscale.
+                3 => {
+                    let index = sample(0) as usize * 3;
+                    let entry = palette
+                        .get(index..(index + 3))
+                        .ok_or("palette index out of range")?;
+                    [
+                        entry[0] as u32 * 257,
+                        entry[1] as u32 * 257,
+                        entry[2] as u32 * 257,
+                    ]
+                }
+                _ => [sample(0), sample(1), sample(2)],
+            };
+            let scale = if header.color_type == 3 {
+                65535.0
+            } else {
+                maximum
+            };
+            let color = Color::new(red as f64, green as f64, blue as f64) / scale;
+            framebuffer.add_samples(x, y, undo_gamma_correction(color), 1);
+        }
+    }
+
+    Ok(framebuffer)
+}
+
+struct PngHeader {
+    bit_depth: u8,
+    color_type: u8,
+    height: u16,
+    width: u16,
+}
+
+impl PngHeader {
+    fn parse(data: &[u8]) -> Result<PngHeader, String> {
+        if data.len() != 13 {
+            return Err("invalid IHDR chunk".to_string());
+        }
+        let width = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+        let height = u32::from_be_bytes([data[4], data[5], data[6], data[7]]);
+        if width == 0 || height == 0 || width > u16::MAX as u32 || height > u16::MAX as u32 {
+            return Err(format!("unsupported image size {}x{}", width, height));
+        }
+
+        let header = PngHeader {
+            bit_depth: data[8],
+            color_type: data[9],
+            height: height as u16,
+            width: width as u16,
+        };
+        let valid_bit_depths: &[u8] = match header.color_type {
+            0 => &[1, 2, 4, 8, 16],
+            3 => &[1, 2, 4, 8],
+            2 | 4 | 6 => &[8, 16],
+            _ => return Err(format!("invalid color type {}", header.color_type)),
+        };
+        if !valid_bit_depths.contains(&header.bit_depth) {
+            return Err(format!("invalid bit depth {}", header.bit_depth));
+        }
+        if data[12] != 0 {
+            return Err("interlaced PNG files are not supported".to_string());
+        }
+
+        Ok(header)
+    }
+
+    // Number of samples (values) per pixel.
+    fn channels(&self) -> usize {
+        match self.color_type {
+            0 | 3 => 1, // Gray or palette index.
+            4 => 2,     // Gray and alpha.
+            2 => 3,     // Red, green and blue.
+            _ => 4,     // Red, green, blue and alpha.
+        }
+    }
+
+    fn bits_per_pixel(&self) -> usize {
+        self.channels() * self.bit_depth as usize
+    }
+}
+
+// Returns the type and data of the chunk at the position, and moves the position to the next
+// chunk.
+fn read_chunk<'a>(bytes: &'a [u8], position: &mut usize) -> Result<([u8; 4], &'a [u8]), String> {
+    let start = *position;
+    let header = bytes
+        .get(start..(start + 8))
+        .ok_or("PNG file is cut short")?;
+    let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let chunk_type = [header[4], header[5], header[6], header[7]];
+
+    let data = bytes
+        .get((start + 8)..(start + 8 + length))
+        .ok_or("PNG file is cut short")?;
+    let crc = bytes
+        .get((start + 8 + length)..(start + 12 + length))
+        .ok_or("PNG file is cut short")?;
+    if u32::from_be_bytes([crc[0], crc[1], crc[2], crc[3]]) != crc32(&[&chunk_type, data]) {
+        return Err(format!(
+            "corrupt {} chunk",
+            String::from_utf8_lossy(&chunk_type)
+        ));
+    }
+
+    *position = start + 12 + length;
+    Ok((chunk_type, data))
+}
+
+// The reverse of filter_scanlines(): add the predictions back, using the rows already unfiltered.
+fn unfilter_scanlines(
+    filtered: &[u8],
+    row_length: usize,
+    bytes_per_pixel: usize,
+    height: u16,
+) -> Result<Vec<u8>, String> {
+    let mut rows = vec![0u8; row_length * height as usize];
+    for y in 0..height as usize {
+        let filter = filtered[y * (row_length + 1)];
+        let start = (y * (row_length + 1)) + 1;
+        let row = &filtered[start..(start + row_length)];
+
+        for (i, byte) in row.iter().enumerate() {
+            let index = (y * row_length) + i;
+            let left = if i >= bytes_per_pixel {
+                rows[index - bytes_per_pixel]
+            } else {
+                0
+            };
+            let above = if y > 0 { rows[index - row_length] } else { 0 };
+            let above_left = if y > 0 && i >= bytes_per_pixel {
+                rows[index - row_length - bytes_per_pixel]
+            } else {
+                0
+            };
+
+            let prediction = match filter {
+                0 => 0,
+                1 => left,
+                2 => above,
+                3 => ((left as u16 + above as u16) / 2) as u8,
+                4 => paeth_predictor(left, above, above_left),
+                _ => return Err(format!("invalid filter type {}", filter)),
+            };
+            rows[index] = byte.wrapping_add(prediction);
+        }
+    }
+    Ok(rows)
+}
+
+// Samples smaller than a byte are packed, starting from the most significant bits. 16-bit samples
+// are big-endian.
+fn read_sample(row: &[u8], index: usize, bit_depth: u8) -> u32 {
+    match bit_depth {
+        16 => u16::from_be_bytes([row[index * 2], row[(index * 2) + 1]]) as u32,
+        8 => row[index] as u32,
+        _ => {
+            let bit = index * bit_depth as usize;
+            let shift = 8 - bit_depth as usize - (bit % 8);
+            ((row[bit / 8] >> shift) & ((1 << bit_depth) - 1)) as u32
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc32_matches_known_checksums() {
+        assert_eq!(crc32(&[b"123456789"]), 0xCBF43926);
+        // Every PNG file ends with the same IEND chunk, so with the same checksum.
+        assert_eq!(crc32(&[b"IEND"]), 0xAE426082);
+        // The checksum runs over the type and the data as if they were one piece.
+        assert_eq!(crc32(&[b"1234", b"56789"]), crc32(&[b"123456789"]));
+    }
+
+    #[test]
+    fn written_chunks_end_with_the_checksum_of_type_and_data() {
+        let mut output = Vec::new();
+        write_chunk(&mut output, b"tEXt", b"abc").unwrap();
+        assert_eq!(&output[..4], &3u32.to_be_bytes());
+        assert_eq!(&output[4..11], b"tEXtabc");
+        assert_eq!(&output[11..], &crc32(&[b"tEXtabc"]).to_be_bytes());
+    }
+
+    #[test]
+    fn paeth_predictor_picks_the_closest_neighbour() {
+        assert_eq!(paeth_predictor(10, 20, 10), 20);
+        assert_eq!(paeth_predictor(20, 10, 10), 20);
+        assert_eq!(paeth_predictor(10, 10, 10), 10);
+        assert_eq!(paeth_predictor(50, 60, 100), 50);
+    }
+
+    #[test]
+    fn round_trips_an_image_through_the_writer_and_reader() {
+        let (width, height) = (7, 5);
+        let mut framebuffer = Framebuffer::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let color = Color::new(x as f64 / 7.0, y as f64 / 5.0, ((x + y) % 2) as f64 * 0.5);
+                framebuffer.add_samples(x, y, color, 1);
+            }
+        }
+
+        let mut file = Vec::new();
+        PngWriter.write_image(&mut file, &framebuffer).unwrap();
+        assert!(file.starts_with(&SIGNATURE));
+        let read_back = read_png(&file).unwrap();
+
+        assert_eq!((read_back.width(), read_back.height()), (width, height));
+        for y in 0..height {
+            for x in 0..width {
+                // Only the 8-bit gamma corrected bytes are stored, so compare those.
+                assert_eq!(
+                    gamma_corrected_bytes(read_back.pixel(x, y)),
+                    gamma_corrected_bytes(framebuffer.pixel(x, y))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_files_that_are_not_png() {
+        assert_eq!(read_png(b"GIF89a").err().as_deref(), Some("not a PNG file"));
+    }
+}
@@ -1,42 +1,173 @@
+use std::io::{self, Write};
+
+use crate::image::framebuffer::Framebuffer;
+use crate::image::reader::undo_gamma_correction;
+use crate::image::writer::{gamma_corrected_bytes, ImageWriter};
 use crate::vec3::Color;
 
-fn clamp(val: f64, min: f64, max: f64) -> f64 {
-    if val < min {
-        min
-    } else if val > max {
-        max
-    } else {
-        val
+// Plain PPM (P3) is about the simplest image format there is. A text header followed by the red,
+// green and blue values of every pixel, written out as text. Easy to write, but the files are big.
+pub struct PpmWriter;
+
+impl ImageWriter for PpmWriter {
+    // Pixels are written out in rows, left to right. Rows are written out top to bottom.
+    fn write_image(&self, output: &mut dyn Write, framebuffer: &Framebuffer) -> io::Result<()> {
+        write_ppm_header(output, framebuffer.width(), framebuffer.height())?;
+        for color in framebuffer.pixels() {
+            write_ppm_color(output, color)?;
+        }
+        Ok(())
     }
 }
 
-// Before printing the color, average it out based on the number of samples per pixel and then
-// gamma correct for "gamma 2.0". Almost all image viewers assume that the image is gamma corrected
-// so we need to correct it ourselves. Here we will approximate it to gamma 2.0, which means
-// raising the color to the power of 1/gamma. Or in this case 1.0/2.0, which is just square root.
-pub fn print_ppm_color(color: Color, samples_per_pixel: u16) {
-    let scale = 1.0 / samples_per_pixel as f64;
+fn write_ppm_color(output: &mut dyn Write, color: Color) -> io::Result<()> {
+    let [red, green, blue] = gamma_corrected_bytes(color);
+    writeln!(output, "{} {} {}", red, green, blue)
+}
+
+fn write_ppm_header(output: &mut dyn Write, image_width: u16, image_height: u16) -> io::Result<()> {
+    const RGB_MAXIMUM_VALUE: u8 = 255;
+    writeln!(output, "P3")?;
+    writeln!(output, "{} {}", image_width, image_height)?;
+    writeln!(output, "{}", RGB_MAXIMUM_VALUE)
+}
+
+pub fn print_progress(progress: usize) {
+    eprintln!("\x1B[2J"); // Clear Terminal.
+    eprintln!("Tiles remaining: {}", progress);
+}
+
+// Reads a PPM file, for image textures. Both plain (P3) and the more common binary (P6) PPM are
+// supported. Their headers are the same: the magic number, width, height and maximum value,
+// separated by whitespace. Comments start with '#' and go to the end of the line.
+pub fn read_ppm(bytes: &[u8]) -> Result<Framebuffer, String> {
+    let mut reader = PpmReader { bytes, position: 0 };
 
-    let r = (color.x() * scale).sqrt();
-    let red = (clamp(r, 0.0, 0.999) * 256.0) as u16;
+    let magic_number = reader.next_token()?;
+    let is_binary = match magic_number.as_str() {
+        "P3" => false,
+        "P6" => true,
+        _ => return Err("not a PPM file (P3 or P6)".to_string()),
+    };
+    let width: u16 = reader.next_number()?;
+    let height: u16 = reader.next_number()?;
+    let maximum_value: u16 = reader.next_number()?;
+    if width == 0 || height == 0 || maximum_value == 0 {
+        return Err("invalid PPM header".to_string());
+    }
 
-    let g = (color.y() * scale).sqrt();
-    let green = (clamp(g, 0.0, 0.999) * 256.0) as u16;
+    // Binary pixels start after a single whitespace character. They take two bytes (big-endian) if
+    // the maximum value doesn't fit in one.
+    reader.position += 1;
+    let bytes_per_value = if maximum_value > 255 { 2 } else { 1 };
 
-    let b = (color.z() * scale).sqrt();
-    let blue = (clamp(b, 0.0, 0.999) * 256.0) as u16;
+    let mut framebuffer = Framebuffer::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let mut values = [0.0; 3];
+            for value in values.iter_mut() {
+                *value = if is_binary {
+                    reader.next_binary_value(bytes_per_value)?
+                } else {
+                    reader.next_number::<u16>()?
+                } as f64;
+            }
+            let color = Color::new(values[0], values[1], values[2]) / maximum_value as f64;
+            framebuffer.add_samples(x, y, undo_gamma_correction(color), 1);
+        }
+    }
 
-    println!("{} {} {}", red, green, blue);
+    Ok(framebuffer)
 }
 
-pub fn print_ppm_header(image_width: u16, image_height: u16) {
-    const RGB_MAXIMUM_VALUE: u8 = 255;
-    println!("P3");
-    println!("{} {}", image_width, image_height);
-    println!("{}", RGB_MAXIMUM_VALUE);
+struct PpmReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
 }
 
-pub fn print_progress(progress: u16) {
-    eprintln!("\x1B[2J"); // Clear Terminal.
-    eprintln!("Scanlines remaining: {}", progress);
+impl PpmReader<'_> {
+    // Skips whitespace and comments, then reads everything up to the next whitespace.
+    fn next_token(&mut self) -> Result<String, String> {
+        loop {
+            match self.bytes.get(self.position) {
+                Some(b'#') => {
+                    while !matches!(self.bytes.get(self.position), Some(b'\n') | None) {
+                        self.position += 1;
+                    }
+                }
+                Some(byte) if byte.is_ascii_whitespace() => self.position += 1,
+                Some(_) => break,
+                None => return Err("PPM file is cut short".to_string()),
+            }
+        }
+
+        let start = self.position;
+        while matches!(self.bytes.get(self.position), Some(byte) if !byte.is_ascii_whitespace()) {
+            self.position += 1;
+        }
+        Ok(String::from_utf8_lossy(&self.bytes[start..self.position]).into_owned())
+    }
+
+    fn next_number<T: std::str::FromStr>(&mut self) -> Result<T, String> {
+        let token = self.next_token()?;
+        token
+            .parse()
+            .map_err(|_| format!("invalid number \"{}\" in PPM file", token))
+    }
+
+    fn next_binary_value(&mut self, bytes_per_value: usize) -> Result<u16, String> {
+        let bytes = self
+            .bytes
+            .get(self.position..(self.position + bytes_per_value))
+            .ok_or("PPM file is cut short")?;
+        self.position += bytes_per_value;
+        Ok(match bytes {
+            [byte] => *byte as u16,
+            _ => u16::from_be_bytes([bytes[0], bytes[1]]),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_an_image_through_the_writer_and_reader() {
+        let mut framebuffer = Framebuffer::new(3, 2);
+        framebuffer.add_samples(0, 0, Color::new(0.25, 0.5, 1.0), 1);
+        framebuffer.add_samples(2, 1, Color::new(3.0, 0.0, 0.1), 2);
+
+        let mut file = Vec::new();
+        PpmWriter.write_image(&mut file, &framebuffer).unwrap();
+        assert!(file.starts_with(b"P3\n3 2\n255\n"));
+        let read_back = read_ppm(&file).unwrap();
+
+        assert_eq!((read_back.width(), read_back.height()), (3, 2));
+        for (read, written) in read_back.pixels().into_iter().zip(framebuffer.pixels()) {
+            // Only the 8-bit gamma corrected values are stored, so compare those.
+            assert_eq!(gamma_corrected_bytes(read), gamma_corrected_bytes(written));
+        }
+    }
+
+    #[test]
+    fn reads_binary_pixels_with_two_bytes_per_value_and_comments() {
+        let mut file = b"P6 # Made by hand.\n2 1\n# Two bytes per value:\n65535\n".to_vec();
+        file.extend_from_slice(&[0xFF, 0xFF, 0x00, 0x00, 0xFF, 0xFF]);
+        file.extend_from_slice(&[0x00, 0x00, 0xFF, 0xFF, 0x00, 0x00]);
+
+        let framebuffer = read_ppm(&file).unwrap();
+        let [first, second] = [framebuffer.pixel(0, 0), framebuffer.pixel(1, 0)];
+        assert_eq!([first.x(), first.y(), first.z()], [1.0, 0.0, 1.0]);
+        assert_eq!([second.x(), second.y(), second.z()], [0.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn rejects_invalid_files() {
+        let error = |file: &[u8]| read_ppm(file).err().unwrap();
+        assert_eq!(error(b"P5 1 1 255\n\0"), "not a PPM file (P3 or P6)");
+        assert_eq!(error(b"P3 1 1 0\n"), "invalid PPM header");
+        assert_eq!(error(b"P3 1 x 255\n"), "invalid number \"x\" in PPM file");
+        assert_eq!(error(b"P6 1 1 255\n\xFF\xFF"), "PPM file is cut short");
+    }
 }
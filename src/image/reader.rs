@@ -0,0 +1,38 @@
+use std::fs;
+use std::path::Path;
+
+use crate::image::framebuffer::Framebuffer;
+use crate::image::hdr::read_hdr;
+use crate::image::pfm::read_pfm;
+use crate::image::png::read_png;
+use crate::image::ppm::read_ppm;
+use crate::vec3::Color;
+
+// Reads an image file (for textures and environment maps) into a framebuffer, with one sample per
+// pixel. The format follows from the file extension, like when writing. PNG and PPM images are
+// gamma corrected, HDR and PFM images are already linear.
+pub fn read_image(path: &Path) -> Result<Framebuffer, String> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase());
+    let bytes = fs::read(path).map_err(|error| error.to_string())?;
+
+    match extension.as_deref() {
+        Some("hdr" | "pic") => read_hdr(&bytes),
+        Some("pfm") => read_pfm(&bytes),
+        Some("png") => read_png(&bytes),
+        Some("ppm") => read_ppm(&bytes),
+        _ => Err(format!(
+            "unknown image format for {}, use a .hdr, .pfm, .png or .ppm file extension",
+            path.display()
+        )),
+    }
+}
+
+// Image files are gamma corrected (see gamma_corrected_bytes()), but the renderer works with
+// linear colors. Undo it, again approximating with gamma 2.0, so a color that is read and written
+// back stays the same.
+pub fn undo_gamma_correction(color: Color) -> Color {
+    color * color
+}
@@ -1,50 +1,208 @@
-use rand::{thread_rng, Rng};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
 
 use crate::config::Config;
-use crate::image::ppm::{print_ppm_color, print_ppm_header, print_progress};
+use crate::image::framebuffer::Framebuffer;
+use crate::image::ppm::print_progress;
+use crate::sampler::Sampler;
+use crate::scene::medium::medium::Medium;
 use crate::scene::object::object::Object;
 use crate::scene::object::world::World;
 use crate::tracer::camera::Camera;
 use crate::tracer::tracer::trace_ray_in_world;
 use crate::vec3::Color;
 
-pub fn render_image<T: Object>(config: Config, world: World<T>) {
-    // Setup camera.
-    let camera = Camera::new(
-        config.camera_look_from,
-        config.camera_look_at,
-        config.camera_vertical_field_of_view,
-        config.aspect_ratio,
-        config.camera_aperture,
-        config.camera_focus_distance,
-    );
+const TILE_SIZE: u16 = 16; // Width and height of a tile, in pixels.
 
+// A rectangular part of the image. Tiles at the right and top edges can be smaller than TILE_SIZE.
+#[derive(Clone, Copy)]
+struct Tile {
+    height: u16,
+    width: u16,
+    x: u16, // Left-most pixel column.
+    y: u16, // Bottom-most pixel row. Rows are counted from the bottom of the image.
+}
+
+// Renders the world, as seen by the camera, into a framebuffer. Writing the image to a file is a
+// separate step, see ImageWriter.
+pub fn render_image<T: Object>(config: Config, camera: Camera, world: World<T>) -> Framebuffer {
     // Setup renderer.
-    const RAY_BOUNCE_LIMIT: u8 = 50; // Limit the number of times a ray can bounce off objects.
-    let mut rng = thread_rng();
-    let image_height = (config.image_width as f64 / config.aspect_ratio) as u16;
-
-    // Pixels are written out in rows, left to right. Rows are written out top to bottom.
-    print_ppm_header(config.image_width, image_height);
-    for h in (0..image_height).rev() {
-        print_progress(h);
-        for w in 0..config.image_width {
+    let image_height = config.image_height();
+    let tiles = split_into_tiles(config.image_width, image_height);
+
+    let mut framebuffer = Framebuffer::new(config.image_width, image_height);
+
+    // A pool of worker threads renders the tiles. Every worker takes the next tile that nobody has
+    // started on, until there are none left. This balances the work, since some tiles (with glass,
+    // for example) take longer than others. Finished tiles are sent back to this thread, which puts
+    // them in the framebuffer. Where a pixel ends up only depends on its tile, not on the thread that
+    // rendered it.
+    //
+    // RUST: A scoped thread can borrow from the function that spawned it (the camera, the world,
+    // etc.) since the scope guarantees that all threads have finished before it returns.
+    let next_tile = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        for _ in 0..config.threads.max(1) {
+            // RUST: Every thread needs its own sender. The original is dropped below.
+            let sender = sender.clone();
+            let (camera, config, next_tile, tiles, world) =
+                (&camera, &config, &next_tile, &tiles, &world);
+
+            scope.spawn(move || loop {
+                // RUST: fetch_add() increments and returns the previous value, atomically. So no two
+                // threads can get the same tile.
+                let tile_index = next_tile.fetch_add(1, Ordering::Relaxed);
+                let Some(tile) = tiles.get(tile_index) else {
+                    break;
+                };
+
+                let colors = render_tile(*tile, image_height, config, camera, world);
+                sender.send((*tile, colors)).unwrap();
+            });
+        }
+        // Otherwise the receiver would wait forever, since there'd always be a sender left.
+        drop(sender);
+
+        let mut tiles_remaining = tiles.len();
+        for (tile, colors) in receiver {
+            for row in 0..tile.height {
+                for column in 0..tile.width {
+                    let h = tile.y + row;
+                    let w = tile.x + column;
+                    // The framebuffer counts rows from the top, tiles from the bottom.
+                    framebuffer.add_samples(
+                        w,
+                        image_height - 1 - h,
+                        colors[(row as usize * tile.width as usize) + column as usize],
+                        config.samples_per_pixel as u32,
+                    );
+                }
+            }
+
+            tiles_remaining -= 1;
+            print_progress(tiles_remaining);
+        }
+    });
+
+    eprintln!("Voila!");
+    framebuffer
+}
+
+fn split_into_tiles(image_width: u16, image_height: u16) -> Vec<Tile> {
+    let mut tiles = Vec::new();
+    for y in (0..image_height).step_by(TILE_SIZE as usize).rev() {
+        for x in (0..image_width).step_by(TILE_SIZE as usize) {
+            tiles.push(Tile {
+                height: TILE_SIZE.min(image_height - y),
+                width: TILE_SIZE.min(image_width - x),
+                x,
+                y,
+            });
+        }
+    }
+    tiles
+}
+
+// Returns the colors of the pixels in the tile, row by row from the bottom.
+fn render_tile<T: Object>(
+    tile: Tile,
+    image_height: u16,
+    config: &Config,
+    camera: &Camera,
+    world: &World<T>,
+) -> Vec<Color> {
+    let mut colors = Vec::with_capacity(tile.width as usize * tile.height as usize);
+
+    for h in tile.y..(tile.y + tile.height) {
+        for w in tile.x..(tile.x + tile.width) {
+            let mut sampler = Sampler::for_pixel(config.seed, w, h);
+
             // Multisample anti-aliasing (MSAA). Traverse multiple random samples, using rays, inside a
             // pixel. Color the samples and average them out for a more representative shaded color.
             let mut color = Color::zero();
             for _ in 0..config.samples_per_pixel {
                 // Use two offset vectors (u,v) to shoot the rays randomly at the pixel.
-                let u = (w as f64 + rng.gen_range(0.0..1.0)) / (config.image_width - 1) as f64;
-                let v = (h as f64 + rng.gen_range(0.0..1.0)) / (image_height - 1) as f64;
+                let u = (w as f64 + sampler.next_f64()) / (config.image_width - 1) as f64;
+                let v = (h as f64 + sampler.next_f64()) / (image_height - 1) as f64;
 
-                let ray = camera.shoot_ray_at(u, v);
+                let ray = camera.shoot_ray_at(u, v, &mut sampler);
 
-                color = color + trace_ray_in_world(&ray, &world, RAY_BOUNCE_LIMIT);
+                color = color
+                    + trace_ray_in_world(
+                        &ray,
+                        world,
+                        &config.lights,
+                        &config.background,
+                        config.fog.as_ref().map(|fog| fog as &dyn Medium),
+                        config.maximum_ray_bounce_depth,
+                        &mut sampler,
+                    );
             }
 
-            print_ppm_color(color, config.samples_per_pixel);
+            colors.push(color);
         }
     }
 
-    eprintln!("Voila!");
+    colors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::generator::random_spheres;
+
+    // A small render of the random spheres scene, which has most kinds of materials.
+    fn render_spheres(seed: u64, threads: usize) -> Vec<[u64; 3]> {
+        let config = Config {
+            image_width: 48, // And 32 high, so six tiles.
+            maximum_ray_bounce_depth: 8,
+            samples_per_pixel: 4,
+            seed,
+            threads,
+            ..Config::default()
+        };
+        let world = World::new(random_spheres(&mut Sampler::new(seed)));
+        let camera = config.camera();
+
+        // RUST: Compare the exact bits. Floating point numbers that are NaN aren't equal to
+        // themselves, and the point is that not a single bit changes.
+        render_image(config, camera, world)
+            .pixels()
+            .into_iter()
+            .map(|color| {
+                [
+                    color.x().to_bits(),
+                    color.y().to_bits(),
+                    color.z().to_bits(),
+                ]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_renders_the_same_image_with_any_number_of_threads() {
+        let image = render_spheres(42, 1);
+        assert_eq!(image.len(), 48 * 32);
+        assert_eq!(render_spheres(42, 1), image);
+        assert_eq!(render_spheres(42, 3), image);
+        assert_ne!(render_spheres(43, 3), image);
+    }
+
+    #[test]
+    fn tiles_cover_every_pixel_once() {
+        let tiles = split_into_tiles(40, 20);
+        assert_eq!(tiles.len(), 3 * 2);
+
+        let mut covered = vec![0; 40 * 20];
+        for tile in tiles {
+            for y in tile.y..(tile.y + tile.height) {
+                for x in tile.x..(tile.x + tile.width) {
+                    covered[(y as usize * 40) + x as usize] += 1;
+                }
+            }
+        }
+        assert!(covered.iter().all(|count| *count == 1));
+    }
 }
@@ -0,0 +1,56 @@
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::image::framebuffer::Framebuffer;
+use crate::image::png::PngWriter;
+use crate::image::ppm::PpmWriter;
+use crate::vec3::Color;
+
+// Writes a rendered image in some file format.
+pub trait ImageWriter {
+    fn write_image(&self, output: &mut dyn Write, framebuffer: &Framebuffer) -> io::Result<()>;
+}
+
+// Pick the image format from the file extension of the path.
+pub fn image_writer_for_path(path: &Path) -> Result<Box<dyn ImageWriter>, String> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase());
+
+    match extension.as_deref() {
+        Some("png") => Ok(Box::new(PngWriter)),
+        Some("ppm") => Ok(Box::new(PpmWriter)),
+        _ => Err(format!(
+            "unknown image format for {}, use a .png or .ppm file extension",
+            path.display()
+        )),
+    }
+}
+
+fn clamp(val: f64, min: f64, max: f64) -> f64 {
+    if val < min {
+        min
+    } else if val > max {
+        max
+    } else {
+        val
+    }
+}
+
+// Before writing the (averaged) color of a pixel, gamma correct it for "gamma 2.0". Almost all image viewers assume that
+// the image is gamma corrected so we need to correct it ourselves. Here we will approximate it to
+// gamma 2.0, which means raising the color to the power of 1/gamma. Or in this case 1.0/2.0, which
+// is just square root. Then scale it to a byte (0 to 255). Anything brighter than 1.0 is clamped.
+pub fn gamma_corrected_bytes(color: Color) -> [u8; 3] {
+    let r = color.x().sqrt();
+    let red = (clamp(r, 0.0, 0.999) * 256.0) as u8;
+
+    let g = color.y().sqrt();
+    let green = (clamp(g, 0.0, 0.999) * 256.0) as u8;
+
+    let b = color.z().sqrt();
+    let blue = (clamp(b, 0.0, 0.999) * 256.0) as u8;
+
+    [red, green, blue]
+}
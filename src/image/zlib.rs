@@ -0,0 +1,585 @@
+// Zlib is the compressed data format used inside PNG files. It's a small header, a stream of data
+// compressed with the DEFLATE algorithm, and a checksum (Adler-32) of the uncompressed data.
+//
+// DEFLATE combines two ideas:
+// - LZ77: Repeated byte sequences are replaced by a reference (length, distance) to an earlier copy
+//   of the same bytes. Images have lots of repetition (think of a row of sky), so this is where most
+//   of the compression comes from.
+// - Huffman coding: Common symbols get shorter bit codes than rare ones. Here we use the "fixed"
+//   Huffman codes that are predefined by the DEFLATE specification (RFC 1951), instead of building
+//   our own for every block. It compresses slightly worse but is a lot simpler.
+
+const WINDOW_SIZE: usize = 32768; // How far back a match can be.
+const MINIMUM_MATCH: usize = 3;
+const MAXIMUM_MATCH: usize = 258;
+const MAXIMUM_CHAIN: usize = 64; // How many earlier positions to try when searching for a match.
+const HASH_BITS: u32 = 15;
+
+// Length symbols 257 to 285. The length is the base plus an extra number stored in the extra bits.
+const LENGTH_BASES: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA_BITS: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+// Distance symbols 0 to 29. Same idea as the lengths.
+const DISTANCE_BASES: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA_BITS: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut output = BitWriter::new();
+
+    // Header: compression method 8 (DEFLATE) with a 32K window, and no preset dictionary. The check
+    // bits make the two header bytes, as a 16-bit number, divisible by 31.
+    output.bytes.push(0x78);
+    output.bytes.push(0x01);
+
+    // A single block, the last one (1), using the fixed Huffman codes (01).
+    output.write_bits(1, 1);
+    output.write_bits(0b01, 2);
+
+    let mut matcher = Matcher::new(data);
+    let mut position = 0;
+    while position < data.len() {
+        let (length, distance) = matcher.find_longest_match(position);
+
+        if length >= MINIMUM_MATCH {
+            output.write_length(length);
+            output.write_distance(distance);
+            for skipped in position..(position + length) {
+                matcher.insert(skipped);
+            }
+            position += length;
+        } else {
+            output.write_literal(data[position]);
+            matcher.insert(position);
+            position += 1;
+        }
+    }
+
+    output.write_symbol(256); // End of block.
+    output.flush();
+
+    let checksum = adler32(data);
+    output.bytes.extend_from_slice(&checksum.to_be_bytes());
+    output.bytes
+}
+
+// Finds earlier copies of the bytes at a position. It keeps the most recent position of every hash
+// of three bytes, and for every position the previous position with the same hash. Together these
+// form "hash chains" of candidate matches.
+struct Matcher<'a> {
+    data: &'a [u8],
+    head: Vec<usize>,
+    previous: Vec<usize>,
+}
+
+impl<'a> Matcher<'a> {
+    fn new(data: &'a [u8]) -> Matcher<'a> {
+        Matcher {
+            data,
+            head: vec![usize::MAX; 1 << HASH_BITS],
+            previous: vec![usize::MAX; data.len()],
+        }
+    }
+
+    fn hash_at(&self, position: usize) -> usize {
+        let value = ((self.data[position] as u32) << 16)
+            | ((self.data[position + 1] as u32) << 8)
+            | self.data[position + 2] as u32;
+        (value.wrapping_mul(2654435761) >> (32 - HASH_BITS)) as usize
+    }
+
+    fn insert(&mut self, position: usize) {
+        if position + MINIMUM_MATCH <= self.data.len() {
+            let hash = self.hash_at(position);
+            self.previous[position] = self.head[hash];
+            self.head[hash] = position;
+        }
+    }
+
+    // Follow the hash chain of the position and return the longest earlier match (length,
+    // distance).
+    fn find_longest_match(&self, position: usize) -> (usize, usize) {
+        if position + MINIMUM_MATCH > self.data.len() {
+            return (0, 0);
+        }
+
+        let maximum_length = MAXIMUM_MATCH.min(self.data.len() - position);
+        let mut best = (0, 0);
+        let mut candidate = self.head[self.hash_at(position)];
+        let mut chain = 0;
+
+        while candidate != usize::MAX
+            && position - candidate <= WINDOW_SIZE
+            && chain < MAXIMUM_CHAIN
+        {
+            let length = self.data[candidate..]
+                .iter()
+                .zip(&self.data[position..(position + maximum_length)])
+                .take_while(|(a, b)| a == b)
+                .count();
+            if length > best.0 {
+                best = (length, position - candidate);
+                if length == maximum_length {
+                    break;
+                }
+            }
+
+            candidate = self.previous[candidate];
+            chain += 1;
+        }
+
+        best
+    }
+}
+
+// Adler-32 is a simple checksum: two running sums, modulo the largest prime below 2^16.
+fn adler32(data: &[u8]) -> u32 {
+    const MODULO: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    // The sums can't overflow a u32 in 5552 bytes, so the (slow) modulo is only needed once per chunk.
+    for chunk in data.chunks(5552) {
+        for byte in chunk {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= MODULO;
+        b %= MODULO;
+    }
+    (b << 16) | a
+}
+
+// DEFLATE packs bits starting from the least significant bit of every byte.
+struct BitWriter {
+    bit_buffer: u64,
+    bit_count: u32,
+    bytes: Vec<u8>,
+}
+
+impl BitWriter {
+    fn new() -> BitWriter {
+        BitWriter {
+            bit_buffer: 0,
+            bit_count: 0,
+            bytes: Vec::new(),
+        }
+    }
+
+    fn write_bits(&mut self, value: u32, count: u32) {
+        self.bit_buffer |= (value as u64) << self.bit_count;
+        self.bit_count += count;
+        while self.bit_count >= 8 {
+            self.bytes.push(self.bit_buffer as u8);
+            self.bit_buffer >>= 8;
+            self.bit_count -= 8;
+        }
+    }
+
+    // Huffman codes are defined starting from the most significant bit, so they are reversed.
+    fn write_code(&mut self, code: u32, length: u32) {
+        let reversed = code.reverse_bits() >> (32 - length);
+        self.write_bits(reversed, length);
+    }
+
+    // The fixed Huffman code of a literal/length symbol (0 to 287).
+    fn write_symbol(&mut self, symbol: u16) {
+        let symbol = symbol as u32;
+        match symbol {
+            0..=143 => self.write_code(0b00110000 + symbol, 8),
+            144..=255 => self.write_code(0b110010000 + (symbol - 144), 9),
+            256..=279 => self.write_code(symbol - 256, 7),
+            _ => self.write_code(0b11000000 + (symbol - 280), 8),
+        }
+    }
+
+    fn write_literal(&mut self, byte: u8) {
+        self.write_symbol(byte as u16);
+    }
+
+    fn write_length(&mut self, length: usize) {
+        let index = LENGTH_BASES
+            .iter()
+            .rposition(|base| *base as usize <= length)
+            .unwrap();
+        self.write_symbol(257 + index as u16);
+        self.write_bits(
+            (length - LENGTH_BASES[index] as usize) as u32,
+            LENGTH_EXTRA_BITS[index] as u32,
+        );
+    }
+
+    // Distance codes are all five bits long.
+    fn write_distance(&mut self, distance: usize) {
+        let index = DISTANCE_BASES
+            .iter()
+            .rposition(|base| *base as usize <= distance)
+            .unwrap();
+        self.write_code(index as u32, 5);
+        self.write_bits(
+            (distance - DISTANCE_BASES[index] as usize) as u32,
+            DISTANCE_EXTRA_BITS[index] as u32,
+        );
+    }
+
+    // Pad the last byte with zeros.
+    fn flush(&mut self) {
+        if self.bit_count > 0 {
+            self.bytes.push(self.bit_buffer as u8);
+            self.bit_buffer = 0;
+            self.bit_count = 0;
+        }
+    }
+}
+
+// Decompression ("inflate") is needed to read PNG files. Unlike compression, it has to handle
+// everything DEFLATE allows, since other programs pick whichever block types suit them:
+// - Stored blocks: Uncompressed bytes.
+// - Fixed Huffman blocks: Like the ones we write.
+// - Dynamic Huffman blocks: The block starts with its own Huffman codes, which are themselves
+//   Huffman coded.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, String> {
+    if data.len() < 6 {
+        return Err("zlib stream is too short".to_string());
+    }
+    if data[0] & 0x0F != 8 || (((data[0] as u16) << 8) | data[1] as u16) % 31 != 0 {
+        return Err("invalid zlib header".to_string());
+    }
+    if data[1] & 0x20 != 0 {
+        return Err("zlib preset dictionaries are not supported".to_string());
+    }
+
+    let mut input = BitReader::new(&data[2..]);
+    let mut output = Vec::new();
+    loop {
+        let is_last_block = input.read_bits(1)? == 1;
+        match input.read_bits(2)? {
+            0 => inflate_stored_block(&mut input, &mut output)?,
+            1 => {
+                let (literals, distances) = fixed_huffman_codes();
+                inflate_huffman_block(&mut input, &mut output, &literals, &distances)?
+            }
+            2 => {
+                let (literals, distances) = read_dynamic_huffman_codes(&mut input)?;
+                inflate_huffman_block(&mut input, &mut output, &literals, &distances)?
+            }
+            _ => return Err("invalid DEFLATE block type".to_string()),
+        }
+        if is_last_block {
+            break;
+        }
+    }
+
+    // The checksum follows the last block, starting at the next whole byte.
+    let checksum_position = 2 + input.position_after_byte_alignment();
+    let checksum = data
+        .get(checksum_position..(checksum_position + 4))
+        .ok_or("zlib stream is missing its checksum")?;
+    if u32::from_be_bytes([checksum[0], checksum[1], checksum[2], checksum[3]]) != adler32(&output)
+    {
+        return Err("zlib checksum doesn't match the data".to_string());
+    }
+
+    Ok(output)
+}
+
+fn inflate_stored_block(input: &mut BitReader, output: &mut Vec<u8>) -> Result<(), String> {
+    let start = input.position_after_byte_alignment();
+    let bytes = input.bytes;
+    let header = bytes
+        .get(start..(start + 4))
+        .ok_or("stored block is cut short")?;
+    let length = u16::from_le_bytes([header[0], header[1]]);
+    let length_complement = u16::from_le_bytes([header[2], header[3]]);
+    if length != !length_complement {
+        return Err("stored block length is corrupt".to_string());
+    }
+
+    let data_start = start + 4;
+    let data = bytes
+        .get(data_start..(data_start + length as usize))
+        .ok_or("stored block is cut short")?;
+    output.extend_from_slice(data);
+    input.skip_to_byte(data_start + length as usize);
+    Ok(())
+}
+
+fn inflate_huffman_block(
+    input: &mut BitReader,
+    output: &mut Vec<u8>,
+    literals: &HuffmanDecoder,
+    distances: &HuffmanDecoder,
+) -> Result<(), String> {
+    loop {
+        let symbol = literals.decode(input)?;
+        match symbol {
+            0..=255 => output.push(symbol as u8),
+            256 => return Ok(()), // End of block.
+            _ => {
+                let index = (symbol - 257) as usize;
+                if index >= LENGTH_BASES.len() {
+                    return Err("invalid length symbol".to_string());
+                }
+                let length = LENGTH_BASES[index] as usize
+                    + input.read_bits(LENGTH_EXTRA_BITS[index] as u32)? as usize;
+
+                let index = distances.decode(input)? as usize;
+                if index >= DISTANCE_BASES.len() {
+                    return Err("invalid distance symbol".to_string());
+                }
+                let distance = DISTANCE_BASES[index] as usize
+                    + input.read_bits(DISTANCE_EXTRA_BITS[index] as u32)? as usize;
+                if distance > output.len() {
+                    return Err("distance reaches back before the start of the data".to_string());
+                }
+
+                // RUST: The copy can overlap what it's writing (distance < length), so it's
+                // done a byte at a time.
+                let start = output.len() - distance;
+                for i in 0..length {
+                    output.push(output[start + i]);
+                }
+            }
+        }
+    }
+}
+
+fn fixed_huffman_codes() -> (HuffmanDecoder, HuffmanDecoder) {
+    let mut literal_lengths = [0u8; 288];
+    for (symbol, length) in literal_lengths.iter_mut().enumerate() {
+        *length = match symbol {
+            0..=143 => 8,
+            144..=255 => 9,
+            256..=279 => 7,
+            _ => 8,
+        };
+    }
+    (
+        HuffmanDecoder::new(&literal_lengths),
+        HuffmanDecoder::new(&[5; 30]),
+    )
+}
+
+// A dynamic block lists the code length of every symbol. Those lengths are compressed too: with a
+// small Huffman code of their own, and run lengths for repeated lengths.
+fn read_dynamic_huffman_codes(
+    input: &mut BitReader,
+) -> Result<(HuffmanDecoder, HuffmanDecoder), String> {
+    // The code lengths of the code length code are stored in this (odd) order.
+    const ORDER: [usize; 19] = [
+        16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+    ];
+
+    let literal_count = input.read_bits(5)? as usize + 257;
+    let distance_count = input.read_bits(5)? as usize + 1;
+    let code_length_count = input.read_bits(4)? as usize + 4;
+
+    let mut code_length_lengths = [0u8; 19];
+    for symbol in ORDER.iter().take(code_length_count) {
+        code_length_lengths[*symbol] = input.read_bits(3)? as u8;
+    }
+    let code_length_decoder = HuffmanDecoder::new(&code_length_lengths);
+
+    let mut lengths = Vec::with_capacity(literal_count + distance_count);
+    while lengths.len() < literal_count + distance_count {
+        let (length, repeat) = match code_length_decoder.decode(input)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths.last().ok_or("nothing to repeat")?;
+                (previous, 3 + input.read_bits(2)?)
+            }
+            17 => (0, 3 + input.read_bits(3)?),
+            _ => (0, 11 + input.read_bits(7)?),
+        };
+        lengths.extend(std::iter::repeat(length).take(repeat as usize));
+    }
+    if lengths.len() > literal_count + distance_count {
+        return Err("code lengths overflow the block header".to_string());
+    }
+
+    Ok((
+        HuffmanDecoder::new(&lengths[..literal_count]),
+        HuffmanDecoder::new(&lengths[literal_count..]),
+    ))
+}
+
+// Decodes a "canonical" Huffman code, which DEFLATE describes by code lengths alone. Codes of the
+// same length are consecutive numbers, in symbol order, and shorter codes come first. So it's
+// enough to know how many codes there are of every length, and the symbols sorted by code.
+struct HuffmanDecoder {
+    counts: [u16; 16], // Number of codes of every length (1 to 15 bits).
+    symbols: Vec<u16>, // Symbols ordered by their code.
+}
+
+impl HuffmanDecoder {
+    fn new(lengths: &[u8]) -> HuffmanDecoder {
+        let mut counts = [0u16; 16];
+        for length in lengths {
+            counts[*length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut symbols = Vec::with_capacity(lengths.len());
+        for length in 1..16 {
+            for (symbol, symbol_length) in lengths.iter().enumerate() {
+                if *symbol_length as usize == length {
+                    symbols.push(symbol as u16);
+                }
+            }
+        }
+
+        HuffmanDecoder { counts, symbols }
+    }
+
+    // Read a bit at a time, until the code read so far is one of the codes of that length.
+    fn decode(&self, input: &mut BitReader) -> Result<u16, String> {
+        let mut code = 0i32; // The code read so far.
+        let mut first = 0i32; // The first code of the current length.
+        let mut index = 0i32; // Index of the first symbol of the current length.
+        for length in 1..16 {
+            code |= input.read_bits(1)? as i32;
+            let count = self.counts[length] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("invalid Huffman code".to_string())
+    }
+}
+
+// Reads bits starting from the least significant bit of every byte, the same order BitWriter
+// writes them in.
+struct BitReader<'a> {
+    bit_position: usize,
+    bytes: &'a [u8],
+}
+
+impl<'a> BitReader<'a> {
+    fn new(bytes: &'a [u8]) -> BitReader<'a> {
+        BitReader {
+            bit_position: 0,
+            bytes,
+        }
+    }
+
+    fn read_bits(&mut self, count: u32) -> Result<u32, String> {
+        let mut value = 0;
+        for i in 0..count {
+            let byte = self
+                .bytes
+                .get(self.bit_position / 8)
+                .ok_or("compressed data is cut short")?;
+            let bit = (byte >> (self.bit_position % 8)) & 1;
+            value |= (bit as u32) << i;
+            self.bit_position += 1;
+        }
+        Ok(value)
+    }
+
+    // Index of the byte after the current one, if any bits of it have been read.
+    fn position_after_byte_alignment(&self) -> usize {
+        self.bit_position.div_ceil(8)
+    }
+
+    fn skip_to_byte(&mut self, position: usize) {
+        self.bit_position = position * 8;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampler::Sampler;
+
+    fn assert_round_trip(data: &[u8]) {
+        let compressed = compress(data);
+        assert_eq!(decompress(&compressed).unwrap(), data);
+    }
+
+    #[test]
+    fn round_trips_empty_and_tiny_data() {
+        assert_round_trip(&[]);
+        assert_round_trip(b"a");
+        assert_round_trip(b"abc");
+    }
+
+    #[test]
+    fn round_trips_repetitive_data_and_compresses_it() {
+        // Long runs use the longest match (258) and a distance of 1, overlapping the copy.
+        let data: Vec<u8> = b"sky ".repeat(5000).into_iter().chain([7; 1000]).collect();
+        let compressed = compress(&data);
+        assert!(compressed.len() < data.len() / 20);
+        assert_eq!(decompress(&compressed).unwrap(), data);
+    }
+
+    #[test]
+    fn round_trips_random_data_longer_than_the_window() {
+        let mut sampler = Sampler::new(7);
+        let data: Vec<u8> = (0..(WINDOW_SIZE * 3))
+            .map(|_| sampler.index(256) as u8)
+            .collect();
+        assert_round_trip(&data);
+    }
+
+    #[test]
+    fn inflates_a_stored_block() {
+        let mut stream = vec![0x78, 0x01];
+        stream.push(0b001); // The last block, stored (00). The rest of the byte is padding.
+        stream.extend_from_slice(&5u16.to_le_bytes());
+        stream.extend_from_slice(&(!5u16).to_le_bytes());
+        stream.extend_from_slice(b"hello");
+        stream.extend_from_slice(&adler32(b"hello").to_be_bytes());
+        assert_eq!(decompress(&stream).unwrap(), b"hello");
+    }
+
+    // Written by zlib itself (level 9), which picked a dynamic Huffman block for it.
+    #[test]
+    fn inflates_a_dynamic_huffman_block() {
+        let stream = [
+            0x78, 0xDA, 0xED, 0xCA, 0xB1, 0x11, 0x00, 0x30, 0x08, 0x02, 0xC0, 0x59, 0xF1, 0x50,
+            0x51, 0xF6, 0xEF, 0xAD, 0xB2, 0x45, 0xBE, 0x7E, 0xA8, 0x97, 0x88, 0xF4, 0x9A, 0x70,
+            0x28, 0x73, 0x18, 0x14, 0x59, 0x9E, 0x81, 0xAA, 0xF1, 0xC3, 0x0B, 0x07, 0x47, 0x98,
+            0x77, 0x87,
+        ];
+        let expected: Vec<u8> = (0..300u32)
+            .map(|i| (((i * i * 7) + (i / 3)) % 11 + 97) as u8)
+            .collect();
+        assert_eq!(decompress(&stream).unwrap(), expected);
+    }
+
+    #[test]
+    fn rejects_damaged_streams() {
+        let mut compressed = compress(b"some pixels, some pixels");
+        assert_eq!(
+            decompress(&compressed[..4]).unwrap_err(),
+            "zlib stream is too short"
+        );
+
+        let last = compressed.len() - 1;
+        compressed[last] ^= 1;
+        assert_eq!(
+            decompress(&compressed).unwrap_err(),
+            "zlib checksum doesn't match the data"
+        );
+
+        compressed[1] ^= 1;
+        assert_eq!(decompress(&compressed).unwrap_err(), "invalid zlib header");
+    }
+
+    #[test]
+    fn adler32_matches_known_checksums() {
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E60398);
+    }
+}
@@ -0,0 +1,9 @@
+// The ray tracer is a library, so that scenes can be built and rendered from other programs too.
+// main.rs is one such program.
+pub mod config;
+pub mod image;
+pub mod matrix4;
+pub mod sampler;
+pub mod scene;
+pub mod tracer;
+pub mod vec3;
@@ -1,27 +1,91 @@
-mod config;
-mod image;
-mod scene;
-mod tracer;
-mod vec3;
+mod cli;
 
-use crate::config::Config;
-use crate::image::render::render_image;
-use crate::scene::generator::random_spheres;
-use crate::scene::object::world::World;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::process;
+
+use rayman::config::Config;
+use rayman::image::ppm::PpmWriter;
+use rayman::image::render::render_image;
+use rayman::image::writer::{image_writer_for_path, ImageWriter};
+use rayman::sampler::Sampler;
+use rayman::scene::generator::random_spheres;
+use rayman::scene::loader::load_scene;
+use rayman::scene::object::world::World;
+
+use crate::cli::{parse_arguments, Command, HELP};
 
 fn main() {
-    // Configure ray tracer.
-    let config = Config::default();
-    // let config = Config {
-    //   image_width: 300,
-    //   samples_per_pixel: 100,
-    //   ..Config::default()
-    // }; // For faster rendering.
-
-    // Setup world.
-    let spheres = random_spheres();
-    let world = World::new(spheres);
+    // Configure ray tracer. Run with --help to see all options.
+    let arguments = match parse_arguments(env::args().skip(1)) {
+        Ok(Command::Help) => {
+            print!("{}", HELP);
+            return;
+        }
+        Ok(Command::Render(arguments)) => arguments,
+        Err(message) => exit_with_error(&message),
+    };
+
+    // Setup world. Render the scene file, if there is one. Otherwise render randomly generated
+    // spheres.
+    let (mut config, world) = match &arguments.scene {
+        Some(path) => {
+            let scene = load_scene(path).unwrap_or_else(|error| {
+                exit_with_error(&format!(
+                    "couldn't load scene {}: {}",
+                    path.display(),
+                    error
+                ))
+            });
+            (scene.config, Some(scene.world))
+        }
+        None => (Config::default(), None),
+    };
+    arguments.apply_to(&mut config);
+    if let Err(message) = config.validate() {
+        exit_with_error(&message);
+    }
+
+    let world = world.unwrap_or_else(|| {
+        // The scene is generated from the seed too. So it's the same between renders.
+        let mut sampler = Sampler::new(config.seed);
+        World::new(random_spheres(&mut sampler))
+    });
+
+    // RUST: Box<dyn Write> can hold any type that implements Write, here either a file or stdout.
+    // BufWriter collects the many small writes into fewer, larger ones.
+    //
+    // The image format of a file follows from its extension. Standard output gets PPM.
+    let (writer, output): (Box<dyn ImageWriter>, Box<dyn Write>) = match &arguments.output {
+        Some(path) => {
+            let writer =
+                image_writer_for_path(path).unwrap_or_else(|message| exit_with_error(&message));
+            let file = File::create(path).unwrap_or_else(|error| {
+                exit_with_error(&format!("couldn't create {}: {}", path.display(), error))
+            });
+            (writer, Box::new(file))
+        }
+        None => (Box::new(PpmWriter), Box::new(io::stdout())),
+    };
+    let mut output = BufWriter::new(output);
 
     // Trace rays!
-    render_image(config, world);
+    let camera = config.camera();
+    let framebuffer = render_image(config, camera, world);
+
+    // Write the image.
+    let result = writer
+        .write_image(&mut output, &framebuffer)
+        .and_then(|_| output.flush());
+    if let Err(error) = result {
+        exit_with_error(&format!("couldn't write the image: {}", error));
+    }
+}
+
+// RUST: The return type ! ("never") means that the function doesn't return.
+fn exit_with_error(message: &str) -> ! {
+    eprintln!("error: {}", message);
+    eprintln!("Run with --help to see all options.");
+    process::exit(2);
 }
@@ -0,0 +1,336 @@
+use std::ops::Mul;
+
+use crate::vec3::{Point3, Vec3};
+
+// A 4x4 matrix, for affine transformations: moving (translation), rotation and scaling. Points and
+// vectors are treated as 4D "homogeneous" coordinates, (x, y, z, 1) for points and (x, y, z, 0)
+// for vectors. So a translation moves a point, but leaves a direction alone.
+//
+// Transformations are combined by multiplying their matrices. The one on the right is applied
+// first: (A * B) transforms with B, then with A.
+#[derive(Clone, Copy)]
+pub struct Matrix4 {
+    elements: [[f64; 4]; 4], // Rows of columns.
+}
+
+impl Matrix4 {
+    // Constructors.
+    pub fn new(elements: [[f64; 4]; 4]) -> Matrix4 {
+        Matrix4 { elements }
+    }
+
+    // Leaves everything as it is.
+    pub fn identity() -> Matrix4 {
+        Matrix4::scaling(Vec3::new(1.0, 1.0, 1.0))
+    }
+
+    // Rotation around an axis through the origin, counter-clockwise when looking from the tip of
+    // the axis towards the origin (right hand rule). Rodrigues' rotation formula.
+    pub fn rotation(axis: Vec3, degrees: f64) -> Matrix4 {
+        let axis = axis.unit_vector();
+        let (x, y, z) = (axis.x(), axis.y(), axis.z());
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let t = 1.0 - cos;
+
+        Matrix4::new([
+            [
+                (t * x * x) + cos,
+                (t * x * y) - (sin * z),
+                (t * x * z) + (sin * y),
+                0.0,
+            ],
+            [
+                (t * x * y) + (sin * z),
+                (t * y * y) + cos,
+                (t * y * z) - (sin * x),
+                0.0,
+            ],
+            [
+                (t * x * z) - (sin * y),
+                (t * y * z) + (sin * x),
+                (t * z * z) + cos,
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn scaling(factors: Vec3) -> Matrix4 {
+        Matrix4::new([
+            [factors.x(), 0.0, 0.0, 0.0],
+            [0.0, factors.y(), 0.0, 0.0],
+            [0.0, 0.0, factors.z(), 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn translation(offset: Vec3) -> Matrix4 {
+        Matrix4::new([
+            [1.0, 0.0, 0.0, offset.x()],
+            [0.0, 1.0, 0.0, offset.y()],
+            [0.0, 0.0, 1.0, offset.z()],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    // Utility functions.
+
+    // The matrix that undoes this one. Gauss-Jordan elimination: row operations that turn the
+    // matrix into the identity, turn the identity into the inverse. None if there is no inverse,
+    // like for a scaling by zero.
+    pub fn inverse(&self) -> Option<Matrix4> {
+        let mut matrix = self.elements;
+        let mut inverse = Matrix4::identity().elements;
+
+        for column in 0..4 {
+            // Use the row with the largest value in the column ("partial pivoting"). Dividing by
+            // a tiny number would make rounding errors worse.
+            let pivot = (column..4)
+                .max_by(|a, b| {
+                    matrix[*a][column]
+                        .abs()
+                        .total_cmp(&matrix[*b][column].abs())
+                })
+                .unwrap();
+            if matrix[pivot][column].abs() < 1e-12 {
+                return None;
+            }
+            matrix.swap(column, pivot);
+            inverse.swap(column, pivot);
+
+            let divisor = matrix[column][column];
+            for i in 0..4 {
+                matrix[column][i] /= divisor;
+                inverse[column][i] /= divisor;
+            }
+
+            for row in 0..4 {
+                if row != column {
+                    let factor = matrix[row][column];
+                    for i in 0..4 {
+                        matrix[row][i] -= factor * matrix[column][i];
+                        inverse[row][i] -= factor * inverse[column][i];
+                    }
+                }
+            }
+        }
+
+        Some(Matrix4::new(inverse))
+    }
+
+    // The determinant of the top left 3x3 part, the part that rotates and scales. It's how much
+    // volumes are scaled by the transformation: the volume of the box that the X, Y and Z axes are
+    // turned into (negative if it's mirrored).
+    pub fn linear_determinant(&self) -> f64 {
+        let x = self.transform_vector(Vec3::new(1.0, 0.0, 0.0));
+        let y = self.transform_vector(Vec3::new(0.0, 1.0, 0.0));
+        let z = self.transform_vector(Vec3::new(0.0, 0.0, 1.0));
+        x.dot(y.cross(z))
+    }
+
+    // A point is moved by the translation. Affine transformations keep the last coordinate at 1,
+    // so there is no need to divide by it.
+    pub fn transform_point(&self, point: Point3) -> Point3 {
+        let m = &self.elements;
+        Point3::new(
+            (m[0][0] * point.x()) + (m[0][1] * point.y()) + (m[0][2] * point.z()) + m[0][3],
+            (m[1][0] * point.x()) + (m[1][1] * point.y()) + (m[1][2] * point.z()) + m[1][3],
+            (m[2][0] * point.x()) + (m[2][1] * point.y()) + (m[2][2] * point.z()) + m[2][3],
+        )
+    }
+
+    // A vector (direction, offset) isn't moved by the translation.
+    pub fn transform_vector(&self, vector: Vec3) -> Vec3 {
+        let m = &self.elements;
+        Vec3::new(
+            (m[0][0] * vector.x()) + (m[0][1] * vector.y()) + (m[0][2] * vector.z()),
+            (m[1][0] * vector.x()) + (m[1][1] * vector.y()) + (m[1][2] * vector.z()),
+            (m[2][0] * vector.x()) + (m[2][1] * vector.y()) + (m[2][2] * vector.z()),
+        )
+    }
+
+    // Normals are special. Transformed like vectors, a scaling would make them lean over instead
+    // of staying perpendicular to the surface. They have to be transformed with the transpose of
+    // the inverse. Call this on the inverse, it does the transpose. The result isn't a unit vector.
+    pub fn transform_normal(&self, normal: Vec3) -> Vec3 {
+        let m = &self.elements;
+        Vec3::new(
+            (m[0][0] * normal.x()) + (m[1][0] * normal.y()) + (m[2][0] * normal.z()),
+            (m[0][1] * normal.x()) + (m[1][1] * normal.y()) + (m[2][1] * normal.z()),
+            (m[0][2] * normal.x()) + (m[1][2] * normal.y()) + (m[2][2] * normal.z()),
+        )
+    }
+}
+
+// Operator overloads ("traits").
+// Multiplication operator: *
+impl Mul for Matrix4 {
+    type Output = Matrix4;
+    fn mul(self, rhs: Matrix4) -> Matrix4 {
+        let mut elements = [[0.0; 4]; 4];
+        for (row, row_elements) in elements.iter_mut().enumerate() {
+            for (column, element) in row_elements.iter_mut().enumerate() {
+                *element = (0..4)
+                    .map(|i| self.elements[row][i] * rhs.elements[i][column])
+                    .sum();
+            }
+        }
+        Matrix4 { elements }
+    }
+}
+
+// A single step of a transformation. Unlike a matrix, steps can be blended (interpolated), which
+// is how a transformation changes over time for motion blur. Blending matrices directly doesn't
+// work for rotations, the object would shrink halfway through.
+#[derive(Clone, Copy)]
+pub enum TransformStep {
+    Rotation { axis: Vec3, degrees: f64 },
+    Scaling(Vec3), // Must not be zero along any axis.
+    Translation(Vec3),
+}
+
+impl TransformStep {
+    pub fn matrix(&self) -> Matrix4 {
+        match *self {
+            TransformStep::Rotation { axis, degrees } => Matrix4::rotation(axis, degrees),
+            TransformStep::Scaling(factors) => Matrix4::scaling(factors),
+            TransformStep::Translation(offset) => Matrix4::translation(offset),
+        }
+    }
+
+    // Every step is easily undone, without inverting a matrix.
+    pub fn inverse_matrix(&self) -> Matrix4 {
+        match *self {
+            TransformStep::Rotation { axis, degrees } => Matrix4::rotation(axis, -degrees),
+            TransformStep::Scaling(factors) => Matrix4::scaling(Vec3::new(
+                1.0 / factors.x(),
+                1.0 / factors.y(),
+                1.0 / factors.z(),
+            )),
+            TransformStep::Translation(offset) => Matrix4::translation(-offset),
+        }
+    }
+
+    // The step a fraction (0.0 to 1.0) of the way from this step to the other. None if they aren't
+    // the same kind of step.
+    pub fn interpolate(&self, other: &TransformStep, fraction: f64) -> Option<TransformStep> {
+        let blend = |a: Vec3, b: Vec3| a + ((b - a) * fraction);
+        match (*self, *other) {
+            (
+                TransformStep::Rotation { axis, degrees },
+                TransformStep::Rotation {
+                    axis: other_axis,
+                    degrees: other_degrees,
+                },
+            ) => Some(TransformStep::Rotation {
+                axis: blend(axis.unit_vector(), other_axis.unit_vector()),
+                degrees: degrees + ((other_degrees - degrees) * fraction),
+            }),
+            (TransformStep::Scaling(a), TransformStep::Scaling(b)) => {
+                Some(TransformStep::Scaling(blend(a, b)))
+            }
+            (TransformStep::Translation(a), TransformStep::Translation(b)) => {
+                Some(TransformStep::Translation(blend(a, b)))
+            }
+            _ => None,
+        }
+    }
+}
+
+// The matrix of all steps, applied in order, and its inverse.
+pub fn matrices_of_steps(steps: &[TransformStep]) -> (Matrix4, Matrix4) {
+    steps.iter().fold(
+        (Matrix4::identity(), Matrix4::identity()),
+        |(matrix, inverse), step| (step.matrix() * matrix, inverse * step.inverse_matrix()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Matrix4, b: Matrix4) {
+        for row in 0..4 {
+            for column in 0..4 {
+                let (x, y) = (a.elements[row][column], b.elements[row][column]);
+                assert!(
+                    (x - y).abs() < 1e-9,
+                    "[{}][{}]: {} != {}",
+                    row,
+                    column,
+                    x,
+                    y
+                );
+            }
+        }
+    }
+
+    fn combined_transformation() -> Matrix4 {
+        Matrix4::translation(Vec3::new(1.0, -2.0, 3.0))
+            * Matrix4::rotation(Vec3::new(1.0, 2.0, -0.5), 37.0)
+            * Matrix4::scaling(Vec3::new(2.0, 0.5, -3.0))
+    }
+
+    #[test]
+    fn inverse_times_matrix_is_the_identity() {
+        let matrix = combined_transformation();
+        let inverse = matrix.inverse().unwrap();
+        assert_close(inverse * matrix, Matrix4::identity());
+        assert_close(matrix * inverse, Matrix4::identity());
+    }
+
+    // Zeros on the diagonal can only be inverted by swapping rows (the pivoting).
+    #[test]
+    fn inverse_swaps_rows_when_needed() {
+        let matrix = Matrix4::new([
+            [0.0, 2.0, 0.0, 1.0],
+            [3.0, 0.0, 0.0, 0.0],
+            [0.0, 0.0, 0.0, 4.0],
+            [0.0, 0.0, 5.0, 0.0],
+        ]);
+        assert_close(matrix.inverse().unwrap() * matrix, Matrix4::identity());
+    }
+
+    #[test]
+    fn flat_transformations_have_no_inverse() {
+        assert!(Matrix4::scaling(Vec3::new(1.0, 0.0, 1.0))
+            .inverse()
+            .is_none());
+    }
+
+    #[test]
+    fn inverse_of_steps_matches_the_inverted_matrix() {
+        let steps = [
+            TransformStep::Scaling(Vec3::new(2.0, 1.0, 0.25)),
+            TransformStep::Rotation {
+                axis: Vec3::new(0.0, 1.0, 0.0),
+                degrees: 120.0,
+            },
+            TransformStep::Translation(Vec3::new(0.0, 5.0, -1.0)),
+        ];
+        let (matrix, inverse) = matrices_of_steps(&steps);
+        assert_close(inverse, matrix.inverse().unwrap());
+    }
+
+    #[test]
+    fn linear_determinant_is_the_volume_scale() {
+        let determinant = Matrix4::scaling(Vec3::new(2.0, 0.5, -3.0)).linear_determinant();
+        assert!((determinant + 3.0).abs() < 1e-12);
+
+        // Rotations and translations don't change volumes.
+        let determinant = combined_transformation().linear_determinant();
+        assert!((determinant + 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn transformed_normals_stay_perpendicular() {
+        let matrix = combined_transformation();
+        let inverse = matrix.inverse().unwrap();
+        let (normal, tangent) = (Vec3::new(1.0, 1.0, 0.0), Vec3::new(1.0, -1.0, 3.0));
+
+        let dot = inverse
+            .transform_normal(normal)
+            .dot(matrix.transform_vector(tangent));
+        assert!(dot.abs() < 1e-9);
+    }
+}
@@ -0,0 +1,144 @@
+use std::ops::Range;
+
+use rand::{Error, RngCore};
+
+// A pseudorandom number generator (PRNG). It produces a sequence of numbers that looks random, but
+// is completely determined by its seed. So rendering with the same seed always gives the same
+// image, down to the last byte. This is the PCG32 generator (permuted congruential generator). It's
+// small, fast and has good statistical quality. It's implemented here, instead of using one from
+// the rand crate, so that the sequence never changes between versions of our dependencies. For
+// the same reason, the numbers the renderer uses come from next_f64(), range() and index() below,
+// and not from the helpers of rand's Rng trait, which could turn the same bits into different
+// numbers in another version.
+//
+// RUST: It still implements the RngCore trait, so it can be handed to code that expects a rand
+// generator.
+pub struct Sampler {
+    state: u64,
+}
+
+const MULTIPLIER: u64 = 6364136223846793005;
+const INCREMENT: u64 = 1442695040888963407;
+
+impl Sampler {
+    pub fn new(seed: u64) -> Sampler {
+        Sampler {
+            state: split_mix(seed),
+        }
+    }
+
+    // Every pixel gets its own sampler, derived from the seed and the pixel position. The samples of
+    // a pixel then don't depend on which pixels were rendered before it, or by which thread.
+    pub fn for_pixel(seed: u64, x: u16, y: u16) -> Sampler {
+        let pixel = ((y as u64) << 16) | x as u64;
+        Sampler::new(seed ^ split_mix(pixel))
+    }
+
+    // A random number from 0.0 up to (but not including) 1.0. The top 53 bits of a random u64,
+    // which is exactly as many as the mantissa of an f64 holds, so every value is equally likely.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    // A random number from the start of the range up to (but not including) the end.
+    pub fn range(&mut self, range: Range<f64>) -> f64 {
+        range.start + ((range.end - range.start) * self.next_f64())
+    }
+
+    // A random index into a list of the given length, from 0 to length - 1. The random u64 times
+    // the length, divided by 2^64 (the top half of the 128-bit product). It's biased towards some
+    // indices by at most length / 2^64, far too little to ever notice.
+    pub fn index(&mut self, length: usize) -> usize {
+        ((self.next_u64() as u128 * length as u128) >> 64) as usize
+    }
+}
+
+// SplitMix64 scrambles the bits of a number. Used to turn seeds that are similar (neighbouring
+// pixels, for example) into states that are very different.
+fn split_mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+impl RngCore for Sampler {
+    // Advance the state with a linear congruential generator (LCG), then scramble ("permute") the
+    // old state into the output. The permutation hides the weak low bits of the LCG.
+    //
+    // RUST: Integer overflow panics in debug builds. wrapping_*() overflows on purpose.
+    fn next_u32(&mut self) -> u32 {
+        let state = self.state;
+        self.state = state.wrapping_mul(MULTIPLIER).wrapping_add(INCREMENT);
+
+        let xor_shifted = (((state >> 18) ^ state) >> 27) as u32;
+        let rotation = (state >> 59) as u32;
+        xor_shifted.rotate_right(rotation)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        ((self.next_u32() as u64) << 32) | self.next_u32() as u64
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(4) {
+            let bytes = self.next_u32().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_gives_the_same_numbers() {
+        let (mut a, mut b) = (Sampler::new(1234), Sampler::new(1234));
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+        assert_ne!(Sampler::new(1234).next_u64(), Sampler::new(1235).next_u64());
+    }
+
+    // The sequence must never change, or old seeds would render different images. If this fails,
+    // the generator (or the seeding) was changed.
+    #[test]
+    fn sequence_is_pinned() {
+        let mut sampler = Sampler::new(0);
+        let numbers: Vec<u32> = (0..4).map(|_| sampler.next_u32()).collect();
+        assert_eq!(numbers, [0x412162a4, 0x109e014a, 0x3dfa6d1d, 0x521a81b8]);
+    }
+
+    #[test]
+    fn neighbouring_pixels_get_different_numbers() {
+        let first = Sampler::for_pixel(7, 10, 20).next_u64();
+        assert_ne!(first, Sampler::for_pixel(7, 11, 20).next_u64());
+        assert_ne!(first, Sampler::for_pixel(7, 10, 21).next_u64());
+        assert_eq!(first, Sampler::for_pixel(7, 10, 20).next_u64());
+    }
+
+    #[test]
+    fn helpers_stay_within_their_ranges() {
+        let mut sampler = Sampler::new(99);
+        let mut index_counts = [0; 5];
+        for _ in 0..10000 {
+            let number = sampler.next_f64();
+            assert!((0.0..1.0).contains(&number));
+
+            let number = sampler.range(-2.0..3.0);
+            assert!((-2.0..3.0).contains(&number));
+
+            index_counts[sampler.index(5)] += 1;
+        }
+        // Every index comes up about 2000 times.
+        assert!(index_counts
+            .iter()
+            .all(|count| (1800..2200).contains(count)));
+    }
+}
@@ -1,15 +1,73 @@
+use crate::sampler::Sampler;
+use crate::scene::environment_map::EnvironmentMap;
+use crate::scene::sky::Sky;
 use crate::tracer::ray::Ray;
-use crate::vec3::Color;
+use crate::vec3::{Color, Vec3};
 
-pub fn background_color(ray: &Ray) -> Color {
-    let blue_color = Color::new(0.5, 0.7, 1.0); // Sky'ish blue.
-    let white_color = Color::new(1.0, 1.0, 1.0);
+// The color of rays that don't hit any object. The background lights the whole world, unless it's
+// black. Then all light has to come from objects with an emissive material (lights).
+pub enum Background {
+    Environment(Box<EnvironmentMap>), // An image all around the scene, usually HDR. Large, so boxed.
+    Gradient { bottom: Color, top: Color }, // Blend from bottom to top, based on ray direction.
+    Sky(Box<Sky>),                    // A daylight sky, with the sun. Also large.
+    Solid(Color),
+}
+
+impl Background {
+    pub fn color(&self, ray: &Ray) -> Color {
+        match self {
+            Background::Environment(environment_map) => environment_map.color(ray.direction()),
+
+            Background::Gradient { bottom, top } => {
+                let ray_direction = ray.direction();
+                let normalized_ray_direction = ray_direction.unit_vector();
+                let t = (normalized_ray_direction.y() + 1.0) * 0.5;
+
+                // When t is 1.0 return top. When 0.0 return bottom. Blend in-between. This is called
+                // linear blend / linear interpolation / "lerp".
+                (*bottom * (1.0 - t)) + (*top * t)
+            }
+
+            Background::Sky(sky) => sky.color(ray.direction()),
 
-    let ray_direction = ray.direction();
-    let normalized_ray_direction = ray_direction.unit_vector();
-    let t = (normalized_ray_direction.y() + 1.0) * 0.5;
+            Background::Solid(color) => *color,
+        }
+    }
+
+    // Can the tracer aim shadow rays at the background, like at a light source? Only worth it for
+    // an environment map, or the sun of a sky. The other backgrounds are too even for it to help.
+    pub fn can_be_sampled(&self) -> bool {
+        match self {
+            Background::Environment(_) => true,
+            Background::Sky(sky) => sky.is_sun_up(),
+            _ => false,
+        }
+    }
+
+    // A random direction towards the background, and its probability density (per solid angle).
+    // Only for backgrounds that can be sampled.
+    pub fn random_direction(&self, sampler: &mut Sampler) -> Vec3 {
+        match self {
+            Background::Environment(environment_map) => environment_map.random_direction(sampler),
+            Background::Sky(sky) => sky.random_direction(sampler),
+            _ => Vec3::random_unit_vector(sampler),
+        }
+    }
+
+    pub fn direction_pdf(&self, direction: Vec3) -> f64 {
+        match self {
+            Background::Environment(environment_map) => environment_map.direction_pdf(direction),
+            Background::Sky(sky) => sky.direction_pdf(direction),
+            _ => 0.0,
+        }
+    }
+}
 
-    // When t is 1.0 return blue. When 0.0 return white. Blend in-between. This is called
-    // linear blend / linear interpolation / "lerp".
-    (white_color * (1.0 - t)) + (blue_color * t)
+impl Default for Background {
+    fn default() -> Background {
+        Background::Gradient {
+            bottom: Color::new(1.0, 1.0, 1.0), // White.
+            top: Color::new(0.5, 0.7, 1.0),    // Sky'ish blue.
+        }
+    }
 }
@@ -0,0 +1,183 @@
+use std::f64::consts::PI;
+
+use crate::image::framebuffer::Framebuffer;
+use crate::matrix4::Matrix4;
+use crate::sampler::Sampler;
+use crate::vec3::{Color, Vec3};
+
+// An image of everything around the scene, far away, that lights it. Usually a high dynamic range
+// (HDR) photo, so that the sun or a lamp in it is as bright as it should be, compared to the sky.
+//
+// The image is "equirectangular", like a map of the world: left to right is all the way around
+// (the longitude, around the Y axis), and top to bottom is straight up to straight down (the
+// latitude). The same mapping as the texture coordinates of a Sphere.
+//
+// A small, bright, sun in the map lights the scene more than the whole sky, but a scattered ray
+// rarely hits it. So the tracer aims shadow rays at the map, picking pixels in proportion to how
+// bright they are ("importance sampling").
+pub struct EnvironmentMap {
+    columns: Vec<PiecewiseConstant>, // For every row, how likely every pixel in it is picked.
+    height: usize,
+    map_to_world: Matrix4,   // The rotation of the map, around the Y axis.
+    pixels: Vec<Color>,      // Linear colors, rows top to bottom.
+    rows: PiecewiseConstant, // How likely every row is picked.
+    width: usize,
+    world_to_map: Matrix4,
+}
+
+impl EnvironmentMap {
+    // The map is rotated counter-clockwise around the Y axis (seen from above), in degrees. That
+    // turns the sun in the map to where it's wanted.
+    pub fn new(image: &Framebuffer, rotation: f64) -> EnvironmentMap {
+        let width = image.width() as usize;
+        let height = image.height() as usize;
+        let pixels = image.pixels();
+
+        // Rows near the top and bottom are squeezed together on the sphere of directions. A pixel
+        // there covers less of it (in proportion to sin(theta)), so it's picked less often.
+        let columns: Vec<PiecewiseConstant> = (0..height)
+            .map(|y| {
+                let sin_theta = (PI * (y as f64 + 0.5) / height as f64).sin();
+                let weights = pixels[(y * width)..((y + 1) * width)]
+                    .iter()
+                    .map(|pixel| luminance(*pixel) * sin_theta)
+                    .collect();
+                PiecewiseConstant::new(weights)
+            })
+            .collect();
+        let rows = PiecewiseConstant::new(columns.iter().map(|row| row.integral).collect());
+
+        let y_axis = Vec3::new(0.0, 1.0, 0.0);
+        EnvironmentMap {
+            columns,
+            height,
+            map_to_world: Matrix4::rotation(y_axis, rotation),
+            pixels,
+            rows,
+            width,
+            world_to_map: Matrix4::rotation(y_axis, -rotation),
+        }
+    }
+
+    // The light coming from the direction. The nearest pixel, no filtering.
+    pub fn color(&self, direction: Vec3) -> Color {
+        let (u, v) = self.coordinates(direction);
+        let (x, y) = self.pixel_at(u, v);
+        self.pixels[(y * self.width) + x]
+    }
+
+    // A random direction, towards the brighter parts of the map more often. First pick a row, then
+    // a pixel in it. Then a random point within the pixel.
+    pub fn random_direction(&self, sampler: &mut Sampler) -> Vec3 {
+        let v = self.rows.sample(sampler.next_f64());
+        let (_, y) = self.pixel_at(0.0, v);
+        let u = self.columns[y].sample(sampler.next_f64());
+        self.direction(u, v)
+    }
+
+    // The probability density of random_direction() picking the direction, per solid angle. The
+    // density per pixel (in u, v between 0.0 and 1.0) is spread out over 2*pi*pi*sin(theta) of the
+    // sphere of directions.
+    pub fn direction_pdf(&self, direction: Vec3) -> f64 {
+        let (u, v) = self.coordinates(direction);
+        let (x, y) = self.pixel_at(u, v);
+        let sin_theta = (PI * v).sin();
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+
+        let pdf = self.rows.pdf(y) * self.columns[y].pdf(x);
+        pdf / (2.0 * PI * PI * sin_theta)
+    }
+
+    // Map (u, v) of a direction. u goes around, from 0.0 to 1.0, and v from the top (0.0) to the
+    // bottom (1.0).
+    fn coordinates(&self, direction: Vec3) -> (f64, f64) {
+        let direction = self.world_to_map.transform_vector(direction).unit_vector();
+        let theta = direction.y().clamp(-1.0, 1.0).acos();
+        let phi = (-direction.z()).atan2(direction.x()) + PI;
+        (phi / (2.0 * PI), theta / PI)
+    }
+
+    // The opposite of coordinates().
+    fn direction(&self, u: f64, v: f64) -> Vec3 {
+        let theta = PI * v;
+        let phi = (2.0 * PI * u) - PI;
+        let direction = Vec3::new(
+            theta.sin() * phi.cos(),
+            theta.cos(),
+            -theta.sin() * phi.sin(),
+        );
+        self.map_to_world.transform_vector(direction)
+    }
+
+    fn pixel_at(&self, u: f64, v: f64) -> (usize, usize) {
+        let x = ((u * self.width as f64) as usize).min(self.width - 1);
+        let y = ((v * self.height as f64) as usize).min(self.height - 1);
+        (x, y)
+    }
+}
+
+// How bright a color looks. The eye is most sensitive to green, and least to blue.
+fn luminance(color: Color) -> f64 {
+    (0.2126 * color.x()) + (0.7152 * color.y()) + (0.0722 * color.z())
+}
+
+// A probability distribution over 0.0 to 1.0, split into equal parts with a constant density each.
+// The density of a part is in proportion to its weight. A value is picked with the "cumulative
+// distribution function" (CDF): the sum of the weights up to every part. A random number between 0
+// and the total weight falls in a part with a chance in proportion to its weight.
+struct PiecewiseConstant {
+    cdf: Vec<f64>,     // Sum of the weights before every part, and the total at the end.
+    integral: f64,     // The average weight. (The area under the weights, over 0.0 to 1.0.)
+    weights: Vec<f64>, // Never all zero. Then they are all one instead.
+}
+
+impl PiecewiseConstant {
+    fn new(weights: Vec<f64>) -> PiecewiseConstant {
+        let count = weights.len() as f64;
+        let integral = weights.iter().sum::<f64>() / count;
+
+        // Nothing to go by (a black row, for example). Then everything is as likely.
+        let weights = if integral > 0.0 {
+            weights
+        } else {
+            vec![1.0; weights.len()]
+        };
+
+        let mut cdf = Vec::with_capacity(weights.len() + 1);
+        cdf.push(0.0);
+        for weight in &weights {
+            cdf.push(cdf[cdf.len() - 1] + (weight / count));
+        }
+
+        PiecewiseConstant {
+            cdf,
+            integral,
+            weights,
+        }
+    }
+
+    // A value between 0.0 and 1.0, from a uniformly random number between 0.0 and 1.0.
+    fn sample(&self, random: f64) -> f64 {
+        let total = self.cdf[self.cdf.len() - 1];
+        let target = random * total;
+
+        // RUST: partition_point() is a binary search, for the first sum that is past the target.
+        let part = (self.cdf.partition_point(|sum| *sum <= target) - 1).min(self.weights.len() - 1);
+
+        // Where in the part, linearly.
+        let width = self.cdf[part + 1] - self.cdf[part];
+        let offset = if width > 0.0 {
+            (target - self.cdf[part]) / width
+        } else {
+            0.0
+        };
+        (part as f64 + offset) / self.weights.len() as f64
+    }
+
+    // The density of a part.
+    fn pdf(&self, part: usize) -> f64 {
+        self.weights[part] / self.cdf[self.cdf.len() - 1]
+    }
+}
@@ -0,0 +1,423 @@
+use std::error::Error;
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+
+// A small JSON parser, for scene description files. Every value remembers where in the text it
+// was found, so that errors (syntax errors, but also a misspelled material name, for example) can
+// point at the exact line and column.
+pub struct Json {
+    column: usize,
+    line: usize,
+    value: JsonValue,
+}
+
+pub enum JsonValue {
+    Array(Vec<Json>),
+    Boolean(bool),
+    Null,
+    Number(f64),
+    Object(Vec<(String, Json)>), // Keeps the order of the keys in the text.
+    String(String),
+}
+
+#[derive(Debug)]
+pub struct ParseError {
+    column: usize,
+    line: usize,
+    message: String,
+}
+
+impl ParseError {
+    pub fn new(line: usize, column: usize, message: impl Into<String>) -> ParseError {
+        ParseError {
+            column,
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+// RUST: Implementing Display lets the error be printed with "{}". Implementing Error makes it
+// usable with the standard error handling, like the ? operator and Box<dyn Error>.
+impl fmt::Display for ParseError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+impl Json {
+    pub fn value(&self) -> &JsonValue {
+        &self.value
+    }
+
+    // An error located at this value.
+    pub fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError::new(self.line, self.column, message)
+    }
+
+    pub fn as_array(&self) -> Result<&[Json], ParseError> {
+        match &self.value {
+            JsonValue::Array(values) => Ok(values),
+            _ => Err(self.error("expected an array")),
+        }
+    }
+
+    pub fn as_boolean(&self) -> Result<bool, ParseError> {
+        match &self.value {
+            JsonValue::Boolean(boolean) => Ok(*boolean),
+            _ => Err(self.error("expected true or false")),
+        }
+    }
+
+    pub fn as_number(&self) -> Result<f64, ParseError> {
+        match &self.value {
+            JsonValue::Number(number) => Ok(*number),
+            _ => Err(self.error("expected a number")),
+        }
+    }
+
+    pub fn as_object(&self) -> Result<&[(String, Json)], ParseError> {
+        match &self.value {
+            JsonValue::Object(members) => Ok(members),
+            _ => Err(self.error("expected an object")),
+        }
+    }
+
+    pub fn as_string(&self) -> Result<&str, ParseError> {
+        match &self.value {
+            JsonValue::String(string) => Ok(string),
+            _ => Err(self.error("expected a string")),
+        }
+    }
+
+    // The value of a key in an object. None if the key is missing (or if this isn't an object).
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match &self.value {
+            JsonValue::Object(members) => members
+                .iter()
+                .find(|(member_key, _)| member_key == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+}
+
+pub fn parse_json(text: &str) -> Result<Json, ParseError> {
+    let mut parser = Parser {
+        characters: text.chars().peekable(),
+        column: 1,
+        line: 1,
+    };
+
+    let json = parser.parse_value()?;
+    parser.skip_whitespace();
+    if parser.characters.peek().is_some() {
+        return Err(parser.error("unexpected text after the end of the document"));
+    }
+
+    Ok(json)
+}
+
+// A recursive descent parser. Every kind of value has its own function, which calls the functions
+// of the values it contains.
+struct Parser<'a> {
+    characters: Peekable<Chars<'a>>,
+    column: usize,
+    line: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError::new(self.line, self.column, message)
+    }
+
+    // Take the next character and keep track of the position in the text.
+    fn next(&mut self) -> Option<char> {
+        let character = self.characters.next()?;
+        if character == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(character)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ParseError> {
+        match self.characters.peek() {
+            Some(&character) if character == expected => {
+                self.next();
+                Ok(())
+            }
+            Some(&character) => {
+                Err(self.error(format!("expected '{}' but found '{}'", expected, character)))
+            }
+            None => Err(self.error(format!("expected '{}' but the file ended", expected))),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(character) = self.characters.peek() {
+            if !character.is_whitespace() {
+                break;
+            }
+            self.next();
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Json, ParseError> {
+        self.skip_whitespace();
+        let (line, column) = (self.line, self.column);
+
+        let value = match self.characters.peek() {
+            None => return Err(self.error("expected a value but the file ended")),
+            Some('{') => self.parse_object()?,
+            Some('[') => self.parse_array()?,
+            Some('"') => JsonValue::String(self.parse_string()?),
+            Some('-' | '0'..='9') => JsonValue::Number(self.parse_number()?),
+            Some('t') => {
+                self.parse_keyword("true")?;
+                JsonValue::Boolean(true)
+            }
+            Some('f') => {
+                self.parse_keyword("false")?;
+                JsonValue::Boolean(false)
+            }
+            Some('n') => {
+                self.parse_keyword("null")?;
+                JsonValue::Null
+            }
+            Some(&character) => return Err(self.error(format!("unexpected '{}'", character))),
+        };
+
+        Ok(Json {
+            column,
+            line,
+            value,
+        })
+    }
+
+    fn parse_keyword(&mut self, keyword: &str) -> Result<(), ParseError> {
+        for expected in keyword.chars() {
+            self.expect(expected)?;
+        }
+        Ok(())
+    }
+
+    fn parse_object(&mut self) -> Result<JsonValue, ParseError> {
+        self.expect('{')?;
+        let mut members: Vec<(String, Json)> = Vec::new();
+
+        self.skip_whitespace();
+        if self.characters.peek() == Some(&'}') {
+            self.next();
+            return Ok(JsonValue::Object(members));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let (line, column) = (self.line, self.column);
+            if self.characters.peek() != Some(&'"') {
+                return Err(self.error("expected a key (a string)"));
+            }
+            let key = self.parse_string()?;
+            if members.iter().any(|(member_key, _)| *member_key == key) {
+                return Err(ParseError::new(
+                    line,
+                    column,
+                    format!("duplicate key \"{}\"", key),
+                ));
+            }
+
+            self.skip_whitespace();
+            self.expect(':')?;
+            let value = self.parse_value()?;
+            members.push((key, value));
+
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some('}') => break,
+                _ => return Err(self.error("expected ',' or '}' after the value")),
+            }
+        }
+
+        Ok(JsonValue::Object(members))
+    }
+
+    fn parse_array(&mut self) -> Result<JsonValue, ParseError> {
+        self.expect('[')?;
+        let mut values = Vec::new();
+
+        self.skip_whitespace();
+        if self.characters.peek() == Some(&']') {
+            self.next();
+            return Ok(JsonValue::Array(values));
+        }
+
+        loop {
+            values.push(self.parse_value()?);
+
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => break,
+                _ => return Err(self.error("expected ',' or ']' after the value")),
+            }
+        }
+
+        Ok(JsonValue::Array(values))
+    }
+
+    fn parse_string(&mut self) -> Result<String, ParseError> {
+        self.expect('"')?;
+        let mut string = String::new();
+
+        loop {
+            match self.next() {
+                None => return Err(self.error("unterminated string")),
+                Some('"') => break,
+                Some('\\') => {
+                    let escaped = match self.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            let mut code = 0;
+                            for _ in 0..4 {
+                                let digit = self
+                                    .next()
+                                    .and_then(|character| character.to_digit(16))
+                                    .ok_or_else(|| self.error("invalid unicode escape"))?;
+                                code = (code * 16) + digit;
+                            }
+                            char::from_u32(code)
+                                .ok_or_else(|| self.error("invalid unicode escape"))?
+                        }
+                        _ => return Err(self.error("invalid escape sequence")),
+                    };
+                    string.push(escaped);
+                }
+                Some(character) => string.push(character),
+            }
+        }
+
+        Ok(string)
+    }
+
+    fn parse_number(&mut self) -> Result<f64, ParseError> {
+        let (line, column) = (self.line, self.column);
+        let mut text = String::new();
+
+        while let Some(&character) = self.characters.peek() {
+            if !matches!(character, '-' | '+' | '.' | 'e' | 'E' | '0'..='9') {
+                break;
+            }
+            text.push(character);
+            self.next();
+        }
+
+        // RUST: parse() understands more than JSON does (like "1." or "+1"), which is fine here.
+        text.parse::<f64>()
+            .map_err(|_| ParseError::new(line, column, format!("invalid number \"{}\"", text)))
+    }
+}
+
+// RUST: A module marked #[cfg(test)] is only compiled by "cargo test". Being a child module, it can
+// see the private fields of ParseError.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error(text: &str) -> ParseError {
+        match parse_json(text) {
+            Ok(_) => panic!("expected {:?} not to parse", text),
+            Err(error) => error,
+        }
+    }
+
+    #[test]
+    fn parses_nested_values_and_keeps_key_order() {
+        let json = parse_json(r#"{"b": [1, -2.5e1, true, null], "a": "x\ny\u0041"}"#).unwrap();
+
+        let members = json.as_object().unwrap();
+        assert_eq!(members[0].0, "b");
+        assert_eq!(members[1].0, "a");
+
+        let array = json.get("b").unwrap().as_array().unwrap();
+        assert_eq!(array[0].as_number().unwrap(), 1.0);
+        assert_eq!(array[1].as_number().unwrap(), -25.0);
+        assert!(array[2].as_boolean().unwrap());
+        assert!(matches!(array[3].value(), JsonValue::Null));
+        assert_eq!(json.get("a").unwrap().as_string().unwrap(), "x\nyA");
+        assert!(json.get("c").is_none());
+    }
+
+    #[test]
+    fn values_remember_their_line_and_column() {
+        let json = parse_json("{\n  \"radius\": 2\n}").unwrap();
+        let radius = json.get("radius").unwrap();
+        assert_eq!((radius.line, radius.column), (2, 13));
+
+        let error = radius.as_string().unwrap_err();
+        assert_eq!((error.line, error.column), (2, 13));
+    }
+
+    #[test]
+    fn syntax_errors_point_at_line_and_column() {
+        let error = parse_error("{\n  \"a\": 1\n  \"b\": 2\n}");
+        assert_eq!((error.line, error.column), (3, 4));
+        assert_eq!(error.message, "expected ',' or '}' after the value");
+
+        let error = parse_error("[1, 2,\n   oops]");
+        assert_eq!((error.line, error.column), (2, 4));
+        assert_eq!(error.message, "unexpected 'o'");
+
+        let error = parse_error("{\"a\": 1, \"a\": 2}");
+        assert_eq!((error.line, error.column), (1, 10));
+        assert_eq!(error.message, "duplicate key \"a\"");
+
+        let error = parse_error("  -1.2.3");
+        assert_eq!((error.line, error.column), (1, 3));
+        assert_eq!(error.message, "invalid number \"-1.2.3\"");
+    }
+
+    #[test]
+    fn rejects_unfinished_documents_and_trailing_text() {
+        assert_eq!(
+            parse_error("").message,
+            "expected a value but the file ended"
+        );
+        assert_eq!(parse_error("\"abc").message, "unterminated string");
+        assert_eq!(
+            parse_error("[1, 2").message,
+            "expected ',' or ']' after the value"
+        );
+        assert_eq!(parse_error("\"\\q\"").message, "invalid escape sequence");
+
+        let error = parse_error("{} {}");
+        assert_eq!((error.line, error.column), (1, 4));
+        assert_eq!(
+            error.message,
+            "unexpected text after the end of the document"
+        );
+    }
+
+    #[test]
+    fn errors_print_their_position() {
+        let error = ParseError::new(3, 7, "something is wrong");
+        assert_eq!(error.to_string(), "line 3, column 7: something is wrong");
+    }
+}
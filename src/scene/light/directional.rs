@@ -0,0 +1,28 @@
+use crate::scene::light::light::{Illumination, Light};
+use crate::vec3::{Color, Point3, Vec3};
+
+// Light from so far away that it arrives everywhere from the same direction, and just as bright.
+// Like sunlight. Shadows are sharp, and parallel.
+pub struct DirectionalLight {
+    direction: Vec3, // Unit vector, the direction the light travels in. Downwards for a sun.
+    irradiance: Color, // The light arriving, on a surface facing the light.
+}
+
+impl DirectionalLight {
+    pub fn new(direction: Vec3, irradiance: Color) -> DirectionalLight {
+        DirectionalLight {
+            direction: direction.unit_vector(),
+            irradiance,
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn illuminate(&self, _point: Point3) -> Option<Illumination> {
+        Some(Illumination {
+            direction: -self.direction,
+            distance: f64::INFINITY,
+            light: self.irradiance,
+        })
+    }
+}
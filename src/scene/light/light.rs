@@ -0,0 +1,19 @@
+use crate::vec3::{Color, Point3, Vec3};
+
+// Lights that are only a point (or a direction), not an object. Classic lights for lighting a
+// scene: point lights, spot lights and the sun. They can't be seen, and no ray ever hits them by
+// chance, since they have no size. So the tracer always aims shadow rays at them (see tracer.rs).
+//
+// RUST: See Material for why lights must be Send and Sync.
+pub trait Light: Send + Sync {
+    // The light arriving at the point from this light, if nothing is in the way. None if it
+    // doesn't reach the point at all, like outside the cone of a spot light.
+    fn illuminate(&self, point: Point3) -> Option<Illumination>;
+}
+
+// Light arriving at a point.
+pub struct Illumination {
+    pub direction: Vec3, // Unit vector from the point towards the light.
+    pub distance: f64,   // To the light. Infinite for lights that are infinitely far away.
+    pub light: Color, // How much arrives, on a surface facing the light (irradiance). Can be above 1.0.
+}
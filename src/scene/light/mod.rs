@@ -0,0 +1,5 @@
+pub mod directional;
+#[allow(clippy::module_inception)]
+pub mod light;
+pub mod point;
+pub mod spot;
@@ -0,0 +1,35 @@
+use crate::scene::light::light::{Illumination, Light};
+use crate::vec3::{Color, Point3};
+
+// A tiny light bulb, that shines equally in all directions. The light spreads out over a sphere
+// that grows with the distance, so the light arriving falls off with the square of the distance
+// (the "inverse-square law"). Twice as far away is a quarter of the light.
+pub struct PointLight {
+    intensity: Color, // Light per solid angle (radiant intensity). The light arriving at distance 1.
+    position: Point3,
+}
+
+impl PointLight {
+    pub fn new(position: Point3, intensity: Color) -> PointLight {
+        PointLight {
+            intensity,
+            position,
+        }
+    }
+}
+
+impl Light for PointLight {
+    fn illuminate(&self, point: Point3) -> Option<Illumination> {
+        let to_light = self.position - point;
+        let distance_squared = to_light.length_squared();
+        if distance_squared == 0.0 {
+            return None;
+        }
+
+        Some(Illumination {
+            direction: to_light.unit_vector(),
+            distance: distance_squared.sqrt(),
+            light: self.intensity / distance_squared,
+        })
+    }
+}
@@ -0,0 +1,65 @@
+use crate::scene::light::light::{Illumination, Light};
+use crate::vec3::{Color, Point3, Vec3};
+
+// A point light that only shines in a cone, like a stage light or a flashlight. Inside the falloff
+// angle it's a point light. Between the falloff angle and the cone angle, the light fades out
+// smoothly. Outside the cone, there is no light.
+pub struct SpotLight {
+    cos_cone_angle: f64, // Cosines of the angles, since they are compared with dot products.
+    cos_falloff_angle: f64,
+    direction: Vec3, // Unit vector, where the cone points.
+    intensity: Color,
+    position: Point3,
+}
+
+impl SpotLight {
+    // The angles are in degrees, from the center of the cone to its edge (half of the angle across
+    // the cone). The falloff angle is at most the cone angle; the same for a hard edge.
+    pub fn new(
+        position: Point3,
+        direction: Vec3,
+        (falloff_angle, cone_angle): (f64, f64),
+        intensity: Color,
+    ) -> SpotLight {
+        SpotLight {
+            cos_cone_angle: cone_angle.to_radians().cos(),
+            cos_falloff_angle: falloff_angle.min(cone_angle).to_radians().cos(),
+            direction: direction.unit_vector(),
+            intensity,
+            position,
+        }
+    }
+
+    // 1.0 inside the falloff angle, 0.0 outside the cone, and a smooth S-curve ("smoothstep") in
+    // between.
+    fn falloff(&self, cos_angle: f64) -> f64 {
+        if cos_angle >= self.cos_falloff_angle {
+            return 1.0;
+        }
+        let t = (cos_angle - self.cos_cone_angle) / (self.cos_falloff_angle - self.cos_cone_angle);
+        let t = t.clamp(0.0, 1.0);
+        t * t * (3.0 - (2.0 * t))
+    }
+}
+
+impl Light for SpotLight {
+    fn illuminate(&self, point: Point3) -> Option<Illumination> {
+        let to_light = self.position - point;
+        let distance_squared = to_light.length_squared();
+        if distance_squared == 0.0 {
+            return None;
+        }
+
+        let direction = to_light.unit_vector();
+        let falloff = self.falloff((-direction).dot(self.direction));
+        if falloff == 0.0 {
+            return None;
+        }
+
+        Some(Illumination {
+            direction,
+            distance: distance_squared.sqrt(),
+            light: self.intensity * (falloff / distance_squared),
+        })
+    }
+}
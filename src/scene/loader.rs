@@ -0,0 +1,1397 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::image::framebuffer::Framebuffer;
+use crate::image::reader::read_image;
+use crate::matrix4::{matrices_of_steps, TransformStep};
+use crate::sampler::Sampler;
+use crate::scene::background::Background;
+use crate::scene::environment_map::EnvironmentMap;
+use crate::scene::json::{parse_json, Json, ParseError};
+use crate::scene::light::directional::DirectionalLight;
+use crate::scene::light::light::Light;
+use crate::scene::light::point::PointLight;
+use crate::scene::light::spot::SpotLight;
+use crate::scene::material::dielectric::Dielectric;
+use crate::scene::material::diffuse::Diffuse;
+use crate::scene::material::diffuse_light::DiffuseLight;
+use crate::scene::material::material::Material;
+use crate::scene::material::metal::Metal;
+use crate::scene::material::reflective::Reflective;
+use crate::scene::material::rough_dielectric::RoughDielectric;
+use crate::scene::medium::grid::GridMedium;
+use crate::scene::medium::homogeneous::HomogeneousMedium;
+use crate::scene::medium::phase_function::PhaseFunction;
+use crate::scene::medium::voxel_grid::{VoxelGrid, MAX_VOXELS};
+use crate::scene::object::csg::{Csg, CsgOperation};
+use crate::scene::object::cuboid::Cuboid;
+use crate::scene::object::disk::Disk;
+use crate::scene::object::distance_field::{DistanceField, DistanceFunction};
+use crate::scene::object::moving_sphere::MovingSphere;
+use crate::scene::object::object::Object;
+use crate::scene::object::plane::Plane;
+use crate::scene::object::quad::Quad;
+use crate::scene::object::sphere::Sphere;
+use crate::scene::object::transformed::Transformed;
+use crate::scene::object::triangle::Triangle;
+use crate::scene::object::triangle_mesh::TriangleMesh;
+use crate::scene::object::volume::Volume;
+use crate::scene::object::world::World;
+use crate::scene::sky::Sky;
+use crate::scene::texture::checker::Checker;
+use crate::scene::texture::image_texture::ImageTexture;
+use crate::scene::texture::noise::{NoiseStyle, NoiseTexture};
+use crate::scene::texture::solid_color::SolidColor;
+use crate::scene::texture::texture::Texture;
+use crate::scene::wavefront::load_obj;
+use crate::tracer::camera::Camera;
+use crate::vec3::{Color, Vec3};
+
+// Scene description files are JSON. Everything is optional, what's left out is taken from
+// Config::default(). For example:
+//
+// {
+//   "image": { "width": 400, "aspect_ratio": 1.5, "samples_per_pixel": 100, "seed": 7 },
+//   "camera": {
+//     "look_from": [13, 2, 3], "look_at": [0, 0, 0], "vertical_field_of_view": 20,
+//     "aperture": 0.1, "focus_distance": 10, "shutter_open": 0, "shutter_close": 1
+//   },
+//   "background": { "type": "solid", "color": [0, 0, 0] },
+//   "lights": [{ "type": "point", "position": [0, 5, 0], "intensity": [20, 20, 20] }],
+//   "materials": {
+//     "ground": { "type": "diffuse", "albedo": [0.5, 0.5, 0.5] },
+//     "lamp": { "type": "diffuse_light", "emitted": [4, 4, 4] }
+//   },
+//   "objects": [
+//     { "type": "plane", "point": [0, 0, 0], "normal": [0, 1, 0], "material": "ground" },
+//     { "type": "sphere", "center": [0, 2, 0], "radius": 1, "material": "lamp" },
+//     { "type": "triangle", "vertices": [[0, 0, 2], [1, 0, 2], [0, 1, 2]], "material": "ground" },
+//     { "type": "mesh", "path": "teapot.obj", "material": "ground" }
+//   ]
+// }
+//
+// The background is either { "type": "gradient", "bottom": [r, g, b], "top": [r, g, b] },
+// { "type": "solid", "color": [r, g, b] } or an environment map,
+// { "type": "environment", "path": "sky.hdr", "rotation": 90 }. The map is an equirectangular image
+// (.hdr, .pfm, .png or .ppm), rotated around the Y axis by the optional rotation, in degrees. Or
+// a daylight sky, { "type": "sky", "sun_direction": [1, 1, 0], "turbidity": 3,
+// "ground_albedo": [0.3, 0.3, 0.3] }. The sun direction points towards the sun: a low sun for the
+// morning or evening, a high one for midday. The turbidity is how hazy the air is, from 1.7 (very
+// clear) to 10, 3 if left out. The ground albedo is the color of the ground below the horizon,
+// [0.3, 0.3, 0.3] if left out.
+//
+// Materials are "diffuse" (albedo), "reflective" (albedo, fuzz), "dielectric" (refractive_index,
+// and roughness for frosted glass), "diffuse_light" (emitted) and "metal" (roughness, and either a
+// "preset" of "aluminium", "copper", "gold" or "silver", or the complex refractive index as "eta"
+// and "k", both [r, g, b]). The roughness goes from 0 for a smooth surface to 1, 0 if left out.
+//
+// A "dielectric" is clear, unless it has an "absorption" ([r, g, b], how quickly light is absorbed
+// inside, per unit of distance), or a "color" that light has after going a "depth" through it (1
+// if left out). Like { "type": "dielectric", "refractive_index": 1.5, "color": [0.2, 0.8, 0.4],
+// "depth": 2 }.
+//
+// Lights are "point" (position, intensity), "spot" (position, direction, cone_angle, and the
+// optional falloff_angle where it starts to fade out, in degrees, intensity) and "directional"
+// (direction the light travels in, irradiance). The intensity is the light arriving at a distance
+// of 1, it falls off with the square of the distance. A directional light is as bright everywhere.
+//
+// The albedo of "diffuse" and "reflective" is either a color or a texture:
+// - { "type": "solid", "color": [r, g, b] }
+// - { "type": "checker", "even": albedo, "odd": albedo, "size": 1 }
+// - { "type": "noise", "scale": 1, "color": [r, g, b] }, also "turbulence" and "marble".
+// - { "type": "image", "path": "earth.png" }, a .png or .ppm file relative to the scene file.
+//
+// Objects are "sphere" (center, radius), "moving_sphere" (two centers, and optionally the times it
+// is at them, [0, 1] by default), "plane" (point, normal), "quad" (corner, and the edges u
+// and v), "rectangle" (two opposite corners, in an axis plane), "disk" (center, normal, radius),
+// "cuboid" (two opposite corners), "triangle" (vertices) and "mesh" (path). They all need a
+// material, except meshes.
+//
+// A "volume" is smoke, or a cloud, in the shape of another object, its "boundary" (without a
+// material). Like { "type": "volume", "boundary": { "type": "sphere", "center": [0, 1, 0],
+// "radius": 1 }, "density": 2, "albedo": [0.8, 0.8, 0.8], "anisotropy": 0.3 }. The density is
+// how likely light is to run into a particle, per unit of distance. The albedo is the color of
+// the particles, white if left out. The anisotropy goes from -1 (scattering light back) to 1
+// (forward), 0 (every direction the same) if left out. The boundary has to be closed, and the
+// camera outside of it.
+//
+// A "csg" combines solids (closed objects) into one, with constructive solid geometry (see Csg).
+// The "operation" is "union" (inside any of them), "intersection" (inside all of them) or
+// "difference" (inside the first, with the others cut out of it), of a list of "objects". Like a
+// lens, { "type": "csg", "operation": "intersection", "objects": [{ "type": "sphere", ... },
+// { "type": "sphere", ... }] }. The surfaces keep their own materials.
+//
+// A "distance_field" is a shape given by a signed distance function (see DistanceField), in its
+// "shape": spheres, boxes, rounded boxes, tori, capsules and the Mandelbulb fractal, blended
+// together and repeated. See parse_distance_function(). Like { "type": "distance_field",
+// "shape": { "type": "smooth_union", "smoothness": 0.3, "shapes": [{ "type": "sphere", ... },
+// { "type": "torus", ... }] }, "material": "wax" }.
+//
+// A "grid_volume" is smoke, a cloud or fire with a density that changes from place to place, from
+// a voxel grid stretched over a box between two "corners". It has an albedo and anisotropy like a
+// volume, a "density" grid, times the optional "density_scale", and an optional "emission" grid
+// for the light it gives off, times the "emission_color" (white if left out). Grids come from a
+// file, { "type": "file", "path": "smoke.txt" }, or from noise, { "type": "noise",
+// "resolution": [64, 64, 64], "scale": 4 } (see VoxelGrid). A text file starts with the resolution
+// of the grid, and a .raw file (32-bit floats) needs a "resolution" next to its path. A grid
+// volume can't be transformed.
+//
+// A "fog" next to the objects fills the whole scene, with a density, albedo and anisotropy like a
+// volume: "fog": { "density": 0.05 }. Nothing gets through infinitely much fog, so the
+// background can't be seen, or light the scene. Use lights and light sources instead.
+//
+// Every object can be moved, rotated and scaled with a "transform", for example
+// "transform": [{ "scale": 2 }, { "rotate_y": 45 }, { "translate": [0, 1, 0] }]. See
+// parse_transform(). A moving object (for motion blur) also has a "transform_end", with the same
+// steps by different amounts, and optionally "transform_times" [start, end] (default [0, 1]). The
+// same mesh file can be placed many times, it's only loaded once.
+//
+// A "mesh" is loaded from a Wavefront OBJ file, with its path relative to the scene file. Faces
+// get their material from the OBJ file's MTL file, or else the mesh's (optional) "material".
+pub struct LoadedScene {
+    pub camera: Camera,
+    pub config: Config,
+    pub world: World<Box<dyn Object>>,
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(io::Error),     // The file couldn't be read.
+    Parse(ParseError), // The file isn't a valid scene.
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneError::Io(error) => write!(formatter, "{}", error),
+            SceneError::Parse(error) => write!(formatter, "{}", error),
+        }
+    }
+}
+
+impl Error for SceneError {}
+
+pub fn load_scene(path: &Path) -> Result<LoadedScene, SceneError> {
+    let text = fs::read_to_string(path).map_err(SceneError::Io)?;
+    let directory = path.parent().unwrap_or(Path::new(""));
+    parse_scene(&text, directory).map_err(SceneError::Parse)
+}
+
+// Relative paths in the scene (like meshes) are relative to the directory.
+pub fn parse_scene(text: &str, directory: &Path) -> Result<LoadedScene, ParseError> {
+    let json = parse_json(text)?;
+    check_keys(
+        &json,
+        &[
+            "background",
+            "camera",
+            "fog",
+            "image",
+            "lights",
+            "materials",
+            "objects",
+        ],
+    )?;
+
+    let mut config = Config::default();
+    if let Some(image) = json.get("image") {
+        parse_image(image, &mut config)?;
+    }
+    if let Some(camera) = json.get("camera") {
+        parse_camera(camera, &mut config)?;
+    }
+    if let Some(background) = json.get("background") {
+        config.background = parse_background(background, directory)?;
+    }
+    if let Some(fog) = json.get("fog") {
+        check_keys(fog, &["albedo", "anisotropy", "density"])?;
+        config.fog = Some(parse_medium(fog)?);
+    }
+    if let Some(lights) = json.get("lights") {
+        for light in lights.as_array()? {
+            config.lights.push(parse_light(light)?);
+        }
+    }
+
+    // Noise textures and volumes are random, but the same between renders.
+    let mut sampler = Sampler::new(config.seed);
+    let mut materials = HashMap::new();
+    if let Some(json_materials) = json.get("materials") {
+        for (name, material) in json_materials.as_object()? {
+            let material = parse_material(material, directory, &mut sampler)?;
+            materials.insert(name.as_str(), material);
+        }
+    }
+
+    let mut objects = Vec::new();
+    let mut meshes = HashMap::new();
+    if let Some(json_objects) = json.get("objects") {
+        for object in json_objects.as_array()? {
+            objects.push(parse_object(
+                object,
+                &materials,
+                None,
+                directory,
+                &mut meshes,
+                &mut sampler,
+            )?);
+        }
+    }
+
+    Ok(LoadedScene {
+        camera: config.camera(),
+        config,
+        world: World::new(objects),
+    })
+}
+
+fn parse_image(image: &Json, config: &mut Config) -> Result<(), ParseError> {
+    check_keys(
+        image,
+        &[
+            "aspect_ratio",
+            "samples_per_pixel",
+            "seed",
+            "threads",
+            "width",
+        ],
+    )?;
+
+    if let Some(width) = image.get("width") {
+        config.image_width = integer(width, u16::MAX as u64)? as u16;
+    }
+    if let Some(aspect_ratio) = image.get("aspect_ratio") {
+        config.aspect_ratio = aspect_ratio.as_number()?;
+    }
+    if let Some(samples_per_pixel) = image.get("samples_per_pixel") {
+        config.samples_per_pixel = integer(samples_per_pixel, u16::MAX as u64)? as u16;
+    }
+    if let Some(seed) = image.get("seed") {
+        config.seed = integer(seed, u64::MAX)?;
+    }
+    if let Some(threads) = image.get("threads") {
+        config.threads = integer(threads, usize::MAX as u64)? as usize;
+    }
+
+    Ok(())
+}
+
+fn parse_camera(camera: &Json, config: &mut Config) -> Result<(), ParseError> {
+    check_keys(
+        camera,
+        &[
+            "aperture",
+            "focus_distance",
+            "look_at",
+            "look_from",
+            "shutter_close",
+            "shutter_open",
+            "vertical_field_of_view",
+        ],
+    )?;
+
+    if let Some(look_from) = camera.get("look_from") {
+        config.camera_look_from = vector(look_from)?;
+    }
+    if let Some(look_at) = camera.get("look_at") {
+        config.camera_look_at = vector(look_at)?;
+    }
+    if let Some(vertical_field_of_view) = camera.get("vertical_field_of_view") {
+        config.camera_vertical_field_of_view = vertical_field_of_view.as_number()?;
+    }
+    if let Some(aperture) = camera.get("aperture") {
+        config.camera_aperture = aperture.as_number()?;
+    }
+    if let Some(focus_distance) = camera.get("focus_distance") {
+        config.camera_focus_distance = focus_distance.as_number()?;
+    }
+    if let Some(shutter_open) = camera.get("shutter_open") {
+        config.camera_shutter_open = shutter_open.as_number()?;
+    }
+    if let Some(shutter_close) = camera.get("shutter_close") {
+        config.camera_shutter_close = shutter_close.as_number()?;
+    }
+
+    Ok(())
+}
+
+fn parse_background(background: &Json, directory: &Path) -> Result<Background, ParseError> {
+    match kind(background)? {
+        "environment" => {
+            check_keys(background, &["path", "rotation", "type"])?;
+            let image = load_image(required(background, "path")?, directory)?;
+            let rotation = optional_number(background, "rotation", 0.0)?;
+            Ok(Background::Environment(Box::new(EnvironmentMap::new(
+                &image, rotation,
+            ))))
+        }
+        "gradient" => {
+            check_keys(background, &["bottom", "top", "type"])?;
+            Ok(Background::Gradient {
+                bottom: vector(required(background, "bottom")?)?,
+                top: vector(required(background, "top")?)?,
+            })
+        }
+        "sky" => {
+            check_keys(
+                background,
+                &["ground_albedo", "sun_direction", "turbidity", "type"],
+            )?;
+            let turbidity = optional_number(background, "turbidity", 3.0)?;
+            if !(1.7..=10.0).contains(&turbidity) {
+                return Err(required(background, "turbidity")?
+                    .error("expected a turbidity between 1.7 and 10"));
+            }
+            let ground_albedo = match background.get("ground_albedo") {
+                Some(ground_albedo) => vector(ground_albedo)?,
+                None => Color::new(0.3, 0.3, 0.3),
+            };
+            Ok(Background::Sky(Box::new(Sky::new(
+                direction(required(background, "sun_direction")?)?,
+                turbidity,
+                ground_albedo,
+            ))))
+        }
+        "solid" => {
+            check_keys(background, &["color", "type"])?;
+            Ok(Background::Solid(vector(required(background, "color")?)?))
+        }
+        other => {
+            Err(required(background, "type")?
+                .error(format!("unknown background type \"{}\"", other)))
+        }
+    }
+}
+
+fn parse_light(light: &Json) -> Result<Box<dyn Light>, ParseError> {
+    match kind(light)? {
+        "directional" => {
+            check_keys(light, &["direction", "irradiance", "type"])?;
+            Ok(Box::new(DirectionalLight::new(
+                direction(required(light, "direction")?)?,
+                vector(required(light, "irradiance")?)?,
+            )))
+        }
+        "point" => {
+            check_keys(light, &["intensity", "position", "type"])?;
+            Ok(Box::new(PointLight::new(
+                vector(required(light, "position")?)?,
+                vector(required(light, "intensity")?)?,
+            )))
+        }
+        "spot" => {
+            check_keys(
+                light,
+                &[
+                    "cone_angle",
+                    "direction",
+                    "falloff_angle",
+                    "intensity",
+                    "position",
+                    "type",
+                ],
+            )?;
+            let json_cone_angle = required(light, "cone_angle")?;
+            let cone_angle = json_cone_angle.as_number()?;
+            if !(cone_angle > 0.0 && cone_angle <= 180.0) {
+                return Err(
+                    json_cone_angle.error("expected an angle above 0 and at most 180 degrees")
+                );
+            }
+            let falloff_angle = optional_number(light, "falloff_angle", cone_angle)?;
+            Ok(Box::new(SpotLight::new(
+                vector(required(light, "position")?)?,
+                direction(required(light, "direction")?)?,
+                (falloff_angle, cone_angle),
+                vector(required(light, "intensity")?)?,
+            )))
+        }
+        other => Err(required(light, "type")?.error(format!("unknown light type \"{}\"", other))),
+    }
+}
+
+// A medium with the same density everywhere, for fog and volumes. The albedo is white if left out.
+// The anisotropy is the asymmetry of the phase function, from -1 (scattering back) to 1 (forward),
+// 0 (the same in every direction) if left out.
+fn parse_medium(medium: &Json) -> Result<HomogeneousMedium, ParseError> {
+    let density = required(medium, "density")?.as_number()?;
+    if density < 0.0 {
+        return Err(required(medium, "density")?.error("expected a density of at least 0"));
+    }
+    let (albedo, phase_function) = parse_scattering(medium)?;
+    Ok(HomogeneousMedium::new(density, albedo, phase_function))
+}
+
+// How the particles of a medium scatter light: the albedo (white if left out), and the phase
+// function from the anisotropy.
+fn parse_scattering(medium: &Json) -> Result<(Color, PhaseFunction), ParseError> {
+    let albedo = match medium.get("albedo") {
+        Some(albedo) => vector(albedo)?,
+        None => Color::new(1.0, 1.0, 1.0),
+    };
+    let anisotropy = optional_number(medium, "anisotropy", 0.0)?;
+    if anisotropy <= -1.0 || anisotropy >= 1.0 {
+        return Err(required(medium, "anisotropy")?
+            .error("expected an anisotropy between -1 and 1 (but not -1 or 1)"));
+    }
+    let phase_function = if anisotropy == 0.0 {
+        PhaseFunction::Isotropic
+    } else {
+        PhaseFunction::HenyeyGreenstein(anisotropy)
+    };
+    Ok((albedo, phase_function))
+}
+
+// A voxel grid, from a file or from noise:
+// - { "type": "file", "path": "smoke.txt" }, or a .raw file with a "resolution": [x, y, z].
+// - { "type": "noise", "resolution": [x, y, z], "scale": 4 }
+fn parse_voxel_grid(
+    grid: &Json,
+    directory: &Path,
+    sampler: &mut Sampler,
+) -> Result<VoxelGrid, ParseError> {
+    match kind(grid)? {
+        "file" => {
+            check_keys(grid, &["path", "resolution", "type"])?;
+            let json_path = required(grid, "path")?;
+            let path = directory.join(json_path.as_string()?);
+            let resolution = match grid.get("resolution") {
+                Some(json_resolution) => Some(resolution(json_resolution)?),
+                None => None,
+            };
+            VoxelGrid::load(&path, resolution).map_err(|error| {
+                json_path.error(format!(
+                    "couldn't load voxel grid {}: {}",
+                    path.display(),
+                    error
+                ))
+            })
+        }
+        "noise" => {
+            check_keys(grid, &["resolution", "scale", "type"])?;
+            let resolution = resolution(required(grid, "resolution")?)?;
+            let scale = optional_number(grid, "scale", 1.0)?;
+            Ok(VoxelGrid::from_noise(resolution, scale, sampler))
+        }
+        other => {
+            Err(required(grid, "type")?.error(format!("unknown voxel grid type \"{}\"", other)))
+        }
+    }
+}
+
+// A signed distance function, a tree of shapes:
+// - { "type": "sphere", "center": [x, y, z], "radius": 1 }
+// - { "type": "box", "center": [x, y, z], "size": [x, y, z] }, and a "rounded_box" with a "radius"
+//   for the edges.
+// - { "type": "torus", "center": [x, y, z], "major_radius": 1, "minor_radius": 0.25 }
+// - { "type": "capsule", "ends": [[x, y, z], [x, y, z]], "radius": 0.5 }
+// - { "type": "mandelbulb", "center": [x, y, z], "scale": 1, "power": 8, "iterations": 12 }
+// - { "type": "smooth_union", "shapes": [...], "smoothness": 0.2 }, and a "smooth_subtraction"
+//   that cuts the other shapes out of the first. The smoothness is 0 (sharp) if left out.
+// - { "type": "repetition", "shape": {...}, "period": [x, y, z] }
+fn parse_distance_function(function: &Json) -> Result<DistanceFunction, ParseError> {
+    let half_size = || -> Result<Vec3, ParseError> {
+        let json_size = required(function, "size")?;
+        let size = vector(json_size)?;
+        if size.x() <= 0.0 || size.y() <= 0.0 || size.z() <= 0.0 {
+            return Err(json_size.error("expected a size above 0 along every axis"));
+        }
+        Ok(size * 0.5)
+    };
+
+    match kind(function)? {
+        "box" => {
+            check_keys(function, &["center", "size", "type"])?;
+            Ok(DistanceFunction::Box {
+                center: vector(required(function, "center")?)?,
+                half_size: half_size()?,
+            })
+        }
+        "capsule" => {
+            check_keys(function, &["ends", "radius", "type"])?;
+            Ok(DistanceFunction::Capsule {
+                ends: corners(required(function, "ends")?)?,
+                radius: positive(function, "radius")?,
+            })
+        }
+        "mandelbulb" => {
+            check_keys(
+                function,
+                &["center", "iterations", "power", "scale", "type"],
+            )?;
+            let iterations = match function.get("iterations") {
+                Some(iterations) => integer(iterations, 100)? as usize,
+                None => 12,
+            };
+            let power = optional_number(function, "power", 8.0)?;
+            if power < 2.0 {
+                return Err(required(function, "power")?.error("expected a power of at least 2"));
+            }
+            let scale = optional_number(function, "scale", 1.0)?;
+            if scale <= 0.0 {
+                return Err(required(function, "scale")?.error("expected a scale above 0"));
+            }
+            Ok(DistanceFunction::Mandelbulb {
+                center: vector(required(function, "center")?)?,
+                iterations,
+                power,
+                scale,
+            })
+        }
+        "repetition" => {
+            check_keys(function, &["period", "shape", "type"])?;
+            let json_period = required(function, "period")?;
+            let period = vector(json_period)?;
+            if period.x() < 0.0 || period.y() < 0.0 || period.z() < 0.0 {
+                return Err(json_period.error("expected a period of at least 0 along every axis"));
+            }
+            Ok(DistanceFunction::Repetition {
+                period,
+                shape: Box::new(parse_distance_function(required(function, "shape")?)?),
+            })
+        }
+        "rounded_box" => {
+            check_keys(function, &["center", "radius", "size", "type"])?;
+            let half_size = half_size()?;
+            let radius = positive(function, "radius")?;
+            if radius > half_size.x().min(half_size.y()).min(half_size.z()) {
+                return Err(required(function, "radius")?
+                    .error("expected a radius of at most half of the size"));
+            }
+            Ok(DistanceFunction::RoundedBox {
+                center: vector(required(function, "center")?)?,
+                half_size,
+                radius,
+            })
+        }
+        style @ ("smooth_subtraction" | "smooth_union") => {
+            check_keys(function, &["shapes", "smoothness", "type"])?;
+            let smoothness = optional_number(function, "smoothness", 0.0)?;
+            if smoothness < 0.0 {
+                return Err(
+                    required(function, "smoothness")?.error("expected a smoothness of at least 0")
+                );
+            }
+
+            // More than two shapes are combined one by one, like for a "csg".
+            let json_shapes = required(function, "shapes")?;
+            let mut shapes = Vec::new();
+            for shape in json_shapes.as_array()? {
+                shapes.push(parse_distance_function(shape)?);
+            }
+            if shapes.len() < 2 {
+                return Err(json_shapes.error("expected at least two shapes to combine"));
+            }
+            let mut shapes = shapes.into_iter();
+            let mut combined = shapes.next().expect("there are at least two shapes");
+            for shape in shapes {
+                let (first, second) = (Box::new(combined), Box::new(shape));
+                combined = match style {
+                    "smooth_subtraction" => DistanceFunction::SmoothSubtraction {
+                        first,
+                        second,
+                        smoothness,
+                    },
+                    _ => DistanceFunction::SmoothUnion {
+                        first,
+                        second,
+                        smoothness,
+                    },
+                };
+            }
+            Ok(combined)
+        }
+        "sphere" => {
+            check_keys(function, &["center", "radius", "type"])?;
+            Ok(DistanceFunction::Sphere {
+                center: vector(required(function, "center")?)?,
+                radius: positive(function, "radius")?,
+            })
+        }
+        "torus" => {
+            check_keys(
+                function,
+                &["center", "major_radius", "minor_radius", "type"],
+            )?;
+            Ok(DistanceFunction::Torus {
+                center: vector(required(function, "center")?)?,
+                major_radius: positive(function, "major_radius")?,
+                minor_radius: positive(function, "minor_radius")?,
+            })
+        }
+        other => Err(required(function, "type")?
+            .error(format!("unknown distance function type \"{}\"", other))),
+    }
+}
+
+fn parse_material(
+    material: &Json,
+    directory: &Path,
+    sampler: &mut Sampler,
+) -> Result<Arc<dyn Material>, ParseError> {
+    match kind(material)? {
+        "dielectric" => {
+            check_keys(
+                material,
+                &[
+                    "absorption",
+                    "color",
+                    "depth",
+                    "refractive_index",
+                    "roughness",
+                    "type",
+                ],
+            )?;
+            let refractive_index = positive(material, "refractive_index")?;
+            let roughness = roughness(material)?;
+            let absorption = absorption(material)?;
+            if roughness > 0.0 {
+                Ok(Arc::new(RoughDielectric::with_absorption(
+                    refractive_index,
+                    roughness,
+                    absorption,
+                )))
+            } else {
+                Ok(Arc::new(Dielectric::with_absorption(
+                    refractive_index,
+                    absorption,
+                )))
+            }
+        }
+        "diffuse" => {
+            check_keys(material, &["albedo", "type"])?;
+            let albedo = parse_texture(required(material, "albedo")?, directory, sampler)?;
+            Ok(Arc::new(Diffuse::from_texture(albedo)))
+        }
+        "diffuse_light" => {
+            check_keys(material, &["emitted", "type"])?;
+            let emitted = vector(required(material, "emitted")?)?;
+            Ok(Arc::new(DiffuseLight::new(emitted)))
+        }
+        "metal" => {
+            check_keys(material, &["eta", "k", "preset", "roughness", "type"])?;
+            let roughness = roughness(material)?;
+            match material.get("preset") {
+                Some(json_preset) => match json_preset.as_string()? {
+                    "aluminium" => Ok(Arc::new(Metal::aluminium(roughness))),
+                    "copper" => Ok(Arc::new(Metal::copper(roughness))),
+                    "gold" => Ok(Arc::new(Metal::gold(roughness))),
+                    "silver" => Ok(Arc::new(Metal::silver(roughness))),
+                    other => Err(json_preset.error(format!("unknown metal \"{}\"", other))),
+                },
+                None => Ok(Arc::new(Metal::new(
+                    vector(required(material, "eta")?)?,
+                    vector(required(material, "k")?)?,
+                    roughness,
+                ))),
+            }
+        }
+        "reflective" => {
+            check_keys(material, &["albedo", "fuzz", "type"])?;
+            let albedo = parse_texture(required(material, "albedo")?, directory, sampler)?;
+            let fuzz = optional_number(material, "fuzz", 0.0)?;
+            Ok(Arc::new(Reflective::from_texture(albedo, fuzz)))
+        }
+        other => {
+            Err(required(material, "type")?.error(format!("unknown material type \"{}\"", other)))
+        }
+    }
+}
+
+// A texture, or just a color.
+fn parse_texture(
+    texture: &Json,
+    directory: &Path,
+    sampler: &mut Sampler,
+) -> Result<Arc<dyn Texture>, ParseError> {
+    if texture.as_array().is_ok() {
+        return Ok(Arc::new(SolidColor::new(vector(texture)?)));
+    }
+
+    match kind(texture)? {
+        "checker" => {
+            check_keys(texture, &["even", "odd", "size", "type"])?;
+            let even = parse_texture(required(texture, "even")?, directory, sampler)?;
+            let odd = parse_texture(required(texture, "odd")?, directory, sampler)?;
+            let size = optional_number(texture, "size", 1.0)?;
+            Ok(Arc::new(Checker::new(even, odd, size)))
+        }
+        "image" => {
+            check_keys(texture, &["path", "type"])?;
+            let image = load_image(required(texture, "path")?, directory)?;
+            Ok(Arc::new(ImageTexture::new(&image)))
+        }
+        style @ ("marble" | "noise" | "turbulence") => {
+            check_keys(texture, &["color", "scale", "type"])?;
+            let style = match style {
+                "marble" => NoiseStyle::Marble,
+                "noise" => NoiseStyle::Noise,
+                _ => NoiseStyle::Turbulence,
+            };
+            let scale = optional_number(texture, "scale", 1.0)?;
+            let color = match texture.get("color") {
+                Some(color) => vector(color)?,
+                None => Vec3::new(1.0, 1.0, 1.0),
+            };
+            Ok(Arc::new(NoiseTexture::new(style, scale, color, sampler)))
+        }
+        "solid" => {
+            check_keys(texture, &["color", "type"])?;
+            Ok(Arc::new(SolidColor::new(vector(required(
+                texture, "color",
+            )?)?)))
+        }
+        other => {
+            Err(required(texture, "type")?.error(format!("unknown texture type \"{}\"", other)))
+        }
+    }
+}
+
+// An image file, with its path relative to the scene file.
+fn load_image(json_path: &Json, directory: &Path) -> Result<Framebuffer, ParseError> {
+    let path = directory.join(json_path.as_string()?);
+    read_image(&path).map_err(|message| {
+        json_path.error(format!(
+            "couldn't load image {}: {}",
+            path.display(),
+            message
+        ))
+    })
+}
+
+// Meshes that are already loaded, by path and material. Placing the same mesh many times shares
+// its triangles.
+type LoadedMeshes = HashMap<(PathBuf, Option<String>), Arc<TriangleMesh>>;
+
+// Objects without a "material" get the default material, if there is one. Only the boundary of a
+// volume has one, its material is never seen.
+fn parse_object(
+    object: &Json,
+    materials: &HashMap<&str, Arc<dyn Material>>,
+    default_material: Option<&Arc<dyn Material>>,
+    directory: &Path,
+    meshes: &mut LoadedMeshes,
+    sampler: &mut Sampler,
+) -> Result<Box<dyn Object>, ParseError> {
+    let shape = parse_shape(
+        object,
+        materials,
+        default_material,
+        directory,
+        meshes,
+        sampler,
+    )?;
+    let Some(json_transform) = object.get("transform") else {
+        return Ok(shape);
+    };
+    let steps = parse_transform(json_transform)?;
+
+    // A moving object changes from the transform to the end transform, between two times.
+    let Some(json_transform_end) = object.get("transform_end") else {
+        let (object_to_world, _) = matrices_of_steps(&steps);
+        return match Transformed::new(shape, object_to_world) {
+            Some(transformed) => Ok(Box::new(transformed)),
+            None => Err(json_transform.error("the transform can't be undone (inverted)")),
+        };
+    };
+    let end_steps = parse_transform(json_transform_end)?;
+    let times = times(object.get("transform_times"))?;
+    match Transformed::moving(shape, (steps, end_steps), times) {
+        Some(transformed) => Ok(Box::new(transformed)),
+        None => Err(json_transform_end
+            .error("the end transform must have the same kinds of steps, in the same order")),
+    }
+}
+
+// A transform is a list of steps, applied in order:
+// - { "scale": 2 } or { "scale": [x, y, z] }
+// - { "rotate_x": degrees }, and the same for "rotate_y" and "rotate_z".
+// - { "rotate": { "axis": [x, y, z], "degrees": 45 } }
+// - { "translate": [x, y, z] }
+fn parse_transform(transform: &Json) -> Result<Vec<TransformStep>, ParseError> {
+    let mut steps = Vec::new();
+    for step in transform.as_array()? {
+        let (name, value) = match step.as_object()? {
+            [(name, value)] => (name.as_str(), value),
+            _ => {
+                return Err(
+                    step.error("expected a single transform, like { \"translate\": [x, y, z] }")
+                )
+            }
+        };
+
+        let rotation = |axis: Vec3| -> Result<TransformStep, ParseError> {
+            let degrees = value.as_number()?;
+            Ok(TransformStep::Rotation { axis, degrees })
+        };
+        steps.push(match name {
+            "rotate" => {
+                check_keys(value, &["axis", "degrees"])?;
+                TransformStep::Rotation {
+                    axis: direction(required(value, "axis")?)?,
+                    degrees: required(value, "degrees")?.as_number()?,
+                }
+            }
+            "rotate_x" => rotation(Vec3::new(1.0, 0.0, 0.0))?,
+            "rotate_y" => rotation(Vec3::new(0.0, 1.0, 0.0))?,
+            "rotate_z" => rotation(Vec3::new(0.0, 0.0, 1.0))?,
+            "scale" => {
+                let factors = match value.as_number() {
+                    Ok(factor) => Vec3::new(factor, factor, factor),
+                    Err(_) => vector(value)?,
+                };
+                if factors.x() == 0.0 || factors.y() == 0.0 || factors.z() == 0.0 {
+                    return Err(value.error("can't scale by zero"));
+                }
+                TransformStep::Scaling(factors)
+            }
+            "translate" => TransformStep::Translation(vector(value)?),
+            other => {
+                let transforms = [
+                    "rotate",
+                    "rotate_x",
+                    "rotate_y",
+                    "rotate_z",
+                    "scale",
+                    "translate",
+                ];
+                return Err(step.error(format!(
+                    "unknown transform \"{}\", expected one of: {}",
+                    other,
+                    transforms.join(", ")
+                )));
+            }
+        });
+    }
+    Ok(steps)
+}
+
+fn parse_shape(
+    object: &Json,
+    materials: &HashMap<&str, Arc<dyn Material>>,
+    default_material: Option<&Arc<dyn Material>>,
+    directory: &Path,
+    meshes: &mut LoadedMeshes,
+    sampler: &mut Sampler,
+) -> Result<Box<dyn Object>, ParseError> {
+    match kind(object)? {
+        "csg" => {
+            check_keys(
+                object,
+                &[
+                    "objects",
+                    "operation",
+                    "transform",
+                    "transform_end",
+                    "transform_times",
+                    "type",
+                ],
+            )?;
+            let json_operation = required(object, "operation")?;
+            let operation = match json_operation.as_string()? {
+                "difference" => CsgOperation::Difference,
+                "intersection" => CsgOperation::Intersection,
+                "union" => CsgOperation::Union,
+                other => {
+                    return Err(json_operation.error(format!(
+                        "unknown operation \"{}\", expected difference, intersection or union",
+                        other
+                    )))
+                }
+            };
+
+            // More than two objects are combined one by one, so a difference cuts all the others
+            // out of the first.
+            let json_objects = required(object, "objects")?;
+            let mut solids = Vec::new();
+            for solid in json_objects.as_array()? {
+                solids.push(parse_object(
+                    solid,
+                    materials,
+                    default_material,
+                    directory,
+                    meshes,
+                    sampler,
+                )?);
+            }
+            if solids.len() < 2 {
+                return Err(json_objects.error("expected at least two objects to combine"));
+            }
+            let mut solids = solids.into_iter();
+            let mut combined = solids.next().expect("there are at least two objects");
+            for solid in solids {
+                combined = Box::new(Csg::new(combined, solid, operation));
+            }
+            Ok(combined)
+        }
+        "cuboid" => {
+            check_keys(
+                object,
+                &[
+                    "corners",
+                    "material",
+                    "transform",
+                    "transform_end",
+                    "transform_times",
+                    "type",
+                ],
+            )?;
+            let [a, b] = corners(required(object, "corners")?)?;
+            let material = object_material(object, materials, default_material)?;
+            Ok(Box::new(Cuboid::new(a, b, material)))
+        }
+        "disk" => {
+            check_keys(
+                object,
+                &[
+                    "center",
+                    "material",
+                    "normal",
+                    "radius",
+                    "transform",
+                    "transform_end",
+                    "transform_times",
+                    "type",
+                ],
+            )?;
+            let center = vector(required(object, "center")?)?;
+            let normal = direction(required(object, "normal")?)?;
+            let radius = positive(object, "radius")?;
+            let material = object_material(object, materials, default_material)?;
+            Ok(Box::new(Disk::new(center, normal, radius, material)))
+        }
+        "distance_field" => {
+            check_keys(
+                object,
+                &[
+                    "material",
+                    "shape",
+                    "transform",
+                    "transform_end",
+                    "transform_times",
+                    "type",
+                ],
+            )?;
+            let function = parse_distance_function(required(object, "shape")?)?;
+            let material = object_material(object, materials, default_material)?;
+            Ok(Box::new(DistanceField::new(function, material)))
+        }
+        "grid_volume" => {
+            check_keys(
+                object,
+                &[
+                    "albedo",
+                    "anisotropy",
+                    "corners",
+                    "density",
+                    "density_scale",
+                    "emission",
+                    "emission_color",
+                    "type",
+                ],
+            )?;
+            let json_corners = required(object, "corners")?;
+            let [a, b] = corners(json_corners)?;
+            if a.x() == b.x() || a.y() == b.y() || a.z() == b.z() {
+                return Err(json_corners.error("the corners of a grid volume can't be in a plane"));
+            }
+            let mut density = parse_voxel_grid(required(object, "density")?, directory, sampler)?;
+            let density_scale = optional_number(object, "density_scale", 1.0)?;
+            if density_scale < 0.0 {
+                return Err(required(object, "density_scale")?
+                    .error("expected a density scale of at least 0"));
+            }
+            density.scale(density_scale);
+            let (albedo, phase_function) = parse_scattering(object)?;
+
+            let medium = match object.get("emission") {
+                Some(json_emission) => {
+                    let emission = parse_voxel_grid(json_emission, directory, sampler)?;
+                    let emission_color = match object.get("emission_color") {
+                        Some(color) => vector(color)?,
+                        None => Color::new(1.0, 1.0, 1.0),
+                    };
+                    GridMedium::with_emission(
+                        [a, b],
+                        density,
+                        albedo,
+                        phase_function,
+                        emission,
+                        emission_color,
+                    )
+                }
+                None => GridMedium::new([a, b], density, albedo, phase_function),
+            };
+            let boundary = Cuboid::new(a, b, Arc::new(Diffuse::new(Vec3::new(0.8, 0.8, 0.8))));
+            Ok(Box::new(Volume::new(boundary, Arc::new(medium))))
+        }
+        "mesh" => {
+            check_keys(
+                object,
+                &[
+                    "material",
+                    "path",
+                    "transform",
+                    "transform_end",
+                    "transform_times",
+                    "type",
+                ],
+            )?;
+            let json_path = required(object, "path")?;
+            let path = directory.join(json_path.as_string()?);
+            let material_name = match object.get("material") {
+                Some(name) => Some(name.as_string()?.to_string()),
+                None => None,
+            };
+            let key = (path.clone(), material_name);
+            if let Some(mesh) = meshes.get(&key) {
+                return Ok(Box::new(mesh.clone()));
+            }
+
+            let default_material = match object.get("material") {
+                Some(name) => material(name, materials)?,
+                None => Arc::new(Diffuse::new(Vec3::new(0.8, 0.8, 0.8))),
+            };
+            let mesh = load_obj(&path, default_material).map_err(|error| {
+                json_path.error(format!("couldn't load mesh {}: {}", path.display(), error))
+            })?;
+            let mesh = Arc::new(mesh);
+            meshes.insert(key, mesh.clone());
+            Ok(Box::new(mesh))
+        }
+        "moving_sphere" => {
+            check_keys(
+                object,
+                &[
+                    "centers",
+                    "material",
+                    "radius",
+                    "times",
+                    "transform",
+                    "transform_end",
+                    "transform_times",
+                    "type",
+                ],
+            )?;
+            let centers = corners(required(object, "centers")?)?;
+            let times = times(object.get("times"))?;
+            let radius = required(object, "radius")?.as_number()?;
+            let material = object_material(object, materials, default_material)?;
+            Ok(Box::new(MovingSphere::new(
+                (centers[0], centers[1]),
+                times,
+                radius,
+                material,
+            )))
+        }
+        "plane" => {
+            check_keys(
+                object,
+                &[
+                    "material",
+                    "normal",
+                    "point",
+                    "transform",
+                    "transform_end",
+                    "transform_times",
+                    "type",
+                ],
+            )?;
+            let point = vector(required(object, "point")?)?;
+            let normal = direction(required(object, "normal")?)?;
+            let material = object_material(object, materials, default_material)?;
+            Ok(Box::new(Plane::new(point, normal, material)))
+        }
+        "quad" => {
+            check_keys(
+                object,
+                &[
+                    "corner",
+                    "material",
+                    "transform",
+                    "transform_end",
+                    "transform_times",
+                    "type",
+                    "u",
+                    "v",
+                ],
+            )?;
+            let corner = vector(required(object, "corner")?)?;
+            let edge_u = vector(required(object, "u")?)?;
+            let edge_v = vector(required(object, "v")?)?;
+            if edge_u.cross(edge_v).is_zero() {
+                return Err(object.error("the edges u and v of a quad can't be parallel"));
+            }
+            let material = object_material(object, materials, default_material)?;
+            Ok(Box::new(Quad::new(corner, edge_u, edge_v, material)))
+        }
+        "rectangle" => {
+            check_keys(
+                object,
+                &[
+                    "corners",
+                    "material",
+                    "transform",
+                    "transform_end",
+                    "transform_times",
+                    "type",
+                ],
+            )?;
+            let json_corners = required(object, "corners")?;
+            let [a, b] = corners(json_corners)?;
+            let material = object_material(object, materials, default_material)?;
+            match Quad::axis_aligned(a, b, material) {
+                Some(rectangle) => Ok(Box::new(rectangle)),
+                None => Err(json_corners.error(
+                    "the corners of a rectangle must have one coordinate in common, use a quad otherwise",
+                )),
+            }
+        }
+        "sphere" => {
+            check_keys(
+                object,
+                &[
+                    "center",
+                    "material",
+                    "radius",
+                    "transform",
+                    "transform_end",
+                    "transform_times",
+                    "type",
+                ],
+            )?;
+            let center = vector(required(object, "center")?)?;
+            let radius = required(object, "radius")?.as_number()?;
+            let material = object_material(object, materials, default_material)?;
+            Ok(Box::new(Sphere::new(center, radius, material)))
+        }
+        "triangle" => {
+            check_keys(
+                object,
+                &[
+                    "material",
+                    "transform",
+                    "transform_end",
+                    "transform_times",
+                    "type",
+                    "vertices",
+                ],
+            )?;
+            let json_vertices = required(object, "vertices")?;
+            let vertices = match json_vertices.as_array()? {
+                [a, b, c] => [vector(a)?, vector(b)?, vector(c)?],
+                _ => return Err(json_vertices.error("expected an array of three vertices")),
+            };
+            let material = object_material(object, materials, default_material)?;
+            Ok(Box::new(Triangle::from_vertices(vertices, material)))
+        }
+        "volume" => {
+            check_keys(
+                object,
+                &[
+                    "albedo",
+                    "anisotropy",
+                    "boundary",
+                    "density",
+                    "transform",
+                    "transform_end",
+                    "transform_times",
+                    "type",
+                ],
+            )?;
+            let medium = parse_medium(object)?;
+
+            // Gray, like a mesh without materials. A mesh is loaded once, whether it's a boundary
+            // or not, so it has to look the same either way.
+            let boundary_material: Arc<dyn Material> =
+                Arc::new(Diffuse::new(Vec3::new(0.8, 0.8, 0.8)));
+            let boundary = parse_object(
+                required(object, "boundary")?,
+                materials,
+                Some(&boundary_material),
+                directory,
+                meshes,
+                sampler,
+            )?;
+            Ok(Box::new(Volume::new(boundary, Arc::new(medium))))
+        }
+        other => Err(required(object, "type")?.error(format!("unknown object type \"{}\"", other))),
+    }
+}
+
+// Helpers for reading values.
+
+// Objects in the scene are told apart by their "type" key.
+fn kind(json: &Json) -> Result<&str, ParseError> {
+    required(json, "type")?.as_string()
+}
+
+// Misspelled keys would otherwise be silently ignored.
+fn check_keys(json: &Json, allowed_keys: &[&str]) -> Result<(), ParseError> {
+    for (key, value) in json.as_object()? {
+        if !allowed_keys.contains(&key.as_str()) {
+            return Err(value.error(format!(
+                "unknown key \"{}\", expected one of: {}",
+                key,
+                allowed_keys.join(", ")
+            )));
+        }
+    }
+    Ok(())
+}
+
+// Two points, like the opposite corners of a box.
+fn corners(json: &Json) -> Result<[Vec3; 2], ParseError> {
+    match json.as_array()? {
+        [a, b] => Ok([vector(a)?, vector(b)?]),
+        _ => Err(json.error("expected an array of two points")),
+    }
+}
+
+// A vector that isn't zero, since it's normalized.
+fn direction(json: &Json) -> Result<Vec3, ParseError> {
+    let direction = vector(json)?;
+    if direction.is_zero() {
+        return Err(json.error("expected a direction, not [0, 0, 0]"));
+    }
+    Ok(direction)
+}
+
+fn integer(json: &Json, maximum: u64) -> Result<u64, ParseError> {
+    let number = json.as_number()?;
+    if number < 0.0 || number.fract() != 0.0 || number > maximum as f64 {
+        return Err(json.error(format!("expected a whole number between 0 and {}", maximum)));
+    }
+    Ok(number as u64)
+}
+
+fn material(
+    json: &Json,
+    materials: &HashMap<&str, Arc<dyn Material>>,
+) -> Result<Arc<dyn Material>, ParseError> {
+    let name = json.as_string()?;
+    materials
+        .get(name)
+        .cloned()
+        .ok_or_else(|| json.error(format!("unknown material \"{}\"", name)))
+}
+
+// The material of an object, by name, or the default if it has none.
+fn object_material(
+    object: &Json,
+    materials: &HashMap<&str, Arc<dyn Material>>,
+    default_material: Option<&Arc<dyn Material>>,
+) -> Result<Arc<dyn Material>, ParseError> {
+    match (object.get("material"), default_material) {
+        (None, Some(default_material)) => Ok(default_material.clone()),
+        _ => material(required(object, "material")?, materials),
+    }
+}
+
+// A number that has to be above 0, like a radius or a refractive index.
+fn positive(json: &Json, key: &str) -> Result<f64, ParseError> {
+    let json_number = required(json, key)?;
+    let number = json_number.as_number()?;
+    if number.is_nan() || number <= 0.0 {
+        return Err(json_number.error(format!("expected a {} above 0", key.replace('_', " "))));
+    }
+    Ok(number)
+}
+
+fn optional_number(json: &Json, key: &str, default: f64) -> Result<f64, ParseError> {
+    match json.get(key) {
+        Some(number) => number.as_number(),
+        None => Ok(default),
+    }
+}
+
+// The absorption of a dielectric, either as it is, or from the color after a depth (1 if left
+// out). Clear if left out.
+fn absorption(material: &Json) -> Result<Color, ParseError> {
+    if let Some(json_absorption) = material.get("absorption") {
+        if material.get("color").is_some() {
+            return Err(json_absorption.error("expected either an absorption or a color, not both"));
+        }
+        let absorption = vector(json_absorption)?;
+        if absorption.x() < 0.0 || absorption.y() < 0.0 || absorption.z() < 0.0 {
+            return Err(json_absorption.error("expected an absorption of at least 0"));
+        }
+        return Ok(absorption);
+    }
+
+    let Some(json_color) = material.get("color") else {
+        return Ok(Color::zero());
+    };
+    let color = vector(json_color)?;
+    let is_in_range = |component: f64| component > 0.0 && component <= 1.0;
+    if !(is_in_range(color.x()) && is_in_range(color.y()) && is_in_range(color.z())) {
+        return Err(json_color.error("expected a color with components above 0 and at most 1"));
+    }
+    let depth = optional_number(material, "depth", 1.0)?;
+    if depth <= 0.0 {
+        return Err(required(material, "depth")?.error("expected a depth above 0"));
+    }
+    Ok(Dielectric::absorption_from_color(color, depth))
+}
+
+// The roughness of a material, from 0 (smooth) to 1. 0 if left out.
+fn roughness(material: &Json) -> Result<f64, ParseError> {
+    let roughness = optional_number(material, "roughness", 0.0)?;
+    if !(0.0..=1.0).contains(&roughness) {
+        return Err(required(material, "roughness")?.error("expected a roughness between 0 and 1"));
+    }
+    Ok(roughness)
+}
+
+// Start and end times of a motion. From 0 to 1 if left out.
+fn times(json: Option<&Json>) -> Result<(f64, f64), ParseError> {
+    let Some(json) = json else {
+        return Ok((0.0, 1.0));
+    };
+    match json.as_array()? {
+        [start, end] if start.as_number()? <= end.as_number()? => {
+            Ok((start.as_number()?, end.as_number()?))
+        }
+        _ => Err(json.error("expected two times, [start, end], with start <= end")),
+    }
+}
+
+// The number of voxels along each axis of a voxel grid, at least one, and not too many in all
+// (see MAX_VOXELS).
+fn resolution(json: &Json) -> Result<[usize; 3], ParseError> {
+    let resolution = match json.as_array()? {
+        [x, y, z] => [integer(x, 4096)?, integer(y, 4096)?, integer(z, 4096)?],
+        _ => return Err(json.error("expected an array of three whole numbers")),
+    };
+    if resolution.contains(&0) {
+        return Err(json.error("expected at least one voxel along every axis"));
+    }
+    if resolution.iter().product::<u64>() > MAX_VOXELS as u64 {
+        return Err(json.error(format!(
+            "expected at most {} voxels in all, not {}",
+            MAX_VOXELS,
+            resolution.iter().product::<u64>()
+        )));
+    }
+    Ok(resolution.map(|voxels| voxels as usize))
+}
+
+fn required<'a>(json: &'a Json, key: &str) -> Result<&'a Json, ParseError> {
+    json.as_object()?;
+    json.get(key)
+        .ok_or_else(|| json.error(format!("missing key \"{}\"", key)))
+}
+
+// Vectors, points and colors are all written as arrays of three numbers.
+fn vector(json: &Json) -> Result<Vec3, ParseError> {
+    match json.as_array()? {
+        [x, y, z] => Ok(Vec3::new(x.as_number()?, y.as_number()?, z.as_number()?)),
+        _ => Err(json.error("expected an array of three numbers")),
+    }
+}
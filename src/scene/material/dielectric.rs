@@ -1,5 +1,4 @@
-use rand::random;
-
+use crate::sampler::Sampler;
 use crate::scene::material::material::Material;
 use crate::scene::material::reflective::Reflective;
 use crate::tracer::hit_point::HitPoint;
@@ -7,13 +6,37 @@ use crate::tracer::ray::{Ray, ScatteredRay};
 use crate::vec3::{Color, Vec3};
 
 // Dielectric material can reflect light and at the same time let light pass through.
+//
+// Colored glass absorbs some of the light going through it, more of some colors than others. The
+// further the light goes, the more is absorbed: what's left falls off exponentially with the
+// distance (the Beer-Lambert law). That happens inside, between the surfaces, so the tracer takes
+// care of it (see absorption()).
 pub struct Dielectric {
+    absorption: Color, // Per unit of distance, for red, green and blue. Zero for clear glass.
     refractive_index: f64, // Describes how fast light travels through the material. This determines how much the path of light is bent when entering the material ("refracted").
 }
 
 impl Dielectric {
     pub fn new(refractive_index: f64) -> Dielectric {
-        Dielectric { refractive_index }
+        Dielectric::with_absorption(refractive_index, Color::zero())
+    }
+
+    pub fn with_absorption(refractive_index: f64, absorption: Color) -> Dielectric {
+        Dielectric {
+            absorption,
+            refractive_index,
+        }
+    }
+
+    // The absorption that leaves the color (the fraction of red, green and blue light that's left)
+    // after light has gone the depth (a distance) through the material. Easier to pick than the
+    // absorption itself. Colors can't be zero: no depth of glass absorbs all light.
+    pub fn absorption_from_color(color: Color, depth: f64) -> Color {
+        Color::new(
+            -color.x().ln() / depth,
+            -color.y().ln() / depth,
+            -color.z().ln() / depth,
+        )
     }
 
     // Snell's law is a formula used to describe the relationship between angles of incidence and
@@ -23,7 +46,7 @@ impl Dielectric {
         let perpendicular_ray = etai_over_etat * (ray_direction + (cos_theta * surface_normal));
         let parallel_ray =
             surface_normal * -((1.0 - perpendicular_ray.length_squared()).abs().sqrt());
-        return perpendicular_ray + parallel_ray;
+        perpendicular_ray + parallel_ray
     }
 
     // Real glass has reflectivity that varies with angle; look at a window at a steep angle and it
@@ -32,13 +55,37 @@ impl Dielectric {
     pub fn schlick(cosine: f64, refractive_index: f64) -> f64 {
         let r0 = (1.0 - refractive_index) / (1.0 + refractive_index);
         let r0 = r0 * r0;
-        return r0 + ((1.0 - r0) * (1.0 - cosine).powi(5));
+        r0 + ((1.0 - r0) * (1.0 - cosine).powi(5))
+    }
+
+    // The exact reflectivity, from the Fresnel equations, for unpolarized light (the average of the
+    // two polarizations, s and p). The cosine is of the angle between the ray and the normal, on
+    // the side the ray comes from. The relative refractive index is of the other side over this
+    // side (1.5 going from air into glass). All of the light is reflected when it can't refract.
+    pub fn fresnel(cosine: f64, relative_refractive_index: f64) -> f64 {
+        let eta = relative_refractive_index;
+        let cos_i = cosine.clamp(0.0, 1.0);
+        let sin_t_squared = (1.0 - (cos_i * cos_i)) / (eta * eta);
+        if sin_t_squared >= 1.0 {
+            return 1.0; // Total internal reflection.
+        }
+        let cos_t = (1.0 - sin_t_squared).sqrt();
+
+        let r_s = (cos_i - (eta * cos_t)) / (cos_i + (eta * cos_t));
+        let r_p = ((eta * cos_i) - cos_t) / ((eta * cos_i) + cos_t);
+        ((r_s * r_s) + (r_p * r_p)) / 2.0
     }
 }
 
 impl Material for Dielectric {
-    fn scatter(&self, ray: &Ray, hit_point: &HitPoint) -> ScatteredRay {
-        let light_attenuation = Color::new(1.0, 1.0, 1.0); // Glass doesn't absorb light.
+    // Glass scatters in exact directions, it's specular.
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_point: &HitPoint,
+        sampler: &mut Sampler,
+    ) -> Option<ScatteredRay> {
+        let light_attenuation = Color::new(1.0, 1.0, 1.0); // The surface doesn't absorb light.
         let normalized_ray_direction = ray.direction().unit_vector();
         let point = hit_point.point();
         let surface_normal = hit_point.surface_normal();
@@ -57,7 +104,12 @@ impl Material for Dielectric {
         // if the glass material can't refract the ray then it must reflect it.
         if (etai_over_etat * sin_theta) > 1.0 {
             let scatter_direction = Reflective::reflect(normalized_ray_direction, surface_normal);
-            return ScatteredRay::new(point, scatter_direction, light_attenuation);
+            return Some(ScatteredRay::specular(
+                point,
+                scatter_direction,
+                ray.time(),
+                light_attenuation,
+            ));
         }
 
         // Both reflection and refraction occurs for dielectric materials. But in this implementation
@@ -66,13 +118,20 @@ impl Material for Dielectric {
         // that here by randomly reflecting some rays and refracting others. Depending on the
         // reflective coefficient.
         let reflect_probability = Dielectric::schlick(cos_theta, etai_over_etat);
-        if random::<f64>() < reflect_probability {
-            let scatter_direction = Reflective::reflect(normalized_ray_direction, surface_normal);
-            return ScatteredRay::new(point, scatter_direction, light_attenuation);
+        let scatter_direction = if sampler.next_f64() < reflect_probability {
+            Reflective::reflect(normalized_ray_direction, surface_normal)
         } else {
-            let scatter_direction =
-                Dielectric::refract(normalized_ray_direction, surface_normal, etai_over_etat);
-            return ScatteredRay::new(point, scatter_direction, light_attenuation);
-        }
+            Dielectric::refract(normalized_ray_direction, surface_normal, etai_over_etat)
+        };
+        Some(ScatteredRay::specular(
+            point,
+            scatter_direction,
+            ray.time(),
+            light_attenuation,
+        ))
+    }
+
+    fn absorption(&self) -> Color {
+        self.absorption
     }
 }
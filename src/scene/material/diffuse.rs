@@ -1,4 +1,10 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::sampler::Sampler;
 use crate::scene::material::material::Material;
+use crate::scene::texture::solid_color::SolidColor;
+use crate::scene::texture::texture::Texture;
 use crate::tracer::hit_point::HitPoint;
 use crate::tracer::ray::{Ray, ScatteredRay};
 use crate::vec3::{Color, Vec3};
@@ -20,11 +26,16 @@ use crate::vec3::{Color, Vec3};
 // Ultimately, multiplying incoming light with how the object reflects it, in given angles, is what
 // gives the object its perceived color.
 pub struct Diffuse {
-    albedo: Color, // "Whiteness". Measure of light reflection for a surface.
+    albedo: Arc<dyn Texture>, // "Whiteness". Measure of light reflection for a surface.
 }
 
 impl Diffuse {
     pub fn new(albedo: Color) -> Diffuse {
+        Diffuse::from_texture(Arc::new(SolidColor::new(albedo)))
+    }
+
+    // The albedo varies over the surface.
+    pub fn from_texture(albedo: Arc<dyn Texture>) -> Diffuse {
         Diffuse { albedo }
     }
 }
@@ -40,7 +51,7 @@ impl Material for Diffuse {
     // // degrees).
     // let point = hit_point.point();
     // let surface_normal = hit_point.surface_normal();
-    // let random_bounce_ray_target = point + surface_normal + Vec3::random_in_unit_sphere(); // Approximation of Lambertian reflection.
+    // let random_bounce_ray_target = point + surface_normal + Vec3::random_in_unit_sphere(sampler); // Approximation of Lambertian reflection.
 
     // // Drop-in replacement for random_in_unit_sphere(), which is more intuitive than Lambertian
     // // reflection. This achieves uniform scattering direction for all angles away from the hit point,
@@ -48,7 +59,7 @@ impl Material for Diffuse {
     // // method before adopting Lambertian diffuse.
     // let point = hit_point.point();
     // let surface_normal = hit_point.surface_normal();
-    // let random_bounce_ray_target = point + Vec3::random_in_hemisphere(surface_normal)
+    // let random_bounce_ray_target = point + Vec3::random_in_hemisphere(surface_normal, sampler)
 
     // Drop-in replacement for random_in_unit_sphere(), which achieves true Lambertian reflection.
     // It's higher probability for ray scattering close to the surface normal, but the distribution
@@ -58,9 +69,48 @@ impl Material for Diffuse {
     // scattering towards the surface normal. This means that for diffuse objects, they will appear
     // lighter. For its shadows, less rays bounce straight up, so the surface underneath will appear
     // brighter.
-    fn scatter(&self, _ray: &Ray, hit_point: &HitPoint) -> ScatteredRay {
+    //
+    // The random unit vector can be (almost) opposite to the normal. Then the direction is (almost)
+    // zero, which would turn into infinities and NaNs later on. Scatter along the normal instead.
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_point: &HitPoint,
+        sampler: &mut Sampler,
+    ) -> Option<ScatteredRay> {
         let point = hit_point.point();
-        let scatter_direction = hit_point.surface_normal() + Vec3::random_unit_vector(); // True Lambertian reflection.
-        return ScatteredRay::new(point, scatter_direction, self.albedo);
+        let mut scatter_direction = hit_point.surface_normal() + Vec3::random_unit_vector(sampler); // True Lambertian reflection.
+        if scatter_direction.length_squared() < 1e-16 {
+            scatter_direction = hit_point.surface_normal();
+        }
+
+        let albedo = self
+            .albedo
+            .value(hit_point.u(), hit_point.v(), hit_point.point());
+        let pdf = self.pdf(ray, hit_point, scatter_direction.unit_vector());
+        Some(ScatteredRay::new(
+            point,
+            scatter_direction,
+            ray.time(),
+            albedo,
+            pdf,
+        ))
+    }
+
+    // Light is scattered equally in all directions: the BSDF is albedo/pi. (The pi makes sure no
+    // more light leaves the surface than arrives, when summed over the hemisphere.)
+    fn evaluate(&self, _ray: &Ray, hit_point: &HitPoint, direction: Vec3) -> Color {
+        let cosine = direction.dot(hit_point.surface_normal()).max(0.0);
+        let albedo = self
+            .albedo
+            .value(hit_point.u(), hit_point.v(), hit_point.point());
+        albedo * (cosine / PI)
+    }
+
+    // The true Lambertian reflection above picks directions close to the normal more often, in
+    // proportion to the cosine: cosine/pi. Scattering with it, the cosine and the pi of evaluate()
+    // cancel out, which is why scatter() attenuates with only the albedo.
+    fn pdf(&self, _ray: &Ray, hit_point: &HitPoint, direction: Vec3) -> f64 {
+        direction.dot(hit_point.surface_normal()).max(0.0) / PI
     }
 }
@@ -0,0 +1,42 @@
+use crate::sampler::Sampler;
+use crate::scene::material::material::Material;
+use crate::tracer::hit_point::HitPoint;
+use crate::tracer::ray::{Ray, ScatteredRay};
+use crate::vec3::Color;
+
+// A diffuse light (area light) emits the same amount of light in every direction from its surface.
+// Any object can become a light source by giving it this material. Colors brighter than 1.0 are
+// fine, and usually needed, for a light to illuminate its surroundings.
+pub struct DiffuseLight {
+    emitted: Color,
+}
+
+impl DiffuseLight {
+    pub fn new(emitted: Color) -> DiffuseLight {
+        DiffuseLight { emitted }
+    }
+}
+
+impl Material for DiffuseLight {
+    // A light source doesn't reflect any light, it absorbs everything that hits it.
+    fn scatter(
+        &self,
+        _ray: &Ray,
+        _hit_point: &HitPoint,
+        _sampler: &mut Sampler,
+    ) -> Option<ScatteredRay> {
+        None
+    }
+
+    // Light is only emitted from the front (outside) of the surface.
+    fn emitted(&self, hit_point: &HitPoint) -> Color {
+        if hit_point.front_facing() {
+            self.emitted
+        } else {
+            Color::zero()
+        }
+    }
+    fn emits_light(&self) -> bool {
+        true
+    }
+}
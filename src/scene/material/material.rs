@@ -1,6 +1,73 @@
+use crate::sampler::Sampler;
+use crate::scene::medium::medium::Medium;
 use crate::tracer::hit_point::HitPoint;
 use crate::tracer::ray::{Ray, ScatteredRay};
+use crate::vec3::{Color, Vec3};
 
-pub trait Material {
-    fn scatter(&self, ray: &Ray, hit_point: &HitPoint) -> ScatteredRay;
+// How a material scatters light is described by its BSDF ("bidirectional scattering distribution
+// function"). For a pair of directions, the incoming ray and a direction away from the hit point,
+// it tells how much of the light coming in from that direction leaves back along the ray.
+//
+// A material answers three questions about it:
+// - scatter(): Pick a random direction for the ray to go on in, and say how likely it was to be
+//   picked (the pdf). Good materials pick the directions where the most light comes from most often
+//   ("importance sampling"), that gives less noise.
+// - evaluate(): The BSDF for a given direction.
+// - pdf(): How likely scatter() is to pick a given direction.
+// The last two are what the tracer needs to aim rays at light sources, and weigh them against the
+// scattered rays (see tracer.rs).
+//
+// Materials that only scatter in a few exact directions ("specular" ones, like mirrors and glass)
+// can't answer the last two: light from a randomly picked direction practically never goes their
+// way. They mark their scattered rays as specular, and leave evaluate() and pdf() as they are.
+//
+// RUST: Send and Sync are "marker traits". Requiring them means every material can be shared
+// between, and sent to, the threads that render the image.
+pub trait Material: Send + Sync {
+    // None if the light is absorbed, and the ray ends here.
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_point: &HitPoint,
+        sampler: &mut Sampler,
+    ) -> Option<ScatteredRay>;
+
+    // The light coming in from the direction (a unit vector, away from the hit point) that is
+    // scattered back along the ray, as a fraction of it. That's the BSDF times the cosine of the
+    // angle between the direction and the normal. Specular materials leave it at zero.
+    fn evaluate(&self, _ray: &Ray, _hit_point: &HitPoint, _direction: Vec3) -> Color {
+        Color::zero()
+    }
+
+    // The probability density (per solid angle) of scatter() picking the direction. Specular
+    // materials leave it at zero.
+    fn pdf(&self, _ray: &Ray, _hit_point: &HitPoint, _direction: Vec3) -> f64 {
+        0.0
+    }
+
+    // Light given off by the material itself (radiance). Most materials don't glow, so by default
+    // they emit nothing.
+    fn emitted(&self, _hit_point: &HitPoint) -> Color {
+        Color::zero()
+    }
+
+    // How much light is absorbed going through the inside of an object made of the material, per
+    // unit of distance, for red, green and blue. Only for materials that let light in, like glass.
+    // The tracer applies it to the stretches of a ray after it went in through the surface, until
+    // it comes back out (see tracer.rs).
+    fn absorption(&self) -> Color {
+        Color::zero()
+    }
+
+    // The medium on the inside, for the surface of a Volume. Rays go through such a surface
+    // without scattering, into or out of the medium (see tracer.rs).
+    fn medium(&self) -> Option<&dyn Medium> {
+        None
+    }
+
+    // Does the material give off light at all? Objects made of it become light sources, which the
+    // tracer aims rays at directly (next-event estimation).
+    fn emits_light(&self) -> bool {
+        false
+    }
 }
@@ -0,0 +1,42 @@
+use std::sync::Arc;
+
+use crate::sampler::Sampler;
+use crate::scene::material::material::Material;
+use crate::scene::medium::medium::Medium;
+use crate::tracer::hit_point::HitPoint;
+use crate::tracer::ray::{Ray, ScatteredRay};
+use crate::vec3::Color;
+
+// The surface of a Volume: not really a surface, just where the medium inside begins and ends.
+// Light goes straight through it. The tracer notices the medium, and keeps track of which medium
+// the ray is in (see tracer.rs).
+pub struct MediumBoundary {
+    medium: Arc<dyn Medium>,
+}
+
+impl MediumBoundary {
+    pub fn new(medium: Arc<dyn Medium>) -> MediumBoundary {
+        MediumBoundary { medium }
+    }
+}
+
+impl Material for MediumBoundary {
+    // The ray goes on as it was, unchanged.
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_point: &HitPoint,
+        _sampler: &mut Sampler,
+    ) -> Option<ScatteredRay> {
+        Some(ScatteredRay::specular(
+            hit_point.point(),
+            ray.direction(),
+            ray.time(),
+            Color::new(1.0, 1.0, 1.0),
+        ))
+    }
+
+    fn medium(&self) -> Option<&dyn Medium> {
+        Some(&*self.medium)
+    }
+}
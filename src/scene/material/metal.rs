@@ -0,0 +1,180 @@
+use crate::sampler::Sampler;
+use crate::scene::material::material::Material;
+use crate::scene::material::microfacet::{reflect, Frame, Ggx};
+use crate::tracer::hit_point::HitPoint;
+use crate::tracer::ray::{Ray, ScatteredRay};
+use crate::vec3::{Color, Vec3};
+
+// A metal ("conductor") with a rough surface, made of tiny mirrors (see microfacet.rs). Unlike the
+// fuzz of Reflective, this is a real BSDF: it never reflects more light than arrives, and it
+// doesn't send rays below the surface.
+//
+// How much light a metal reflects, and in what color, depends on its (complex) refractive index:
+// eta, how much light slows down in it, and k, how quickly it's absorbed. They differ by color,
+// which is why gold is yellow. Looking at a metal at a low angle, it reflects more, and whiter.
+pub struct Metal {
+    eta: Color,
+    ggx: Ggx,
+    k: Color,
+}
+
+impl Metal {
+    // The roughness is 0.0 (a perfect mirror) to 1.0.
+    pub fn new(eta: Color, k: Color, roughness: f64) -> Metal {
+        Metal {
+            eta,
+            ggx: Ggx::new(roughness),
+            k,
+        }
+    }
+
+    // Refractive indices of real metals, for red, green and blue light (about 650, 550 and 450 nm).
+    pub fn aluminium(roughness: f64) -> Metal {
+        Metal::new(
+            Color::new(1.657, 0.880, 0.521),
+            Color::new(9.224, 6.270, 4.837),
+            roughness,
+        )
+    }
+
+    pub fn copper(roughness: f64) -> Metal {
+        Metal::new(
+            Color::new(0.200, 0.924, 1.102),
+            Color::new(3.912, 2.452, 2.142),
+            roughness,
+        )
+    }
+
+    pub fn gold(roughness: f64) -> Metal {
+        Metal::new(
+            Color::new(0.143, 0.374, 1.442),
+            Color::new(3.983, 2.386, 1.603),
+            roughness,
+        )
+    }
+
+    pub fn silver(roughness: f64) -> Metal {
+        Metal::new(
+            Color::new(0.155, 0.117, 0.138),
+            Color::new(4.828, 3.122, 2.147),
+            roughness,
+        )
+    }
+
+    // The fraction of light a (micro) mirror reflects, at an angle with its normal.
+    fn fresnel(&self, cos_theta: f64) -> Color {
+        Color::new(
+            fresnel_conductor(cos_theta, self.eta.x(), self.k.x()),
+            fresnel_conductor(cos_theta, self.eta.y(), self.k.y()),
+            fresnel_conductor(cos_theta, self.eta.z(), self.k.z()),
+        )
+    }
+}
+
+impl Material for Metal {
+    // Pick a visible microfacet, and reflect the ray off of it. The BSDF is F*D*G/(4*cos_o*cos_i),
+    // and the density F*D*G1/(4*cos_o), so all that's left is the Fresnel term times the fraction
+    // of the visible microfacets that the reflected ray isn't shadowed on, G/G1.
+    //
+    // A ray can still be reflected below the surface, by a microfacet facing far sideways. That
+    // light would bounce off of other microfacets, in reality. Here it's absorbed, which makes
+    // very rough metals a bit darker than they should be.
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_point: &HitPoint,
+        sampler: &mut Sampler,
+    ) -> Option<ScatteredRay> {
+        let frame = Frame::new(hit_point.surface_normal());
+        let outgoing = frame.to_local(-ray.direction().unit_vector());
+        if outgoing.z() <= 0.0 {
+            return None;
+        }
+
+        // A perfect mirror only reflects in one direction.
+        if self.ggx.is_smooth() {
+            let incoming = Vec3::new(-outgoing.x(), -outgoing.y(), outgoing.z());
+            return Some(ScatteredRay::specular(
+                hit_point.point(),
+                frame.to_world(incoming),
+                ray.time(),
+                self.fresnel(outgoing.z()),
+            ));
+        }
+
+        let half_vector = self.ggx.sample_visible_normal(outgoing, sampler);
+        let incoming = reflect(outgoing, half_vector);
+        if incoming.z() <= 0.0 {
+            return None;
+        }
+
+        let shadowing = self.ggx.masking_shadowing(outgoing, incoming) / self.ggx.masking(outgoing);
+        let light_attenuation = self.fresnel(outgoing.dot(half_vector)) * shadowing;
+        let direction = frame.to_world(incoming);
+        Some(ScatteredRay::new(
+            hit_point.point(),
+            direction,
+            ray.time(),
+            light_attenuation,
+            self.pdf(ray, hit_point, direction),
+        ))
+    }
+
+    fn evaluate(&self, ray: &Ray, hit_point: &HitPoint, direction: Vec3) -> Color {
+        if self.ggx.is_smooth() {
+            return Color::zero();
+        }
+        let frame = Frame::new(hit_point.surface_normal());
+        let outgoing = frame.to_local(-ray.direction().unit_vector());
+        let incoming = frame.to_local(direction);
+        if outgoing.z() <= 0.0 || incoming.z() <= 0.0 {
+            return Color::zero();
+        }
+
+        // The cosine of the incoming direction cancels out.
+        let half_vector = (outgoing + incoming).unit_vector();
+        let distribution = self.ggx.distribution(half_vector);
+        let shadowing = self.ggx.masking_shadowing(outgoing, incoming);
+        self.fresnel(outgoing.dot(half_vector)) * (distribution * shadowing / (4.0 * outgoing.z()))
+    }
+
+    // The density of the microfacet normal, over the density of the reflected direction. Reflecting
+    // stretches the directions around the microfacet normal to four times their solid angle.
+    fn pdf(&self, ray: &Ray, hit_point: &HitPoint, direction: Vec3) -> f64 {
+        if self.ggx.is_smooth() {
+            return 0.0;
+        }
+        let frame = Frame::new(hit_point.surface_normal());
+        let outgoing = frame.to_local(-ray.direction().unit_vector());
+        let incoming = frame.to_local(direction);
+        if outgoing.z() <= 0.0 || incoming.z() <= 0.0 {
+            return 0.0;
+        }
+
+        let half_vector = (outgoing + incoming).unit_vector();
+        self.ggx.visible_normal_pdf(outgoing, half_vector) / (4.0 * outgoing.dot(half_vector))
+    }
+}
+
+// The Fresnel equations for a conductor, for unpolarized light (the average of the two
+// polarizations, s and p). From the outside, with a refractive index of one (air).
+fn fresnel_conductor(cos_theta: f64, eta: f64, k: f64) -> f64 {
+    let cos_theta = cos_theta.clamp(0.0, 1.0);
+    let cos_squared = cos_theta * cos_theta;
+    let sin_squared = 1.0 - cos_squared;
+    let eta_squared = eta * eta;
+    let k_squared = k * k;
+
+    let t0 = eta_squared - k_squared - sin_squared;
+    let a_squared_plus_b_squared = ((t0 * t0) + (4.0 * eta_squared * k_squared)).sqrt();
+    let t1 = a_squared_plus_b_squared + cos_squared;
+    let a = (0.5 * (a_squared_plus_b_squared + t0)).max(0.0).sqrt();
+    let t2 = 2.0 * cos_theta * a;
+    let r_s = (t1 - t2) / (t1 + t2);
+
+    let t3 = (cos_squared * a_squared_plus_b_squared) + (sin_squared * sin_squared);
+    let t4 = t2 * sin_squared;
+    let r_p = r_s * (t3 - t4) / (t3 + t4);
+
+    (r_s + r_p) / 2.0
+}
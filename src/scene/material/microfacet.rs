@@ -0,0 +1,178 @@
+use std::f64::consts::PI;
+
+use crate::sampler::Sampler;
+use crate::vec3::Vec3;
+
+// A rough surface, seen up close, is made of tiny flat mirrors ("microfacets"), each facing a bit
+// of a different way. The rougher the surface, the more their normals spread out around the
+// surface normal, and the blurrier its reflection. Light is reflected by the microfacets whose
+// normal is exactly halfway between the incoming and outgoing directions (the "half vector").
+//
+// The GGX (or Trowbridge-Reitz) distribution tells how many microfacets face every way. It has a
+// long tail, a soft glow around highlights, like real metals have. Its width is alpha, the
+// roughness squared (that looks more even, going from smooth to rough).
+//
+// Microfacets also hide each other: from a low angle, some are behind others ("masking"), and
+// light from a low angle doesn't reach all of them ("shadowing"). The Smith model accounts for
+// that, G below.
+//
+// All directions here are in the local frame of the surface: the normal is the Z axis (see Frame).
+pub struct Ggx {
+    alpha: f64,
+}
+
+impl Ggx {
+    // The roughness is 0.0 (a mirror) to 1.0 (very rough).
+    pub fn new(roughness: f64) -> Ggx {
+        Ggx {
+            alpha: roughness * roughness,
+        }
+    }
+
+    // Smooth enough to be treated as a perfect mirror. The distribution is too narrow to work with
+    // numbers then.
+    pub fn is_smooth(&self) -> bool {
+        self.alpha < 1e-4
+    }
+
+    // The distribution of microfacet normals, D. Its integral over the hemisphere, weighted by the
+    // cosine of the microfacet normal (its area projected on the surface), is one.
+    pub fn distribution(&self, half_vector: Vec3) -> f64 {
+        let cos_theta = half_vector.z();
+        if cos_theta <= 0.0 {
+            return 0.0;
+        }
+        let alpha_squared = self.alpha * self.alpha;
+        let denominator = (cos_theta * cos_theta * (alpha_squared - 1.0)) + 1.0;
+        alpha_squared / (PI * denominator * denominator)
+    }
+
+    // The Smith lambda function, how much of the surface is hidden seen from the direction.
+    fn lambda(&self, direction: Vec3) -> f64 {
+        let cos_squared = direction.z() * direction.z();
+        if cos_squared <= 0.0 {
+            return f64::INFINITY;
+        }
+        let tan_squared = (1.0 - cos_squared).max(0.0) / cos_squared;
+        (-1.0 + (1.0 + (self.alpha * self.alpha * tan_squared)).sqrt()) / 2.0
+    }
+
+    // The fraction of the microfacets seen from the direction that aren't hidden (masking).
+    pub fn masking(&self, direction: Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(direction))
+    }
+
+    // The fraction of microfacets seen from both directions, that light from one reaches and the
+    // other sees. Microfacets that are higher up are less likely to be hidden from either, so the
+    // two aren't independent ("height-correlated").
+    pub fn masking_shadowing(&self, outgoing: Vec3, incoming: Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(outgoing) + self.lambda(incoming))
+    }
+
+    // A random microfacet normal, of the ones seen from the outgoing direction. Picking only
+    // visible microfacets wastes no rays on ones that are facing away or hidden (Heitz, "Sampling
+    // the GGX Distribution of Visible Normals", 2018).
+    //
+    // Stretch the surface so that the roughness is one: then the microfacets form a hemisphere.
+    // Pick a point on the disk it looks like from the outgoing direction, with the half of the
+    // disk that is partly hidden squeezed together. Go up to the hemisphere, and stretch back.
+    pub fn sample_visible_normal(&self, outgoing: Vec3, sampler: &mut Sampler) -> Vec3 {
+        let stretched = Vec3::new(
+            self.alpha * outgoing.x(),
+            self.alpha * outgoing.y(),
+            outgoing.z(),
+        )
+        .unit_vector();
+
+        let length_squared = (stretched.x() * stretched.x()) + (stretched.y() * stretched.y());
+        let tangent = if length_squared > 0.0 {
+            Vec3::new(-stretched.y(), stretched.x(), 0.0) / length_squared.sqrt()
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let bitangent = stretched.cross(tangent);
+
+        let radius = sampler.next_f64().sqrt();
+        let phi = 2.0 * PI * sampler.next_f64();
+        let t1 = radius * phi.cos();
+        let t2 = radius * phi.sin();
+        let s = 0.5 * (1.0 + stretched.z());
+        let t2 = ((1.0 - s) * (1.0 - (t1 * t1)).sqrt()) + (s * t2);
+
+        let normal = (tangent * t1)
+            + (bitangent * t2)
+            + (stretched * (1.0 - (t1 * t1) - (t2 * t2)).max(0.0).sqrt());
+        Vec3::new(
+            self.alpha * normal.x(),
+            self.alpha * normal.y(),
+            normal.z().max(1e-6),
+        )
+        .unit_vector()
+    }
+
+    // The probability density of sample_visible_normal() picking the microfacet normal.
+    pub fn visible_normal_pdf(&self, outgoing: Vec3, half_vector: Vec3) -> f64 {
+        if outgoing.z() <= 0.0 {
+            return 0.0;
+        }
+        let cosine = outgoing.dot(half_vector).max(0.0);
+        self.masking(outgoing) * cosine * self.distribution(half_vector) / outgoing.z()
+    }
+}
+
+// The local frame of a surface: the normal is the Z axis, two tangents the X and Y axes. Directions
+// are simpler to work with in it: the cosine with the normal is just the Z component.
+pub struct Frame {
+    bitangent: Vec3,
+    normal: Vec3,
+    tangent: Vec3,
+}
+
+impl Frame {
+    // The normal has to be a unit vector.
+    pub fn new(normal: Vec3) -> Frame {
+        let (tangent, bitangent) = normal.orthonormal_basis();
+        Frame {
+            bitangent,
+            normal,
+            tangent,
+        }
+    }
+
+    pub fn to_local(&self, direction: Vec3) -> Vec3 {
+        Vec3::new(
+            direction.dot(self.tangent),
+            direction.dot(self.bitangent),
+            direction.dot(self.normal),
+        )
+    }
+
+    pub fn to_world(&self, direction: Vec3) -> Vec3 {
+        (self.tangent * direction.x())
+            + (self.bitangent * direction.y())
+            + (self.normal * direction.z())
+    }
+}
+
+// Mirror the direction around the normal. Both point away from the surface.
+pub fn reflect(direction: Vec3, normal: Vec3) -> Vec3 {
+    (normal * (2.0 * direction.dot(normal))) - direction
+}
+
+// Bend the direction through a surface with the normal (Snell's law). The direction points away
+// from the surface, on the side of the normal, and so does the refracted one, on the other side.
+// The relative refractive index is of the other side over this side. None if the direction can't
+// refract, and is all reflected instead ("total internal reflection").
+pub fn refract(direction: Vec3, normal: Vec3, relative_refractive_index: f64) -> Option<Vec3> {
+    let cos_i = direction.dot(normal);
+    let sin_t_squared =
+        (1.0 - (cos_i * cos_i)).max(0.0) / (relative_refractive_index * relative_refractive_index);
+    if sin_t_squared >= 1.0 {
+        return None;
+    }
+    let cos_t = (1.0 - sin_t_squared).sqrt();
+    Some(
+        (-direction / relative_refractive_index)
+            + (normal * ((cos_i / relative_refractive_index) - cos_t)),
+    )
+}
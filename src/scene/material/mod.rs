@@ -1,4 +1,10 @@
 pub mod dielectric;
 pub mod diffuse;
+pub mod diffuse_light;
+#[allow(clippy::module_inception)]
 pub mod material;
+pub mod medium_boundary;
+pub mod metal;
+pub mod microfacet;
 pub mod reflective;
+pub mod rough_dielectric;
@@ -1,4 +1,9 @@
+use std::sync::Arc;
+
+use crate::sampler::Sampler;
 use crate::scene::material::material::Material;
+use crate::scene::texture::solid_color::SolidColor;
+use crate::scene::texture::texture::Texture;
 use crate::tracer::hit_point::HitPoint;
 use crate::tracer::ray::{Ray, ScatteredRay};
 use crate::vec3::{Color, Vec3};
@@ -8,12 +13,17 @@ use crate::vec3::{Color, Vec3};
 // - Blurry (tiny random bumps on the surface cause the reflection to become blurry)
 // - Metallic (highlights and reflections retain the color of the reflective object)
 pub struct Reflective {
-    albedo: Color,
+    albedo: Arc<dyn Texture>,
     fuzz: f64, // 0.0 to 1.0.
 }
 
 impl Reflective {
     pub fn new(albedo: Color, fuzz: f64) -> Reflective {
+        Reflective::from_texture(Arc::new(SolidColor::new(albedo)), fuzz)
+    }
+
+    // The albedo (tint of the reflection) varies over the surface.
+    pub fn from_texture(albedo: Arc<dyn Texture>, fuzz: f64) -> Reflective {
         Reflective { albedo, fuzz }
     }
 
@@ -29,15 +39,36 @@ impl Material for Reflective {
     // it perfectly. This endpoint will be within a unit sphere; the bigger the sphere, the more
     // fuzziness. A problem however is that if the sphere is big enough, or if the scattered ray is
     // grazing the surface of the object, then the scattered ray may end up underneath the surface!
-    fn scatter(&self, ray: &Ray, hit_point: &HitPoint) -> ScatteredRay {
+    // Then the surface absorbs it.
+    //
+    // The fuzz is a cheap trick, not a BSDF that can be evaluated. So even a blurry reflection is
+    // treated as specular.
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_point: &HitPoint,
+        sampler: &mut Sampler,
+    ) -> Option<ScatteredRay> {
         let normalized_ray_direction = ray.direction().unit_vector();
         let point = hit_point.point();
         let surface_normal = hit_point.surface_normal();
 
         let scatter_direction = Reflective::reflect(normalized_ray_direction, surface_normal);
         let fuzzy_scatter_direction =
-            scatter_direction + (Vec3::random_in_unit_sphere() * self.fuzz);
+            scatter_direction + (Vec3::random_in_unit_sphere(sampler) * self.fuzz);
+
+        if fuzzy_scatter_direction.dot(surface_normal) <= 0.0 {
+            return None;
+        }
 
-        return ScatteredRay::new(point, fuzzy_scatter_direction, self.albedo);
+        let albedo = self
+            .albedo
+            .value(hit_point.u(), hit_point.v(), hit_point.point());
+        Some(ScatteredRay::specular(
+            point,
+            fuzzy_scatter_direction,
+            ray.time(),
+            albedo,
+        ))
     }
 }
@@ -0,0 +1,203 @@
+use crate::sampler::Sampler;
+use crate::scene::material::dielectric::Dielectric;
+use crate::scene::material::material::Material;
+use crate::scene::material::microfacet::{reflect, refract, Frame, Ggx};
+use crate::tracer::hit_point::HitPoint;
+use crate::tracer::ray::{Ray, ScatteredRay};
+use crate::vec3::{Color, Vec3};
+
+// Frosted glass: a Dielectric with a rough surface, made of tiny flat pieces of glass (see
+// microfacet.rs). Every piece reflects some light, and lets the rest through, bent by its own
+// normal. So both the reflection and what's seen through the glass are blurred (Walter et al.,
+// "Microfacet Models for Refraction through Rough Surfaces", 2007).
+//
+// The surface normal of the hit point always faces the ray, on the outside or the inside of the
+// glass. Whether the ray goes into the glass or out of it is told by front_facing.
+pub struct RoughDielectric {
+    absorption: Color, // Inside, like for Dielectric.
+    ggx: Ggx,
+    refractive_index: f64,
+}
+
+impl RoughDielectric {
+    // The roughness is 0.0 (smooth, like Dielectric) to 1.0.
+    pub fn new(refractive_index: f64, roughness: f64) -> RoughDielectric {
+        RoughDielectric::with_absorption(refractive_index, roughness, Color::zero())
+    }
+
+    pub fn with_absorption(
+        refractive_index: f64,
+        roughness: f64,
+        absorption: Color,
+    ) -> RoughDielectric {
+        RoughDielectric {
+            absorption,
+            ggx: Ggx::new(roughness),
+            refractive_index,
+        }
+    }
+
+    // The refractive index of the other side of the surface, over that of the ray's side.
+    fn relative_refractive_index(&self, hit_point: &HitPoint) -> f64 {
+        if hit_point.front_facing() {
+            self.refractive_index
+        } else {
+            1.0 / self.refractive_index
+        }
+    }
+
+    // The microfacet normal that turns the outgoing direction into the incoming one, in the local
+    // frame, facing up. When reflected, it's halfway between them. When refracted, Snell's law
+    // says it's in the direction of outgoing + eta*incoming. None if it's on the wrong side of
+    // either direction: no microfacet does that.
+    fn half_vector(outgoing: Vec3, incoming: Vec3, eta: f64) -> Option<Vec3> {
+        let is_reflection = incoming.z() > 0.0;
+        let half_vector = if is_reflection {
+            outgoing + incoming
+        } else {
+            outgoing + (incoming * eta)
+        };
+        if half_vector.is_zero() {
+            return None;
+        }
+        let half_vector = half_vector.unit_vector();
+        let half_vector = if half_vector.z() < 0.0 {
+            -half_vector
+        } else {
+            half_vector
+        };
+
+        let is_on_the_right_side =
+            outgoing.dot(half_vector) > 0.0 && (incoming.dot(half_vector) > 0.0) == is_reflection;
+        is_on_the_right_side.then_some(half_vector)
+    }
+}
+
+impl Material for RoughDielectric {
+    // Pick a visible microfacet. It reflects as much as the Fresnel equations say, and refracts the
+    // rest. So pick one or the other in proportion, like Dielectric does. The Fresnel term cancels
+    // out with that choice, and what's left is G/G1, like for Metal.
+    //
+    // Radiance is squeezed into a smaller solid angle when light goes into glass, and spread out
+    // when it comes back out. Like Dielectric, this leaves that out: for a closed object it
+    // cancels out.
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_point: &HitPoint,
+        sampler: &mut Sampler,
+    ) -> Option<ScatteredRay> {
+        let frame = Frame::new(hit_point.surface_normal());
+        let outgoing = frame.to_local(-ray.direction().unit_vector());
+        if outgoing.z() <= 0.0 {
+            return None;
+        }
+        let eta = self.relative_refractive_index(hit_point);
+
+        // Smooth glass has a single microfacet, the surface itself. It's specular.
+        if self.ggx.is_smooth() {
+            let normal = Vec3::new(0.0, 0.0, 1.0);
+            let reflectivity = Dielectric::fresnel(outgoing.z(), eta);
+            let incoming = match refract(outgoing, normal, eta) {
+                Some(refracted) if sampler.next_f64() >= reflectivity => refracted,
+                _ => reflect(outgoing, normal),
+            };
+            return Some(ScatteredRay::specular(
+                hit_point.point(),
+                frame.to_world(incoming),
+                ray.time(),
+                Color::new(1.0, 1.0, 1.0),
+            ));
+        }
+
+        let half_vector = self.ggx.sample_visible_normal(outgoing, sampler);
+        let reflectivity = Dielectric::fresnel(outgoing.dot(half_vector), eta);
+        let incoming = match refract(outgoing, half_vector, eta) {
+            Some(refracted) if sampler.next_f64() >= reflectivity => refracted,
+            _ => reflect(outgoing, half_vector),
+        };
+
+        // Reflected below, or refracted above, the surface. The light would bounce between the
+        // microfacets, but here it's lost.
+        let is_reflection = outgoing.dot(half_vector) * incoming.dot(half_vector) > 0.0;
+        if (incoming.z() > 0.0) != is_reflection || incoming.z() == 0.0 {
+            return None;
+        }
+
+        let shadowing = self.ggx.masking_shadowing(outgoing, incoming) / self.ggx.masking(outgoing);
+        let direction = frame.to_world(incoming);
+        Some(ScatteredRay::new(
+            hit_point.point(),
+            direction,
+            ray.time(),
+            Color::new(1.0, 1.0, 1.0) * shadowing,
+            self.pdf(ray, hit_point, direction),
+        ))
+    }
+
+    // Reflection is like Metal, with the Fresnel equations of a dielectric. For refraction, the
+    // solid angle around the microfacet normal is stretched differently (the last factor).
+    fn evaluate(&self, ray: &Ray, hit_point: &HitPoint, direction: Vec3) -> Color {
+        if self.ggx.is_smooth() {
+            return Color::zero();
+        }
+        let frame = Frame::new(hit_point.surface_normal());
+        let outgoing = frame.to_local(-ray.direction().unit_vector());
+        let incoming = frame.to_local(direction);
+        let eta = self.relative_refractive_index(hit_point);
+        if outgoing.z() <= 0.0 || incoming.z() == 0.0 {
+            return Color::zero();
+        }
+        let Some(half_vector) = RoughDielectric::half_vector(outgoing, incoming, eta) else {
+            return Color::zero();
+        };
+
+        let cos_o = outgoing.dot(half_vector);
+        let reflectivity = Dielectric::fresnel(cos_o, eta);
+        let distribution = self.ggx.distribution(half_vector);
+        let shadowing = self.ggx.masking_shadowing(outgoing, incoming);
+        let value = if incoming.z() > 0.0 {
+            reflectivity * distribution * shadowing / (4.0 * outgoing.z())
+        } else {
+            let cos_i = incoming.dot(half_vector);
+            let denominator = cos_o + (eta * cos_i);
+            (1.0 - reflectivity) * distribution * shadowing * eta * eta * (cos_i * cos_o).abs()
+                / (outgoing.z() * denominator * denominator)
+        };
+        Color::new(1.0, 1.0, 1.0) * value
+    }
+
+    // The density of the visible microfacet normal, times the chance to reflect or refract, over
+    // how much the direction stretches around it.
+    fn pdf(&self, ray: &Ray, hit_point: &HitPoint, direction: Vec3) -> f64 {
+        if self.ggx.is_smooth() {
+            return 0.0;
+        }
+        let frame = Frame::new(hit_point.surface_normal());
+        let outgoing = frame.to_local(-ray.direction().unit_vector());
+        let incoming = frame.to_local(direction);
+        let eta = self.relative_refractive_index(hit_point);
+        if outgoing.z() <= 0.0 || incoming.z() == 0.0 {
+            return 0.0;
+        }
+        let Some(half_vector) = RoughDielectric::half_vector(outgoing, incoming, eta) else {
+            return 0.0;
+        };
+
+        let cos_o = outgoing.dot(half_vector);
+        let reflectivity = Dielectric::fresnel(cos_o, eta);
+        let normal_pdf = self.ggx.visible_normal_pdf(outgoing, half_vector);
+        if incoming.z() > 0.0 {
+            reflectivity * normal_pdf / (4.0 * cos_o)
+        } else {
+            let cos_i = incoming.dot(half_vector);
+            let denominator = cos_o + (eta * cos_i);
+            (1.0 - reflectivity) * normal_pdf * eta * eta * cos_i.abs()
+                / (denominator * denominator)
+        }
+    }
+
+    fn absorption(&self) -> Color {
+        self.absorption
+    }
+}
@@ -0,0 +1,162 @@
+use crate::sampler::Sampler;
+use crate::scene::medium::medium::{Interaction, Medium};
+use crate::scene::medium::phase_function::PhaseFunction;
+use crate::scene::medium::voxel_grid::VoxelGrid;
+use crate::scene::object::bounding_box::BoundingBox;
+use crate::tracer::ray::Ray;
+use crate::vec3::{Color, Point3, Vec3};
+
+// A medium with a density that changes from point to point, like real smoke and clouds: thick in
+// some places, wispy in others. The density comes from a VoxelGrid, stretched over a box. Outside
+// of the box there is nothing.
+//
+// Where the density isn't the same everywhere, there's no formula for how far light gets. Instead,
+// pretend the medium is as dense everywhere as at its densest (the "majorant"), by adding made-up
+// particles that don't do anything ("null collisions"). Pick the distance to the next particle as
+// for a HomogeneousMedium. Then it's either a real one, with a chance of density / majorant, or a
+// made-up one, and the ray goes on as if nothing happened ("delta tracking", Woodcock et al. 1965).
+//
+// It can also glow, like fire: an emission grid gives the light it gives off, per unit of distance.
+pub struct GridMedium {
+    albedo: Color,
+    bounds: BoundingBox,
+    density: VoxelGrid,
+    emission: Option<VoxelGrid>,
+    emission_color: Color,
+    majorant: f64, // At least the density everywhere, and never zero.
+    phase_function: PhaseFunction,
+}
+
+impl GridMedium {
+    // The grid is stretched over the box between the two (opposite) corners.
+    pub fn new(
+        corners: [Point3; 2],
+        density: VoxelGrid,
+        albedo: Color,
+        phase_function: PhaseFunction,
+    ) -> GridMedium {
+        let bounds = BoundingBox::new(
+            corners[0].minimum(corners[1]),
+            corners[0].maximum(corners[1]),
+        );
+
+        // A medium without any particles can still glow. The made-up ones find the light, so there
+        // have to be some: about one across the box.
+        let diagonal = (bounds.maximum() - bounds.minimum()).length();
+        let majorant = density.maximum().max(1.0 / diagonal);
+
+        GridMedium {
+            albedo,
+            bounds,
+            density,
+            emission: None,
+            emission_color: Color::zero(),
+            majorant,
+            phase_function,
+        }
+    }
+
+    // Light given off, per unit of distance, is the emission grid times the color.
+    pub fn with_emission(
+        corners: [Point3; 2],
+        density: VoxelGrid,
+        albedo: Color,
+        phase_function: PhaseFunction,
+        emission: VoxelGrid,
+        emission_color: Color,
+    ) -> GridMedium {
+        GridMedium {
+            emission: Some(emission),
+            emission_color,
+            ..GridMedium::new(corners, density, albedo, phase_function)
+        }
+    }
+
+    // Where the ray is in the box, up to t_max. None if it misses it.
+    fn ray_interval(&self, ray: &Ray, t_max: f64) -> Option<(f64, f64)> {
+        let direction = ray.direction();
+        let inverse_direction = Vec3::new(
+            1.0 / direction.x(),
+            1.0 / direction.y(),
+            1.0 / direction.z(),
+        );
+        self.bounds.ray_interval(ray, inverse_direction, 0.0, t_max)
+    }
+
+    // The point, in the unit cube of the voxel grids.
+    fn grid_point(&self, point: Point3) -> Point3 {
+        let minimum = self.bounds.minimum();
+        let size = self.bounds.maximum() - minimum;
+        Point3::new(
+            (point.x() - minimum.x()) / size.x(),
+            (point.y() - minimum.y()) / size.y(),
+            (point.z() - minimum.z()) / size.z(),
+        )
+    }
+
+    // The distance to the next (real or made-up) particle, in units of t along the ray.
+    fn free_flight(&self, ray: &Ray, sampler: &mut Sampler) -> f64 {
+        -(1.0 - sampler.next_f64()).ln() / (self.majorant * ray.direction().length())
+    }
+}
+
+impl Medium for GridMedium {
+    // Delta tracking. On the way, every particle, real or made-up, adds the light given off around
+    // it. There is one every 1 / majorant on average, so that's the emission over the majorant.
+    fn sample_interaction(&self, ray: &Ray, t_max: f64, sampler: &mut Sampler) -> Interaction {
+        let mut emitted = Color::zero();
+        let Some((t_enter, t_exit)) = self.ray_interval(ray, t_max) else {
+            return Interaction::Passed {
+                emitted,
+                light_attenuation: Color::new(1.0, 1.0, 1.0),
+            };
+        };
+
+        let mut t = t_enter;
+        loop {
+            t += self.free_flight(ray, sampler);
+            if t >= t_exit {
+                return Interaction::Passed {
+                    emitted,
+                    light_attenuation: Color::new(1.0, 1.0, 1.0),
+                };
+            }
+
+            let point = self.grid_point(ray.point_at(t));
+            if let Some(emission) = &self.emission {
+                emitted = emitted + (self.emission_color * (emission.value(point) / self.majorant));
+            }
+            if sampler.next_f64() < self.density.value(point) / self.majorant {
+                return Interaction::Scattered {
+                    emitted,
+                    light_attenuation: self.albedo,
+                    t,
+                };
+            }
+        }
+    }
+
+    // Ratio tracking (Novák et al., 2014). Step from particle to particle like delta tracking, but
+    // instead of stopping at a real one, keep the chance that it wasn't: 1 - density / majorant.
+    // The product of those is the transmittance, on average, without the all or nothing answers
+    // of delta tracking.
+    fn transmittance(&self, ray: &Ray, t_max: f64, sampler: &mut Sampler) -> Color {
+        let mut transmittance = 1.0;
+        if let Some((t_enter, t_exit)) = self.ray_interval(ray, t_max) {
+            let mut t = t_enter;
+            loop {
+                t += self.free_flight(ray, sampler);
+                if t >= t_exit || transmittance <= 0.0 {
+                    break;
+                }
+                let point = self.grid_point(ray.point_at(t));
+                transmittance *= 1.0 - (self.density.value(point) / self.majorant);
+            }
+        }
+        Color::new(transmittance, transmittance, transmittance)
+    }
+
+    fn phase_function(&self) -> &PhaseFunction {
+        &self.phase_function
+    }
+}
@@ -0,0 +1,66 @@
+use crate::sampler::Sampler;
+use crate::scene::medium::medium::{Interaction, Medium};
+use crate::scene::medium::phase_function::PhaseFunction;
+use crate::tracer::ray::Ray;
+use crate::vec3::Color;
+
+// A medium with the same density everywhere, like fog or a cloud of smoke seen from far away.
+//
+// Light that runs into a particle is scattered with the albedo (for red, green and blue), and the
+// rest is absorbed. An albedo of one is a white cloud, that absorbs nothing. Zero is black smoke.
+pub struct HomogeneousMedium {
+    albedo: Color,
+    density: f64, // How likely light is to run into a particle, per unit of distance.
+    phase_function: PhaseFunction,
+}
+
+impl HomogeneousMedium {
+    pub fn new(density: f64, albedo: Color, phase_function: PhaseFunction) -> HomogeneousMedium {
+        HomogeneousMedium {
+            albedo,
+            density,
+            phase_function,
+        }
+    }
+}
+
+impl Medium for HomogeneousMedium {
+    // The chance of getting a distance d through unhindered falls off exponentially: e^(-density*d)
+    // (the Beer-Lambert law). Pick the distance to the first particle with that distribution
+    // ("free-flight sampling"). Rays that go further than t_max got through. The chances of each
+    // are exactly the transmittance, so it cancels out, and only the albedo is left.
+    fn sample_interaction(&self, ray: &Ray, t_max: f64, sampler: &mut Sampler) -> Interaction {
+        let passed = Interaction::Passed {
+            emitted: Color::zero(),
+            light_attenuation: Color::new(1.0, 1.0, 1.0),
+        };
+        if self.density <= 0.0 {
+            return passed;
+        }
+
+        let length = ray.direction().length();
+        let distance = -(1.0 - sampler.next_f64()).ln() / self.density;
+        if distance >= t_max * length {
+            return passed;
+        }
+        Interaction::Scattered {
+            emitted: Color::zero(),
+            light_attenuation: self.albedo,
+            t: distance / length,
+        }
+    }
+
+    fn transmittance(&self, ray: &Ray, t_max: f64, _sampler: &mut Sampler) -> Color {
+        let distance = t_max * ray.direction().length();
+        let transmittance = if self.density > 0.0 {
+            (-self.density * distance).exp()
+        } else {
+            1.0
+        };
+        Color::new(transmittance, transmittance, transmittance)
+    }
+
+    fn phase_function(&self) -> &PhaseFunction {
+        &self.phase_function
+    }
+}
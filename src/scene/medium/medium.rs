@@ -0,0 +1,44 @@
+use crate::sampler::Sampler;
+use crate::scene::medium::phase_function::PhaseFunction;
+use crate::tracer::ray::Ray;
+use crate::vec3::Color;
+
+// Something light travels through, instead of just bouncing off of: fog, smoke, clouds. It's full
+// of tiny particles. Every now and then, light runs into one. Then it's either absorbed, or
+// scattered off in a new direction. How likely that is, per unit of distance, is the "density" of
+// the medium. The further light goes through it, the less of it gets through unhindered.
+//
+// Media fill the inside of a Volume, or the whole world (fog, see Config). The tracer keeps track
+// of which medium a ray is in (see tracer.rs).
+//
+// RUST: See Material for why media must be Send and Sync.
+pub trait Medium: Send + Sync {
+    // Follow the ray through the medium, up to t_max along it (where it leaves the medium, or hits
+    // a surface). It either runs into a particle on the way, or gets through.
+    fn sample_interaction(&self, ray: &Ray, t_max: f64, sampler: &mut Sampler) -> Interaction;
+
+    // The fraction of light that gets through the medium unhindered, along the ray, up to t_max.
+    // For shadow rays.
+    fn transmittance(&self, ray: &Ray, t_max: f64, sampler: &mut Sampler) -> Color;
+
+    // Which way light scatters off of the particles.
+    fn phase_function(&self) -> &PhaseFunction;
+}
+
+// What happened to a ray going through a medium. Like the light attenuation of a ScatteredRay, the
+// light attenuation already accounts for how likely it was to happen. A glowing medium (fire) also
+// adds the light it gave off along the way.
+pub enum Interaction {
+    // The ray got through, all the way to t_max.
+    Passed {
+        emitted: Color,
+        light_attenuation: Color,
+    },
+    // The ray ran into a particle at t along it, and scatters off in a new direction (see
+    // PhaseFunction). The light attenuation includes the fraction that was absorbed.
+    Scattered {
+        emitted: Color,
+        light_attenuation: Color,
+        t: f64,
+    },
+}
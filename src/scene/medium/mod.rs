@@ -0,0 +1,6 @@
+pub mod grid;
+pub mod homogeneous;
+#[allow(clippy::module_inception)]
+pub mod medium;
+pub mod phase_function;
+pub mod voxel_grid;
@@ -0,0 +1,87 @@
+use std::f64::consts::PI;
+
+use crate::sampler::Sampler;
+use crate::scene::material::material::Material;
+use crate::tracer::hit_point::HitPoint;
+use crate::tracer::ray::{Ray, ScatteredRay};
+use crate::vec3::{Color, Vec3};
+
+// Which way light goes after running into a particle of a medium, relative to the way it was going.
+// Like the BSDF of a surface, but without a surface: there is no normal, no cosine, and nothing is
+// absorbed (the medium takes care of that).
+pub enum PhaseFunction {
+    // Every direction is as likely.
+    Isotropic,
+    // Henyey-Greenstein, with an "asymmetry" g from -1.0 to 1.0. Positive values scatter mostly
+    // forward, like the water droplets in fog and clouds, negative values mostly back. Zero is
+    // isotropic.
+    HenyeyGreenstein(f64),
+}
+
+impl PhaseFunction {
+    // The asymmetry, as g of Henyey-Greenstein.
+    fn asymmetry(&self) -> f64 {
+        match self {
+            PhaseFunction::Isotropic => 0.0,
+            PhaseFunction::HenyeyGreenstein(g) => *g,
+        }
+    }
+
+    // The density of scattering in a direction, at an angle with the way the light was going.
+    // Over the sphere of directions it adds up to one.
+    pub fn value(&self, cos_theta: f64) -> f64 {
+        let g = self.asymmetry();
+        let denominator = 1.0 + (g * g) - (2.0 * g * cos_theta);
+        (1.0 - (g * g)) / (4.0 * PI * denominator * denominator.sqrt())
+    }
+
+    // A random direction, picked in proportion to value(), around the way the light was going.
+    fn random_direction(&self, direction: Vec3, sampler: &mut Sampler) -> Vec3 {
+        let g = self.asymmetry();
+        let random = sampler.next_f64();
+        let cos_theta = if g.abs() < 1e-3 {
+            1.0 - (2.0 * random)
+        } else {
+            let s = (1.0 - (g * g)) / (1.0 - g + (2.0 * g * random));
+            ((1.0 + (g * g) - (s * s)) / (2.0 * g)).clamp(-1.0, 1.0)
+        };
+        let sin_theta = (1.0 - (cos_theta * cos_theta)).max(0.0).sqrt();
+        let phi = 2.0 * PI * sampler.next_f64();
+
+        let (tangent, bitangent) = direction.orthonormal_basis();
+        (tangent * (phi.cos() * sin_theta))
+            + (bitangent * (phi.sin() * sin_theta))
+            + (direction * cos_theta)
+    }
+}
+
+// A phase function scatters light like a material does, at a point in a medium instead of on a
+// surface. So the tracer can treat such a point like a hit point, aiming shadow rays at lights and
+// all. It picks directions exactly in proportion to the phase function, so the light attenuation
+// is one.
+impl Material for PhaseFunction {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_point: &HitPoint,
+        sampler: &mut Sampler,
+    ) -> Option<ScatteredRay> {
+        let direction = self.random_direction(ray.direction().unit_vector(), sampler);
+        Some(ScatteredRay::new(
+            hit_point.point(),
+            direction,
+            ray.time(),
+            Color::new(1.0, 1.0, 1.0),
+            self.pdf(ray, hit_point, direction),
+        ))
+    }
+
+    fn evaluate(&self, ray: &Ray, hit_point: &HitPoint, direction: Vec3) -> Color {
+        let value = self.pdf(ray, hit_point, direction);
+        Color::new(value, value, value)
+    }
+
+    fn pdf(&self, ray: &Ray, _hit_point: &HitPoint, direction: Vec3) -> f64 {
+        self.value(ray.direction().unit_vector().dot(direction.unit_vector()))
+    }
+}
@@ -0,0 +1,193 @@
+use std::fs;
+use std::path::Path;
+
+use crate::sampler::Sampler;
+use crate::scene::texture::perlin::Perlin;
+use crate::vec3::{Point3, Vec3};
+
+// The most voxels a grid can have, 2 GB of values. More is almost certainly a typo in the
+// resolution, which shouldn't take all of the memory before it's noticed.
+pub const MAX_VOXELS: usize = 256 * 1024 * 1024;
+
+// A 3D grid of numbers ("voxels", the 3D version of pixels), like the density of smoke at every
+// point of a box. Between the voxels, values are blended with trilinear interpolation, so the smoke
+// doesn't look blocky.
+//
+// The grid covers the unit cube, from (0, 0, 0) to (1, 1, 1). Every voxel is a small box, with its
+// value at the center.
+pub struct VoxelGrid {
+    maximum: f64,           // The largest value anywhere.
+    resolution: [usize; 3], // Number of voxels along X, Y and Z.
+    values: Vec<f64>,       // X changes fastest, then Y, then Z.
+}
+
+impl VoxelGrid {
+    // The values can't be negative, and there have to be exactly as many as there are voxels.
+    pub fn new(resolution: [usize; 3], values: Vec<f64>) -> Result<VoxelGrid, String> {
+        let count = resolution[0]
+            .checked_mul(resolution[1])
+            .and_then(|count| count.checked_mul(resolution[2]))
+            .filter(|count| *count <= MAX_VOXELS)
+            .ok_or_else(|| format!("a voxel grid can't have more than {} voxels", MAX_VOXELS))?;
+        if count == 0 {
+            return Err("a voxel grid needs at least one voxel along every axis".to_string());
+        }
+        if values.len() != count {
+            return Err(format!(
+                "expected {} values for a {}x{}x{} voxel grid, not {}",
+                count,
+                resolution[0],
+                resolution[1],
+                resolution[2],
+                values.len()
+            ));
+        }
+        if values.iter().any(|value| value.is_nan() || *value < 0.0) {
+            return Err("the values of a voxel grid must be numbers of at least 0".to_string());
+        }
+
+        Ok(VoxelGrid {
+            maximum: values.iter().cloned().fold(0.0, f64::max),
+            resolution,
+            values,
+        })
+    }
+
+    // A puff of smoke, or a cloud: Perlin turbulence, fading out towards the sides of the cube so
+    // that it's round, and doesn't show the shape of the box. The scale is how many bumps fit
+    // across it, like for NoiseTexture.
+    pub fn from_noise(resolution: [usize; 3], scale: f64, sampler: &mut Sampler) -> VoxelGrid {
+        let perlin = Perlin::new(sampler);
+        let center = Point3::new(0.5, 0.5, 0.5);
+
+        let mut values = Vec::with_capacity(resolution[0] * resolution[1] * resolution[2]);
+        for z in 0..resolution[2] {
+            for y in 0..resolution[1] {
+                for x in 0..resolution[0] {
+                    let point = Point3::new(
+                        (x as f64 + 0.5) / resolution[0] as f64,
+                        (y as f64 + 0.5) / resolution[1] as f64,
+                        (z as f64 + 0.5) / resolution[2] as f64,
+                    );
+
+                    // One in the middle, zero on the sphere that just fits in the cube.
+                    let falloff = 1.0 - (2.0 * (point - center).length());
+                    let turbulence = perlin.turbulence(point * scale, 5);
+                    values.push(((2.0 * falloff) + turbulence - 0.5).max(0.0));
+                }
+            }
+        }
+
+        // RUST: The values are made to fit, so this can't fail (unless the resolution is zero).
+        VoxelGrid::new(resolution, values).expect("a voxel grid needs at least one voxel")
+    }
+
+    // Reads a voxel grid from a file. A .raw file is just the values, as 32-bit floating point
+    // numbers (little-endian), so it needs the resolution. Any other file is text: the resolution
+    // (three whole numbers), and then the values, all separated by whitespace.
+    pub fn load(path: &Path, resolution: Option<[usize; 3]>) -> Result<VoxelGrid, String> {
+        let is_raw = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("raw"));
+        if is_raw {
+            let Some(resolution) = resolution else {
+                return Err("a .raw voxel grid needs a resolution".to_string());
+            };
+            let bytes = fs::read(path).map_err(|error| error.to_string())?;
+            if bytes.len() % 4 != 0 {
+                return Err(format!(
+                    "{} isn't a whole number of 32-bit values",
+                    path.display()
+                ));
+            }
+            let values = bytes
+                .chunks_exact(4)
+                .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]) as f64)
+                .collect();
+            return VoxelGrid::new(resolution, values);
+        }
+
+        if resolution.is_some() {
+            return Err("a text voxel grid has its resolution in the file".to_string());
+        }
+        let text = fs::read_to_string(path).map_err(|error| error.to_string())?;
+        let mut numbers = text.split_whitespace().map(|token| {
+            token
+                .parse::<f64>()
+                .map_err(|_| format!("expected a number, not \"{}\"", token))
+        });
+        let mut dimension = || match numbers.next() {
+            Some(Ok(number)) if number >= 0.0 && number.fract() == 0.0 => Ok(number as usize),
+            Some(Ok(number)) => Err(format!("expected a whole number, not {}", number)),
+            Some(Err(error)) => Err(error),
+            None => Err("expected the resolution of the voxel grid".to_string()),
+        };
+        let resolution = [dimension()?, dimension()?, dimension()?];
+        let values = numbers.collect::<Result<Vec<f64>, String>>()?;
+        VoxelGrid::new(resolution, values)
+    }
+
+    pub fn maximum(&self) -> f64 {
+        self.maximum
+    }
+
+    // Every value times the factor.
+    pub fn scale(&mut self, factor: f64) {
+        for value in &mut self.values {
+            *value *= factor;
+        }
+        self.maximum *= factor;
+    }
+
+    // The value at a point of the unit cube, blended between the eight voxel centers around it.
+    // Zero outside of the cube. Towards the sides, past the last voxel centers, the values of the
+    // outermost voxels are kept.
+    pub fn value(&self, point: Point3) -> f64 {
+        if !(0.0..=1.0).contains(&point.x())
+            || !(0.0..=1.0).contains(&point.y())
+            || !(0.0..=1.0).contains(&point.z())
+        {
+            return 0.0;
+        }
+
+        // Position in voxels, relative to the center of the first voxel.
+        let position = Vec3::new(
+            (point.x() * self.resolution[0] as f64) - 0.5,
+            (point.y() * self.resolution[1] as f64) - 0.5,
+            (point.z() * self.resolution[2] as f64) - 0.5,
+        );
+        let mut lower = [0; 3];
+        let mut fraction = [0.0; 3];
+        for axis in 0..3 {
+            let last = (self.resolution[axis] - 1) as f64;
+            let clamped = position[axis].clamp(0.0, last);
+            let floor = clamped.floor().min((last - 1.0).max(0.0));
+            lower[axis] = floor as usize;
+            fraction[axis] = clamped - floor;
+        }
+
+        let mut value = 0.0;
+        for dz in 0..2 {
+            for dy in 0..2 {
+                for dx in 0..2 {
+                    let weight = |d: usize, f: f64| if d == 1 { f } else { 1.0 - f };
+                    let weight =
+                        weight(dx, fraction[0]) * weight(dy, fraction[1]) * weight(dz, fraction[2]);
+                    if weight > 0.0 {
+                        value += weight * self.voxel(lower[0] + dx, lower[1] + dy, lower[2] + dz);
+                    }
+                }
+            }
+        }
+        value
+    }
+
+    // The value of a voxel. Past the end of an axis (for a grid one voxel thick), the last one.
+    fn voxel(&self, x: usize, y: usize, z: usize) -> f64 {
+        let x = x.min(self.resolution[0] - 1);
+        let y = y.min(self.resolution[1] - 1);
+        let z = z.min(self.resolution[2] - 1);
+        self.values[x + (self.resolution[0] * (y + (self.resolution[1] * z)))]
+    }
+}
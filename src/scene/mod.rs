@@ -1,4 +1,12 @@
 pub mod background;
+pub mod environment_map;
 pub mod generator;
+pub mod json;
+pub mod light;
+pub mod loader;
 pub mod material;
+pub mod medium;
 pub mod object;
+pub mod sky;
+pub mod texture;
+pub mod wavefront;
@@ -0,0 +1,106 @@
+use crate::tracer::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+
+// An axis-aligned bounding box (AABB). The box is described by its two opposite corners and its
+// sides are always parallel to the X, Y and Z axes. Testing a ray against a box is much cheaper
+// than testing it against the object inside, so if the ray misses the box it must also miss the
+// object.
+#[derive(Clone, Copy)]
+pub struct BoundingBox {
+    maximum: Point3, // Corner with the largest (x,y,z).
+    minimum: Point3, // Corner with the smallest (x,y,z).
+}
+
+impl BoundingBox {
+    pub fn new(minimum: Point3, maximum: Point3) -> BoundingBox {
+        BoundingBox { maximum, minimum }
+    }
+
+    // The smallest box that contains both boxes.
+    pub fn surrounding(first: BoundingBox, second: BoundingBox) -> BoundingBox {
+        BoundingBox {
+            maximum: first.maximum.maximum(second.maximum),
+            minimum: first.minimum.minimum(second.minimum),
+        }
+    }
+
+    pub fn maximum(&self) -> Point3 {
+        self.maximum
+    }
+
+    pub fn minimum(&self) -> Point3 {
+        self.minimum
+    }
+
+    pub fn centroid(&self) -> Point3 {
+        (self.minimum + self.maximum) * 0.5
+    }
+
+    // The surface area heuristic uses this. The probability that a random ray hits a box is
+    // proportional to its surface area.
+    pub fn surface_area(&self) -> f64 {
+        let extent = self.maximum - self.minimum;
+        2.0 * ((extent.x() * extent.y()) + (extent.y() * extent.z()) + (extent.z() * extent.x()))
+    }
+
+    // Index of the axis (0=X, 1=Y, 2=Z) along which the box is the longest.
+    pub fn longest_axis(&self) -> usize {
+        let extent = self.maximum - self.minimum;
+        if extent.x() > extent.y() && extent.x() > extent.z() {
+            0
+        } else if extent.y() > extent.z() {
+            1
+        } else {
+            2
+        }
+    }
+
+    // Does the ray go through the box, anywhere between t_min and t_max?
+    pub fn is_hit_by_ray(
+        &self,
+        ray: &Ray,
+        inverse_direction: Vec3,
+        t_min: f64,
+        t_max: f64,
+    ) -> bool {
+        self.ray_interval(ray, inverse_direction, t_min, t_max)
+            .is_some()
+    }
+
+    // The "slab method". Each pair of parallel sides forms a slab, and the ray enters and exits
+    // every slab at some t. If the intervals where the ray is inside all three slabs overlap, then
+    // the ray is inside the box during that overlap. The inverse ray direction is passed in since
+    // it's the same for every box tested with a ray, and multiplication is cheaper than division.
+    //
+    // Dividing by a zero direction component gives +/- infinity, which makes the comparisons below
+    // do the right thing for rays parallel to a slab.
+    //
+    // Returns where the ray is inside of the box, from t_enter to t_exit, within t_min and t_max.
+    pub fn ray_interval(
+        &self,
+        ray: &Ray,
+        inverse_direction: Vec3,
+        t_min: f64,
+        t_max: f64,
+    ) -> Option<(f64, f64)> {
+        let origin = ray.origin();
+        let mut t_enter = t_min;
+        let mut t_exit = t_max;
+
+        for axis in 0..3 {
+            let mut t0 = (self.minimum[axis] - origin[axis]) * inverse_direction[axis];
+            let mut t1 = (self.maximum[axis] - origin[axis]) * inverse_direction[axis];
+            if inverse_direction[axis] < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            t_enter = t_enter.max(t0);
+            t_exit = t_exit.min(t1);
+            if t_exit < t_enter {
+                return None;
+            }
+        }
+
+        Some((t_enter, t_exit))
+    }
+}
@@ -0,0 +1,296 @@
+use crate::scene::object::bounding_box::BoundingBox;
+use crate::scene::object::object::Object;
+use crate::tracer::hit_point::HitPoint;
+use crate::tracer::ray::Ray;
+use crate::vec3::Vec3;
+
+// A bounding volume hierarchy (BVH) is a tree of bounding boxes. Every node has a box that contains
+// everything below it, and the leaves hold the objects themselves. A ray that misses a node's box
+// can skip the whole subtree, so instead of testing every object we only test the few whose boxes
+// the ray actually passes through. This takes intersection from linear to (roughly) logarithmic
+// time in the number of objects.
+//
+// The tree is stored "flattened" in a vector. A node's left child is always the node right after
+// it, so only the index of the right child has to be stored.
+pub struct BoundingVolumeHierarchy<T: Object> {
+    nodes: Vec<Node>,
+    objects: Vec<T>, // Reordered so that every leaf refers to a contiguous range.
+}
+
+enum Node {
+    Interior {
+        axis: usize, // The axis the objects were split along. Used to visit the closest child first.
+        bounding_box: BoundingBox,
+        right_child: usize,
+    },
+    Leaf {
+        bounding_box: BoundingBox,
+        first_object: usize,
+        object_count: usize,
+    },
+}
+
+// What the builder needs to know about an object, without touching the object itself.
+struct Primitive {
+    bounding_box: BoundingBox,
+    centroid: Vec3,
+    index: usize, // Index into the original object vector.
+}
+
+// The surface area heuristic (SAH) estimates the cost of a split. The cost of a subtree is the
+// cost of traversing it plus the cost of intersecting its objects, weighted by the probability of
+// a ray hitting each child. That probability is the ratio of the surface areas of the child and
+// parent boxes. Instead of trying every possible split, the objects are sorted into a few buckets
+// along the longest axis and only the bucket boundaries are tried.
+const SAH_BUCKET_COUNT: usize = 12;
+const SAH_TRAVERSAL_COST: f64 = 0.125; // Relative to the cost of intersecting one object.
+const MAXIMUM_LEAF_OBJECTS: usize = 4;
+
+impl<T: Object> BoundingVolumeHierarchy<T> {
+    // Every object must have a bounding box!
+    pub fn new(objects: Vec<T>) -> BoundingVolumeHierarchy<T> {
+        let mut primitives: Vec<Primitive> = objects
+            .iter()
+            .enumerate()
+            .map(|(index, object)| {
+                let bounding_box = object
+                    .bounding_box()
+                    .expect("Objects in a bounding volume hierarchy must be bounded.");
+                Primitive {
+                    bounding_box,
+                    centroid: bounding_box.centroid(),
+                    index,
+                }
+            })
+            .collect();
+
+        let mut nodes = Vec::with_capacity(objects.len() * 2);
+        if !primitives.is_empty() {
+            BoundingVolumeHierarchy::<T>::build(&mut nodes, &mut primitives, 0);
+        }
+
+        // Put the objects in the same order as the primitives, so that leaves can refer to ranges.
+        // RUST: Wrapping in Option lets us move objects out of the vector in any order.
+        let mut unordered_objects: Vec<Option<T>> = objects.into_iter().map(Some).collect();
+        let objects = primitives
+            .iter()
+            .map(|primitive| unordered_objects[primitive.index].take().unwrap())
+            .collect();
+
+        BoundingVolumeHierarchy { nodes, objects }
+    }
+
+    // Recursively build the (sub)tree for the primitives and return the index of its root node.
+    // The primitives are reordered in place, offset is the index of the first one in the full list.
+    fn build(nodes: &mut Vec<Node>, primitives: &mut [Primitive], offset: usize) -> usize {
+        let bounding_box =
+            primitives[1..]
+                .iter()
+                .fold(primitives[0].bounding_box, |bounding_box, primitive| {
+                    BoundingBox::surrounding(bounding_box, primitive.bounding_box)
+                });
+
+        let node_index = nodes.len();
+        let leaf = Node::Leaf {
+            bounding_box,
+            first_object: offset,
+            object_count: primitives.len(),
+        };
+        if primitives.len() == 1 {
+            nodes.push(leaf);
+            return node_index;
+        }
+
+        // Split along the axis where the centroids are the most spread out.
+        let centroid_bounds = primitives[1..].iter().fold(
+            BoundingBox::new(primitives[0].centroid, primitives[0].centroid),
+            |bounds, primitive| {
+                BoundingBox::surrounding(
+                    bounds,
+                    BoundingBox::new(primitive.centroid, primitive.centroid),
+                )
+            },
+        );
+        let axis = centroid_bounds.longest_axis();
+        let axis_minimum = centroid_bounds.minimum()[axis];
+        let axis_extent = centroid_bounds.maximum()[axis] - axis_minimum;
+
+        // All centroids in the same place. There is no way to separate them.
+        if axis_extent <= 0.0 {
+            nodes.push(leaf);
+            return node_index;
+        }
+
+        let bucket_of = |primitive: &Primitive| -> usize {
+            let relative_position = (primitive.centroid[axis] - axis_minimum) / axis_extent;
+            ((relative_position * SAH_BUCKET_COUNT as f64) as usize).min(SAH_BUCKET_COUNT - 1)
+        };
+
+        let mut bucket_counts = [0usize; SAH_BUCKET_COUNT];
+        let mut bucket_boxes: [Option<BoundingBox>; SAH_BUCKET_COUNT] = [None; SAH_BUCKET_COUNT];
+        for primitive in primitives.iter() {
+            let bucket = bucket_of(primitive);
+            bucket_counts[bucket] += 1;
+            bucket_boxes[bucket] = Some(surrounding(bucket_boxes[bucket], primitive.bounding_box));
+        }
+
+        // Cost of splitting after each bucket boundary.
+        let mut best_split = 0;
+        let mut best_cost = f64::INFINITY;
+        for split in 0..(SAH_BUCKET_COUNT - 1) {
+            let (left_box, left_count) =
+                merge_buckets(&bucket_boxes[..=split], &bucket_counts[..=split]);
+            let (right_box, right_count) =
+                merge_buckets(&bucket_boxes[split + 1..], &bucket_counts[split + 1..]);
+            if left_count == 0 || right_count == 0 {
+                continue;
+            }
+
+            let cost = SAH_TRAVERSAL_COST
+                + ((left_count as f64 * left_box.unwrap().surface_area())
+                    + (right_count as f64 * right_box.unwrap().surface_area()))
+                    / bounding_box.surface_area();
+            if cost < best_cost {
+                best_cost = cost;
+                best_split = split;
+            }
+        }
+
+        // Intersecting every object in a leaf can be cheaper than splitting it further.
+        let leaf_cost = primitives.len() as f64;
+        if primitives.len() <= MAXIMUM_LEAF_OBJECTS && best_cost >= leaf_cost {
+            nodes.push(leaf);
+            return node_index;
+        }
+
+        // Move the primitives left of the split to the front (a partition, like in quicksort).
+        let mut middle = 0;
+        for i in 0..primitives.len() {
+            if bucket_of(&primitives[i]) <= best_split {
+                primitives.swap(i, middle);
+                middle += 1;
+            }
+        }
+
+        // Placeholder until the right child index is known.
+        nodes.push(Node::Interior {
+            axis,
+            bounding_box,
+            right_child: 0,
+        });
+
+        let (left_primitives, right_primitives) = primitives.split_at_mut(middle);
+        BoundingVolumeHierarchy::<T>::build(nodes, left_primitives, offset);
+        let right_child =
+            BoundingVolumeHierarchy::<T>::build(nodes, right_primitives, offset + middle);
+
+        if let Node::Interior {
+            right_child: child, ..
+        } = &mut nodes[node_index]
+        {
+            *child = right_child;
+        }
+
+        node_index
+    }
+}
+
+fn surrounding(bounding_box: Option<BoundingBox>, other: BoundingBox) -> BoundingBox {
+    match bounding_box {
+        None => other,
+        Some(bounding_box) => BoundingBox::surrounding(bounding_box, other),
+    }
+}
+
+fn merge_buckets(boxes: &[Option<BoundingBox>], counts: &[usize]) -> (Option<BoundingBox>, usize) {
+    let bounding_box = boxes.iter().flatten().fold(None, |merged, bucket_box| {
+        Some(surrounding(merged, *bucket_box))
+    });
+    (bounding_box, counts.iter().sum())
+}
+
+impl<T: Object> Object for BoundingVolumeHierarchy<T> {
+    // Walk the tree with an explicit stack instead of recursion. Of the two children, the one
+    // closest to the ray origin is visited first. A hit there shrinks t_max, which lets us skip the
+    // farther child if its box is behind the hit.
+    fn is_hit_by_ray(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitPoint<'_>> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+
+        let direction = ray.direction();
+        let inverse_direction = Vec3::new(
+            1.0 / direction.x(),
+            1.0 / direction.y(),
+            1.0 / direction.z(),
+        );
+
+        let mut closest_object_so_far = t_max;
+        let mut object_hit_by_ray: Option<HitPoint> = None;
+
+        let mut stack = Vec::with_capacity(64);
+        stack.push(0);
+        while let Some(node_index) = stack.pop() {
+            match &self.nodes[node_index] {
+                Node::Interior {
+                    axis,
+                    bounding_box,
+                    right_child,
+                } => {
+                    if !bounding_box.is_hit_by_ray(
+                        ray,
+                        inverse_direction,
+                        t_min,
+                        closest_object_so_far,
+                    ) {
+                        continue;
+                    }
+
+                    // The stack is last in, first out. So push the closest child last.
+                    let left_child = node_index + 1;
+                    if direction[*axis] < 0.0 {
+                        stack.push(left_child);
+                        stack.push(*right_child);
+                    } else {
+                        stack.push(*right_child);
+                        stack.push(left_child);
+                    }
+                }
+
+                Node::Leaf {
+                    bounding_box,
+                    first_object,
+                    object_count,
+                } => {
+                    if !bounding_box.is_hit_by_ray(
+                        ray,
+                        inverse_direction,
+                        t_min,
+                        closest_object_so_far,
+                    ) {
+                        continue;
+                    }
+
+                    for object in &self.objects[*first_object..(*first_object + *object_count)] {
+                        if let Some(hit_point) =
+                            object.is_hit_by_ray(ray, t_min, closest_object_so_far)
+                        {
+                            closest_object_so_far = hit_point.t();
+                            object_hit_by_ray = Some(hit_point);
+                        }
+                    }
+                }
+            }
+        }
+
+        object_hit_by_ray
+    }
+
+    // The box of the root node contains everything.
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        self.nodes.first().map(|node| match node {
+            Node::Interior { bounding_box, .. } => *bounding_box,
+            Node::Leaf { bounding_box, .. } => *bounding_box,
+        })
+    }
+}
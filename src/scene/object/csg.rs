@@ -0,0 +1,228 @@
+use crate::scene::object::bounding_box::BoundingBox;
+use crate::scene::object::object::{Interval, Object};
+use crate::tracer::hit_point::HitPoint;
+use crate::tracer::ray::Ray;
+use crate::vec3::Vec3;
+
+// How the two solids of a Csg are combined.
+#[derive(Clone, Copy)]
+pub enum CsgOperation {
+    Difference,   // Inside the first, but not the second: the second is cut out of the first.
+    Intersection, // Inside both. Like a lens, from two spheres.
+    Union,        // Inside either.
+}
+
+impl CsgOperation {
+    fn is_inside(&self, inside_first: bool, inside_second: bool) -> bool {
+        match self {
+            CsgOperation::Difference => inside_first && !inside_second,
+            CsgOperation::Intersection => inside_first && inside_second,
+            CsgOperation::Union => inside_first || inside_second,
+        }
+    }
+}
+
+// Constructive solid geometry: a solid made by combining two others, like modelling with clay. The
+// solids have to be closed (see Object::intervals()), and they can be Csgs themselves, to build
+// up more complex shapes.
+//
+// Along a ray, every solid is a list of intervals, where the ray is inside of it. Walking along
+// the ray through where it goes into and out of either solid, the combination is inside or
+// outside depending on the operation. Where that changes, the ray goes through the surface of the
+// combination. That's a point on the surface of one of the two solids, with its material and
+// normal. Where the second solid is cut out of the first, its surface is seen from the inside, so
+// going into the second solid is coming out of the combination.
+pub struct Csg<A: Object, B: Object> {
+    first: A,
+    operation: CsgOperation,
+    second: B,
+}
+
+impl<A: Object, B: Object> Csg<A, B> {
+    pub fn new(first: A, second: B, operation: CsgOperation) -> Csg<A, B> {
+        Csg {
+            first,
+            operation,
+            second,
+        }
+    }
+}
+
+impl<A: Object, B: Object> Object for Csg<A, B> {
+    // The first time the ray goes through the surface of the combination, between t_min and t_max.
+    fn is_hit_by_ray(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitPoint<'_>> {
+        // Nothing to find if the ray misses the box around it all.
+        if let Some(bounding_box) = self.bounding_box() {
+            let direction = ray.direction();
+            let inverse_direction = Vec3::new(
+                1.0 / direction.x(),
+                1.0 / direction.y(),
+                1.0 / direction.z(),
+            );
+            if !bounding_box.is_hit_by_ray(ray, inverse_direction, t_min, t_max) {
+                return None;
+            }
+        }
+
+        self.intervals(ray)
+            .into_iter()
+            .flat_map(|interval| [interval.enter, interval.exit])
+            .find(|hit_point| hit_point.t() > t_min && hit_point.t() < t_max)
+    }
+
+    // The difference is never bigger than the first solid, and the intersection no bigger than
+    // either.
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        let first = self.first.bounding_box();
+        let second = self.second.bounding_box();
+        match self.operation {
+            CsgOperation::Difference => first,
+            CsgOperation::Intersection => match (first, second) {
+                (Some(first), Some(second)) => Some(BoundingBox::new(
+                    first.minimum().maximum(second.minimum()),
+                    first.maximum().minimum(second.maximum()),
+                )),
+                (first, None) => first,
+                (None, second) => second,
+            },
+            CsgOperation::Union => match (first, second) {
+                (Some(first), Some(second)) => Some(BoundingBox::surrounding(first, second)),
+                _ => None,
+            },
+        }
+    }
+
+    // The points where the ray goes into or out of either solid, in order along the ray. Keep the
+    // ones where the ray goes into or out of the combination.
+    fn intervals(&self, ray: &Ray) -> Vec<Interval<'_>> {
+        let mut crossings: Vec<(HitPoint, bool)> = Vec::new(); // And whether it's on the second.
+        for (intervals, is_second) in [
+            (self.first.intervals(ray), false),
+            (self.second.intervals(ray), true),
+        ] {
+            for interval in intervals {
+                crossings.push((interval.enter, is_second));
+                crossings.push((interval.exit, is_second));
+            }
+        }
+        crossings.sort_by(|(a, _), (b, _)| a.t().total_cmp(&b.t()));
+
+        let mut intervals = Vec::new();
+        let mut enter = None;
+        let mut inside = [false, false];
+        for (mut hit_point, is_second) in crossings {
+            let was_inside = self.operation.is_inside(inside[0], inside[1]);
+            inside[usize::from(is_second)] = hit_point.front_facing();
+            let is_inside = self.operation.is_inside(inside[0], inside[1]);
+            if is_inside == was_inside {
+                continue;
+            }
+
+            if is_second && matches!(self.operation, CsgOperation::Difference) {
+                hit_point.flip_front_facing();
+            }
+            match enter.take() {
+                None => enter = Some(hit_point),
+                Some(enter_point) => intervals.push(Interval {
+                    enter: enter_point,
+                    exit: hit_point,
+                }),
+            }
+        }
+        intervals
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::scene::material::diffuse::Diffuse;
+    use crate::scene::object::sphere::Sphere;
+    use crate::vec3::{Color, Point3};
+
+    fn sphere(center_x: f64, radius: f64) -> Sphere {
+        let material = Arc::new(Diffuse::new(Color::new(0.5, 0.5, 0.5)));
+        Sphere::new(Point3::new(center_x, 0.0, 0.0), radius, material)
+    }
+
+    // Along the X axis, from x = -5, so t is x + 5.
+    fn ray_along_x_axis() -> Ray {
+        Ray::new(Point3::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0)
+    }
+
+    // The t of where the ray goes in and out of every interval, and whether those are front facing.
+    fn crossings(object: &impl Object) -> Vec<(f64, bool, f64, bool)> {
+        object
+            .intervals(&ray_along_x_axis())
+            .iter()
+            .map(|interval| {
+                (
+                    interval.enter.t(),
+                    interval.enter.front_facing(),
+                    interval.exit.t(),
+                    interval.exit.front_facing(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn difference_cuts_a_hole_out_of_the_first_solid() {
+        // A ball with a hollow middle: going in at x = -2, into the hole at -1, out of the hole at
+        // 1 and out of the ball at 2.
+        let hollow_ball = Csg::new(sphere(0.0, 2.0), sphere(0.0, 1.0), CsgOperation::Difference);
+        assert_eq!(
+            crossings(&hollow_ball),
+            [(3.0, true, 4.0, false), (6.0, true, 7.0, false)]
+        );
+
+        // The surface of the hole faces the hole, towards the ray.
+        let hit_point = hollow_ball
+            .is_hit_by_ray(&ray_along_x_axis(), 3.5, f64::INFINITY)
+            .unwrap();
+        assert_eq!(hit_point.t(), 4.0);
+        assert_eq!(hit_point.surface_normal().x(), -1.0);
+    }
+
+    #[test]
+    fn difference_with_a_solid_in_front_only_moves_the_entry() {
+        let bitten_ball = Csg::new(
+            sphere(0.0, 2.0),
+            sphere(-2.0, 1.0),
+            CsgOperation::Difference,
+        );
+        assert_eq!(crossings(&bitten_ball), [(4.0, true, 7.0, false)]);
+    }
+
+    #[test]
+    fn intersection_and_union_of_overlapping_spheres() {
+        let lens = Csg::new(
+            sphere(-0.5, 1.0),
+            sphere(0.5, 1.0),
+            CsgOperation::Intersection,
+        );
+        assert_eq!(crossings(&lens), [(4.5, true, 5.5, false)]);
+
+        let union = Csg::new(sphere(-0.5, 1.0), sphere(0.5, 1.0), CsgOperation::Union);
+        assert_eq!(crossings(&union), [(3.5, true, 6.5, false)]);
+
+        let apart = Csg::new(
+            sphere(-2.0, 1.0),
+            sphere(2.0, 1.0),
+            CsgOperation::Intersection,
+        );
+        assert!(crossings(&apart).is_empty());
+        assert!(apart
+            .is_hit_by_ray(&ray_along_x_axis(), 0.001, f64::INFINITY)
+            .is_none());
+    }
+
+    #[test]
+    fn csgs_combine_with_other_csgs() {
+        let hollow_ball = Csg::new(sphere(0.0, 2.0), sphere(0.0, 1.0), CsgOperation::Difference);
+        let half = Csg::new(hollow_ball, sphere(2.0, 2.0), CsgOperation::Difference);
+        assert_eq!(crossings(&half), [(3.0, true, 4.0, false)]);
+    }
+}
@@ -0,0 +1,93 @@
+use std::sync::Arc;
+
+use crate::sampler::Sampler;
+use crate::scene::material::material::Material;
+use crate::scene::object::bounding_box::BoundingBox;
+use crate::scene::object::object::Object;
+use crate::scene::object::quad::Quad;
+use crate::tracer::hit_point::HitPoint;
+use crate::tracer::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+
+// A box with six rectangular sides, aligned with the axes. (Called cuboid, since Box means
+// something else in Rust.) Every side has its own texture coordinates.
+pub struct Cuboid {
+    minimum: Point3,
+    maximum: Point3,
+    sides: [Quad; 6],
+}
+
+impl Cuboid {
+    // Two opposite corners. They don't have to be the smallest and the largest.
+    pub fn new(corner_a: Point3, corner_b: Point3, material: Arc<dyn Material>) -> Cuboid {
+        let minimum = corner_a.minimum(corner_b);
+        let maximum = corner_a.maximum(corner_b);
+
+        let dx = Vec3::new(maximum.x() - minimum.x(), 0.0, 0.0);
+        let dy = Vec3::new(0.0, maximum.y() - minimum.y(), 0.0);
+        let dz = Vec3::new(0.0, 0.0, maximum.z() - minimum.z());
+
+        // The edges are picked so that every side faces outwards.
+        let side = |corner: Point3, edge_u: Vec3, edge_v: Vec3| {
+            Quad::new(corner, edge_u, edge_v, material.clone())
+        };
+        let sides = [
+            side(Point3::new(minimum.x(), minimum.y(), maximum.z()), dx, dy), // Front.
+            side(Point3::new(maximum.x(), minimum.y(), maximum.z()), -dz, dy), // Right.
+            side(Point3::new(maximum.x(), minimum.y(), minimum.z()), -dx, dy), // Back.
+            side(minimum, dz, dy),                                            // Left.
+            side(Point3::new(minimum.x(), maximum.y(), maximum.z()), dx, -dz), // Top.
+            side(minimum, dx, dz),                                            // Bottom.
+        ];
+
+        Cuboid {
+            minimum,
+            maximum,
+            sides,
+        }
+    }
+}
+
+impl Object for Cuboid {
+    // The closest of the sides hit by the ray.
+    fn is_hit_by_ray(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitPoint<'_>> {
+        let mut closest_side_so_far = t_max;
+        let mut side_hit_by_ray = None;
+
+        for side in &self.sides {
+            if let Some(hit_point) = side.is_hit_by_ray(ray, t_min, closest_side_so_far) {
+                closest_side_so_far = hit_point.t();
+                side_hit_by_ray = Some(hit_point);
+            }
+        }
+
+        side_hit_by_ray
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        const PADDING: f64 = 0.0001;
+        let padding = Vec3::new(PADDING, PADDING, PADDING);
+        Some(BoundingBox::new(
+            self.minimum - padding,
+            self.maximum + padding,
+        ))
+    }
+
+    // All sides have the same material.
+    fn is_light_source(&self) -> bool {
+        self.sides[0].is_light_source()
+    }
+
+    // Towards a random side. Sides facing away are picked too; their light is blocked by the sides
+    // in front of them, but that's still correct, just a bit wasteful.
+    fn random_direction_from(&self, origin: Point3, time: f64, sampler: &mut Sampler) -> Vec3 {
+        let side = sampler.index(self.sides.len());
+        self.sides[side].random_direction_from(origin, time, sampler)
+    }
+
+    // Every side is picked as often, so the density is the average of their densities.
+    fn direction_pdf(&self, ray: &Ray) -> f64 {
+        let sum: f64 = self.sides.iter().map(|side| side.direction_pdf(ray)).sum();
+        sum / self.sides.len() as f64
+    }
+}
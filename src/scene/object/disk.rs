@@ -0,0 +1,110 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::sampler::Sampler;
+use crate::scene::material::material::Material;
+use crate::scene::object::bounding_box::BoundingBox;
+use crate::scene::object::object::{area_to_solid_angle_pdf, Object};
+use crate::tracer::hit_point::HitPoint;
+use crate::tracer::ray::Ray;
+use crate::tracer::tracer::T_MIN;
+use crate::vec3::{Point3, Vec3};
+
+// A flat, round, surface. The part of a plane within a radius of the center.
+pub struct Disk {
+    bitangent: Vec3, // Directions along the disk, for the texture coordinates.
+    center: Point3,
+    material: Arc<dyn Material>,
+    normal: Vec3,
+    radius: f64,
+    tangent: Vec3,
+}
+
+impl Disk {
+    pub fn new(center: Point3, normal: Vec3, radius: f64, material: Arc<dyn Material>) -> Disk {
+        let normal = normal.unit_vector();
+        let (tangent, bitangent) = normal.orthonormal_basis();
+        Disk {
+            bitangent,
+            center,
+            material,
+            normal,
+            radius,
+            tangent,
+        }
+    }
+}
+
+impl Object for Disk {
+    // Hit the plane of the disk (see Plane), then check the distance to the center.
+    fn is_hit_by_ray(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitPoint<'_>> {
+        let denominator = ray.direction().dot(self.normal);
+        if denominator.abs() < 1e-12 {
+            return None; // The ray is parallel to the disk.
+        }
+
+        let t = (self.center - ray.origin()).dot(self.normal) / denominator;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+
+        let point = ray.point_at(t);
+        let offset = point - self.center;
+        let distance_squared = offset.length_squared();
+        if distance_squared > self.radius * self.radius {
+            return None;
+        }
+
+        // Polar texture coordinates. u goes around the disk, and v from the center to the edge.
+        let angle = offset.dot(self.bitangent).atan2(offset.dot(self.tangent));
+        let u = (angle + PI) / (2.0 * PI);
+        let v = distance_squared.sqrt() / self.radius;
+
+        Some(HitPoint::new(
+            point,
+            t,
+            self.normal,
+            (u, v),
+            ray,
+            &*self.material,
+        ))
+    }
+
+    // The disk spans less along the axes that the normal points along. Along an axis with the
+    // normal component n, the half size is radius * sqrt(1 - n^2).
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        const PADDING: f64 = 0.0001;
+        let half_size = |n: f64| (self.radius * (1.0 - (n * n)).max(0.0).sqrt()) + PADDING;
+        let extent = Vec3::new(
+            half_size(self.normal.x()),
+            half_size(self.normal.y()),
+            half_size(self.normal.z()),
+        );
+        Some(BoundingBox::new(self.center - extent, self.center + extent))
+    }
+
+    fn is_light_source(&self) -> bool {
+        self.material.emits_light()
+    }
+
+    // Towards a random point on the disk, picked uniformly over its area. The square root keeps the
+    // points from bunching up at the center, where there is less area.
+    fn random_direction_from(&self, origin: Point3, _time: f64, sampler: &mut Sampler) -> Vec3 {
+        let distance = self.radius * sampler.next_f64().sqrt();
+        let angle = 2.0 * PI * sampler.next_f64();
+        let point = self.center
+            + (self.tangent * (distance * angle.cos()))
+            + (self.bitangent * (distance * angle.sin()));
+        point - origin
+    }
+
+    fn direction_pdf(&self, ray: &Ray) -> f64 {
+        match self.is_hit_by_ray(ray, T_MIN, f64::INFINITY) {
+            Some(hit_point) => {
+                let area = PI * self.radius * self.radius;
+                area_to_solid_angle_pdf(ray, hit_point.t(), self.normal, area)
+            }
+            None => 0.0,
+        }
+    }
+}
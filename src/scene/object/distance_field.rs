@@ -0,0 +1,414 @@
+use std::sync::Arc;
+
+use crate::scene::material::material::Material;
+use crate::scene::object::bounding_box::BoundingBox;
+use crate::scene::object::object::Object;
+use crate::tracer::hit_point::HitPoint;
+use crate::tracer::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+
+// How close to the surface counts as on it, in world units.
+const EPSILON: f64 = 1e-4;
+// Give up after this many steps. Rays that just miss the surface slow down next to it, and might
+// never get past.
+const MAX_STEPS: usize = 512;
+// How far to march, in world units, when there is no bounding box to stop at.
+const MAX_DISTANCE: f64 = 1000.0;
+// Steps are a bit shorter than the distance to the surface. Blending shapes and fractals only
+// estimate the distance, and sometimes a bit too much.
+const STEP_FACTOR: f64 = 0.9;
+
+// A signed distance function (SDF): for any point, how far it is from the closest point of the
+// surface of a shape. Negative inside of it. Shapes are built from a few simple ones, blended
+// together, and repeated. The distances of a blend or a fractal are only estimates, but never too
+// far off.
+//
+// See Inigo Quilez, https://iquilezles.org/articles/distfunctions/.
+pub enum DistanceFunction {
+    // A box, with its center, and half of its size along X, Y and Z.
+    Box {
+        center: Point3,
+        half_size: Vec3,
+    },
+    // A sphere stretched along a line, between the two ends.
+    Capsule {
+        ends: [Point3; 2],
+        radius: f64,
+    },
+    // The Mandelbulb fractal, a 3D version of the Mandelbrot set. Up to 2 times the scale from the
+    // center. A power of 8 gives the classic shape, and more iterations give more detail.
+    Mandelbulb {
+        center: Point3,
+        iterations: usize,
+        power: f64,
+        scale: f64,
+    },
+    // The same shape over and over, every period along X, Y and Z (0 for along an axis, the shape
+    // isn't repeated). The shape should fit in the cell around the origin, from -period / 2 to
+    // period / 2.
+    Repetition {
+        period: Vec3,
+        shape: Box<DistanceFunction>,
+    },
+    // A box with edges and corners rounded off, by the radius. The size is the same as for a box.
+    RoundedBox {
+        center: Point3,
+        half_size: Vec3,
+        radius: f64,
+    },
+    Sphere {
+        center: Point3,
+        radius: f64,
+    },
+    // Like a union, but the shapes melt together where they are closer than the smoothness. Blobby
+    // shapes, like drops of water. A smoothness of 0 is the plain union.
+    SmoothUnion {
+        first: Box<DistanceFunction>,
+        second: Box<DistanceFunction>,
+        smoothness: f64,
+    },
+    // The second shape cut out of the first, with the edges of the cut smoothed over. A smoothness
+    // of 0 is the plain difference.
+    SmoothSubtraction {
+        first: Box<DistanceFunction>,
+        second: Box<DistanceFunction>,
+        smoothness: f64,
+    },
+    // A ring, like a doughnut, lying flat (around the Y axis). The major radius goes from the
+    // center to the middle of the tube, the minor radius is that of the tube.
+    Torus {
+        center: Point3,
+        major_radius: f64,
+        minor_radius: f64,
+    },
+}
+
+impl DistanceFunction {
+    pub fn distance(&self, point: Point3) -> f64 {
+        match self {
+            DistanceFunction::Box { center, half_size } => {
+                rounded_box_distance(point - *center, *half_size, 0.0)
+            }
+            DistanceFunction::Capsule { ends, radius } => {
+                // The distance to the closest point of the line between the ends.
+                let along = point - ends[0];
+                let line = ends[1] - ends[0];
+                let h = if line.is_zero() {
+                    0.0
+                } else {
+                    (along.dot(line) / line.length_squared()).clamp(0.0, 1.0)
+                };
+                (along - (line * h)).length() - radius
+            }
+            DistanceFunction::Mandelbulb {
+                center,
+                iterations,
+                power,
+                scale,
+            } => mandelbulb_distance((point - *center) / *scale, *iterations, *power) * scale,
+            DistanceFunction::Repetition { period, shape } => {
+                // Move the point into the cell around the origin.
+                let repeat = |x: f64, period: f64| {
+                    if period > 0.0 {
+                        x - (period * (x / period).round())
+                    } else {
+                        x
+                    }
+                };
+                shape.distance(Point3::new(
+                    repeat(point.x(), period.x()),
+                    repeat(point.y(), period.y()),
+                    repeat(point.z(), period.z()),
+                ))
+            }
+            DistanceFunction::RoundedBox {
+                center,
+                half_size,
+                radius,
+            } => rounded_box_distance(point - *center, *half_size, *radius),
+            DistanceFunction::Sphere { center, radius } => (point - *center).length() - radius,
+            DistanceFunction::SmoothUnion {
+                first,
+                second,
+                smoothness,
+            } => {
+                let a = first.distance(point);
+                let b = second.distance(point);
+                if *smoothness <= 0.0 {
+                    return a.min(b);
+                }
+                // Polynomial smooth minimum. Where the distances are close, less than the
+                // smoothness apart, it's pulled below both, by up to a quarter of the smoothness.
+                let h = (0.5 + (0.5 * (b - a) / smoothness)).clamp(0.0, 1.0);
+                (b * (1.0 - h)) + (a * h) - (smoothness * h * (1.0 - h))
+            }
+            DistanceFunction::SmoothSubtraction {
+                first,
+                second,
+                smoothness,
+            } => {
+                // Inside the first, and outside of the second: the maximum of the distance to the
+                // first and minus the distance to the second. Smoothed like the union.
+                let a = first.distance(point);
+                let b = -second.distance(point);
+                if *smoothness <= 0.0 {
+                    return a.max(b);
+                }
+                let h = (0.5 - (0.5 * (a - b) / smoothness)).clamp(0.0, 1.0);
+                (a * (1.0 - h)) + (b * h) + (smoothness * h * (1.0 - h))
+            }
+            DistanceFunction::Torus {
+                center,
+                major_radius,
+                minor_radius,
+            } => {
+                let point = point - *center;
+                let around = (point.x() * point.x()) + (point.z() * point.z());
+                let from_ring = around.sqrt() - major_radius;
+                ((from_ring * from_ring) + (point.y() * point.y())).sqrt() - minor_radius
+            }
+        }
+    }
+
+    // The box around the shape. None if it goes on forever, like when repeated.
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        let around = |center: Point3, half_size: Vec3| {
+            Some(BoundingBox::new(center - half_size, center + half_size))
+        };
+        match self {
+            DistanceFunction::Box { center, half_size }
+            | DistanceFunction::RoundedBox {
+                center, half_size, ..
+            } => around(*center, *half_size),
+            DistanceFunction::Capsule { ends, radius } => {
+                let radius = Vec3::new(*radius, *radius, *radius);
+                Some(BoundingBox::new(
+                    ends[0].minimum(ends[1]) - radius,
+                    ends[0].maximum(ends[1]) + radius,
+                ))
+            }
+            DistanceFunction::Mandelbulb { center, scale, .. } => {
+                around(*center, Vec3::new(2.0, 2.0, 2.0) * *scale)
+            }
+            DistanceFunction::Repetition { period, shape } => {
+                if period.is_zero() {
+                    shape.bounding_box()
+                } else {
+                    None
+                }
+            }
+            DistanceFunction::Sphere { center, radius } => {
+                around(*center, Vec3::new(*radius, *radius, *radius))
+            }
+            // The blend reaches out by up to a quarter of the smoothness.
+            DistanceFunction::SmoothUnion {
+                first,
+                second,
+                smoothness,
+            } => {
+                let bounding_box =
+                    BoundingBox::surrounding(first.bounding_box()?, second.bounding_box()?);
+                let margin = Vec3::new(1.0, 1.0, 1.0) * (smoothness.max(0.0) / 4.0);
+                Some(BoundingBox::new(
+                    bounding_box.minimum() - margin,
+                    bounding_box.maximum() + margin,
+                ))
+            }
+            // Cutting never makes the first shape any bigger.
+            DistanceFunction::SmoothSubtraction { first, .. } => first.bounding_box(),
+            DistanceFunction::Torus {
+                center,
+                major_radius,
+                minor_radius,
+            } => {
+                let radius = major_radius + minor_radius;
+                around(*center, Vec3::new(radius, *minor_radius, radius))
+            }
+        }
+    }
+}
+
+// The distance to a box around the origin, with rounded edges. Inside, it's the distance to the
+// closest side. Outside, the distance to the closest point on a side, edge or corner.
+fn rounded_box_distance(point: Vec3, half_size: Vec3, radius: f64) -> f64 {
+    let q = Vec3::new(
+        point.x().abs() - half_size.x() + radius,
+        point.y().abs() - half_size.y() + radius,
+        point.z().abs() - half_size.z() + radius,
+    );
+    let outside = q.maximum(Vec3::zero()).length();
+    let inside = q.x().max(q.y()).max(q.z()).min(0.0);
+    outside + inside - radius
+}
+
+// The Mandelbrot set repeats z = z^2 + c, for complex numbers, and a point c is in the set if z
+// never gets far away. The Mandelbulb does the same with 3D points, where raising to a power
+// multiplies the angles of the point (in spherical coordinates) by the power (Daniel White and
+// Paul Nylander, 2009). The distance is estimated from how fast z runs away: 0.5*ln(r)*r/dr, where
+// dr is the derivative of z.
+fn mandelbulb_distance(point: Point3, iterations: usize, power: f64) -> f64 {
+    let mut z = point;
+    let mut derivative = 1.0;
+    let mut r = z.length();
+    for _ in 0..iterations {
+        if r > 2.0 {
+            break;
+        }
+        // Y is up, like in the rest of the scene.
+        let theta = if r > 0.0 { (z.y() / r).acos() } else { 0.0 };
+        let phi = z.z().atan2(z.x());
+        derivative = (power * r.powf(power - 1.0) * derivative) + 1.0;
+
+        let theta = theta * power;
+        let phi = phi * power;
+        z = (Vec3::new(
+            theta.sin() * phi.cos(),
+            theta.cos(),
+            theta.sin() * phi.sin(),
+        ) * r.powf(power))
+            + point;
+        r = z.length();
+    }
+
+    if r == 0.0 {
+        return 0.0;
+    }
+    0.5 * r.ln() * r / derivative
+}
+
+// A shape given by a signed distance function, found by "sphere tracing" (John C. Hart, 1996).
+// There's no formula for where a ray hits it. But nothing is closer than the distance, so the ray
+// can safely go that far, and then ask again. Close to the surface, the steps get smaller and
+// smaller, until the ray is on it. Far away from it, they are big.
+//
+// The surface normal is the direction in which the distance grows the fastest (the gradient),
+// found from the distance just next to the hit point, along X, Y and Z ("finite differences").
+pub struct DistanceField {
+    bounding_box: Option<BoundingBox>,
+    function: DistanceFunction,
+    material: Arc<dyn Material>,
+}
+
+impl DistanceField {
+    pub fn new(function: DistanceFunction, material: Arc<dyn Material>) -> DistanceField {
+        // A bit bigger than the shape, so that the marching starts away from its surface (see
+        // is_hit_by_ray()).
+        let margin = Vec3::new(1.0, 1.0, 1.0) * (10.0 * EPSILON);
+        let bounding_box = function.bounding_box().map(|bounding_box| {
+            BoundingBox::new(
+                bounding_box.minimum() - margin,
+                bounding_box.maximum() + margin,
+            )
+        });
+        DistanceField {
+            bounding_box,
+            function,
+            material,
+        }
+    }
+
+    // The gradient of the distance, by central differences.
+    fn surface_normal(&self, point: Point3) -> Option<Vec3> {
+        let difference = |offset: Vec3| {
+            self.function.distance(point + offset) - self.function.distance(point - offset)
+        };
+        let gradient = Vec3::new(
+            difference(Vec3::new(EPSILON, 0.0, 0.0)),
+            difference(Vec3::new(0.0, EPSILON, 0.0)),
+            difference(Vec3::new(0.0, 0.0, EPSILON)),
+        );
+        if gradient.is_zero() {
+            return None;
+        }
+        Some(gradient.unit_vector())
+    }
+
+    // Where the distance changes sign, between two points along the ray, by cutting the stretch in
+    // half, over and over.
+    fn crossing(&self, ray: &Ray, mut t_before: f64, mut t_after: f64) -> f64 {
+        let sign_before = self.function.distance(ray.point_at(t_before)).signum();
+        for _ in 0..32 {
+            let t = (t_before + t_after) / 2.0;
+            if self.function.distance(ray.point_at(t)).signum() == sign_before {
+                t_before = t;
+            } else {
+                t_after = t;
+            }
+        }
+        (t_before + t_after) / 2.0
+    }
+
+    fn hit_point(&self, ray: &Ray, t: f64) -> HitPoint<'_> {
+        let point = ray.point_at(t);
+        let surface_normal = self
+            .surface_normal(point)
+            .unwrap_or(-ray.direction().unit_vector());
+
+        // Onto the surface, so that the next ray starts right on it.
+        let point = point - (surface_normal * self.function.distance(point));
+
+        // There are no texture coordinates, so image textures show a single color.
+        HitPoint::new(point, t, surface_normal, (0.0, 0.0), ray, &*self.material)
+    }
+}
+
+impl Object for DistanceField {
+    // A ray that bounced off the surface starts on it. The first steps have to get away from it,
+    // or the ray would hit it right away, again. So a hit only counts once the ray has been
+    // further away than EPSILON. Going through the surface, in a step that was a bit too long,
+    // also counts, since the distance is only estimated for some shapes.
+    fn is_hit_by_ray(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitPoint<'_>> {
+        let direction = ray.direction();
+        let direction_length = direction.length();
+        let (mut t, t_end) = match self.bounding_box {
+            Some(bounding_box) => {
+                let inverse_direction = Vec3::new(
+                    1.0 / direction.x(),
+                    1.0 / direction.y(),
+                    1.0 / direction.z(),
+                );
+                bounding_box.ray_interval(ray, inverse_direction, t_min, t_max)?
+            }
+            None => (
+                t_min.max(-MAX_DISTANCE / direction_length),
+                t_max.min(MAX_DISTANCE / direction_length),
+            ),
+        };
+
+        let mut is_away = false;
+        let mut previous: Option<(f64, f64)> = None; // The last t, and the distance there.
+        for _ in 0..MAX_STEPS {
+            if t > t_end {
+                return None;
+            }
+            let distance = self.function.distance(ray.point_at(t));
+
+            if is_away {
+                if let Some((t_previous, distance_previous)) = previous {
+                    if distance.signum() != distance_previous.signum() {
+                        let t = self.crossing(ray, t_previous, t);
+                        return (t > t_min && t < t_max).then(|| self.hit_point(ray, t));
+                    }
+                }
+                if distance.abs() < EPSILON && t > t_min {
+                    return Some(self.hit_point(ray, t));
+                }
+            }
+            if distance.abs() >= EPSILON {
+                is_away = true;
+            }
+
+            previous = Some((t, distance));
+            t += (distance.abs() * STEP_FACTOR).max(EPSILON) / direction_length;
+        }
+        None
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        self.bounding_box
+    }
+
+    fn is_light_source(&self) -> bool {
+        self.material.emits_light()
+    }
+}
@@ -1,3 +1,17 @@
+pub mod bounding_box;
+pub mod bounding_volume_hierarchy;
+pub mod csg;
+pub mod cuboid;
+pub mod disk;
+pub mod distance_field;
+pub mod moving_sphere;
+#[allow(clippy::module_inception)]
 pub mod object;
+pub mod plane;
+pub mod quad;
 pub mod sphere;
+pub mod transformed;
+pub mod triangle;
+pub mod triangle_mesh;
+pub mod volume;
 pub mod world;
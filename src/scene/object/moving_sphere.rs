@@ -0,0 +1,84 @@
+use std::sync::Arc;
+
+use crate::sampler::Sampler;
+use crate::scene::material::material::Material;
+use crate::scene::object::bounding_box::BoundingBox;
+use crate::scene::object::object::Object;
+use crate::scene::object::sphere::{
+    is_sphere_hit_by_ray, random_direction_towards_sphere, sphere_direction_pdf,
+};
+use crate::tracer::hit_point::HitPoint;
+use crate::tracer::ray::Ray;
+use crate::tracer::tracer::T_MIN;
+use crate::vec3::{Point3, Vec3};
+
+// A sphere that moves in a straight line, from one center at one time to another center at
+// another time. It stays put before and after. Rendered with a shutter interval, it's blurred
+// along its path (motion blur).
+pub struct MovingSphere {
+    center_end: Point3,
+    center_start: Point3,
+    material: Arc<dyn Material>,
+    radius: f64,
+    time_end: f64,
+    time_start: f64,
+}
+
+impl MovingSphere {
+    pub fn new(
+        (center_start, center_end): (Point3, Point3),
+        (time_start, time_end): (f64, f64),
+        radius: f64,
+        material: Arc<dyn Material>,
+    ) -> MovingSphere {
+        MovingSphere {
+            center_end,
+            center_start,
+            material,
+            radius,
+            time_end,
+            time_start,
+        }
+    }
+
+    // Linear interpolation between the two centers.
+    fn center(&self, time: f64) -> Point3 {
+        if self.time_end <= self.time_start {
+            return self.center_start;
+        }
+        let fraction =
+            ((time - self.time_start) / (self.time_end - self.time_start)).clamp(0.0, 1.0);
+        self.center_start + ((self.center_end - self.center_start) * fraction)
+    }
+}
+
+impl Object for MovingSphere {
+    // A sphere, where it is at the time of the ray.
+    fn is_hit_by_ray(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitPoint<'_>> {
+        let center = self.center(ray.time());
+        is_sphere_hit_by_ray(center, self.radius, &*self.material, ray, t_min, t_max)
+    }
+
+    // Contains the sphere at every time, so the boxes at both ends of the path.
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        let radius = Vec3::new(self.radius, self.radius, self.radius);
+        let start = BoundingBox::new(self.center_start - radius, self.center_start + radius);
+        let end = BoundingBox::new(self.center_end - radius, self.center_end + radius);
+        Some(BoundingBox::surrounding(start, end))
+    }
+
+    fn is_light_source(&self) -> bool {
+        self.material.emits_light()
+    }
+
+    fn random_direction_from(&self, origin: Point3, time: f64, sampler: &mut Sampler) -> Vec3 {
+        random_direction_towards_sphere(self.center(time), self.radius, origin, sampler)
+    }
+
+    fn direction_pdf(&self, ray: &Ray) -> f64 {
+        if self.is_hit_by_ray(ray, T_MIN, f64::INFINITY).is_none() {
+            return 0.0;
+        }
+        sphere_direction_pdf(self.center(ray.time()), self.radius, ray.origin())
+    }
+}
@@ -1,6 +1,140 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::sampler::Sampler;
+use crate::scene::object::bounding_box::BoundingBox;
 use crate::tracer::hit_point::HitPoint;
 use crate::tracer::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+
+// RUST: See Material for why objects must be Send and Sync.
+pub trait Object: Send + Sync {
+    fn is_hit_by_ray(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitPoint<'_>>;
+
+    // The box that contains the whole object. None if the object is infinite in size, since then
+    // no box can contain it.
+    fn bounding_box(&self) -> Option<BoundingBox>;
+
+    // Light sampling, for next-event estimation (see tracer.rs). Instead of waiting for a ray to
+    // bounce into a light by chance, the tracer aims rays straight at the lights. Objects that give
+    // off light, and know how to be aimed at, say so here.
+    fn is_light_source(&self) -> bool {
+        false
+    }
+
+    // A random direction from the origin towards the object (not a unit vector). By default any
+    // direction at all, which is correct but wasteful; most of them miss the object.
+    fn random_direction_from(&self, _origin: Point3, _time: f64, sampler: &mut Sampler) -> Vec3 {
+        Vec3::random_unit_vector(sampler)
+    }
+
+    // The probability density of random_direction_from() picking the direction of the ray, from
+    // the origin of the ray. Per solid angle (per unit area on the unit sphere of directions), so
+    // directions uniformly over the whole sphere have the density 1/(4*pi).
+    fn direction_pdf(&self, _ray: &Ray) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+
+    // Where the ray is inside of the object, all along the (infinite) line of the ray, in order.
+    // For constructive solid geometry (see Csg), which needs to know more than the closest hit.
+    // This only makes sense for closed objects ("solids"), like spheres, boxes and closed meshes.
+    //
+    // By default, it follows the ray from hit to hit. Hitting the front of the surface goes into
+    // the object, the back comes out of it. A ray that hits an edge between two triangles can hit
+    // both, going in twice in a row; the second one is skipped.
+    fn intervals(&self, ray: &Ray) -> Vec<Interval<'_>> {
+        let mut intervals = Vec::new();
+        let mut enter: Option<HitPoint> = None;
+        let mut t_min = f64::NEG_INFINITY;
+        while let Some(hit_point) = self.is_hit_by_ray(ray, t_min, f64::INFINITY) {
+            // Just past the hit point, so it isn't found again.
+            t_min = hit_point.t() + (1e-9 * hit_point.t().abs().max(1.0));
+            match (enter.take(), hit_point.front_facing()) {
+                (None, true) => enter = Some(hit_point),
+                (Some(enter_point), false) => intervals.push(Interval {
+                    enter: enter_point,
+                    exit: hit_point,
+                }),
+                (current, _) => enter = current,
+            }
+        }
+        intervals
+    }
+}
+
+// A stretch of a ray inside of an object, from where it goes in to where it comes out.
+pub struct Interval<'a> {
+    pub enter: HitPoint<'a>,
+    pub exit: HitPoint<'a>,
+}
+
+// The probability density, per solid angle, of hitting a flat light source at a distance t along
+// the ray, when points are picked uniformly over its area. A bit of area seen from far away, or
+// from the side (at a grazing angle), covers less of the sphere of directions. So it's hit by more
+// of them: distance^2 / (cosine * area).
+pub fn area_to_solid_angle_pdf(ray: &Ray, t: f64, normal: Vec3, area: f64) -> f64 {
+    let distance_squared = t * t * ray.direction().length_squared();
+    let cosine = (ray.direction().unit_vector().dot(normal)).abs();
+    if cosine < 1e-12 {
+        return 0.0;
+    }
+    distance_squared / (cosine * area)
+}
+
+// A boxed object is an object too. This lets a World hold different kinds of objects (spheres,
+// meshes, etc.) as Box<dyn Object>.
+//
+// RUST: ?Sized allows T to be a trait object (dyn Object), whose size isn't known at compile time.
+impl<T: Object + ?Sized> Object for Box<T> {
+    fn is_hit_by_ray(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitPoint<'_>> {
+        (**self).is_hit_by_ray(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        (**self).bounding_box()
+    }
+
+    fn is_light_source(&self) -> bool {
+        (**self).is_light_source()
+    }
+
+    fn random_direction_from(&self, origin: Point3, time: f64, sampler: &mut Sampler) -> Vec3 {
+        (**self).random_direction_from(origin, time, sampler)
+    }
+
+    fn direction_pdf(&self, ray: &Ray) -> f64 {
+        (**self).direction_pdf(ray)
+    }
+
+    fn intervals(&self, ray: &Ray) -> Vec<Interval<'_>> {
+        (**self).intervals(ray)
+    }
+}
+
+// Same for a shared object. This lets many Transformed objects use the same (large) object, like a
+// mesh, without copying it.
+impl<T: Object + ?Sized> Object for Arc<T> {
+    fn is_hit_by_ray(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitPoint<'_>> {
+        (**self).is_hit_by_ray(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        (**self).bounding_box()
+    }
+
+    fn is_light_source(&self) -> bool {
+        (**self).is_light_source()
+    }
+
+    fn random_direction_from(&self, origin: Point3, time: f64, sampler: &mut Sampler) -> Vec3 {
+        (**self).random_direction_from(origin, time, sampler)
+    }
+
+    fn direction_pdf(&self, ray: &Ray) -> f64 {
+        (**self).direction_pdf(ray)
+    }
 
-pub trait Object {
-    fn is_hit_by_ray(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitPoint>;
+    fn intervals(&self, ray: &Ray) -> Vec<Interval<'_>> {
+        (**self).intervals(ray)
+    }
 }
@@ -0,0 +1,67 @@
+use std::sync::Arc;
+
+use crate::scene::material::material::Material;
+use crate::scene::object::bounding_box::BoundingBox;
+use crate::scene::object::object::Object;
+use crate::tracer::hit_point::HitPoint;
+use crate::tracer::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+
+// An infinite, flat, surface. Like the ground, all the way to the horizon. It's every point P
+// where (P-Q)*n=0, for a point Q on the plane and its normal n.
+pub struct Plane {
+    bitangent: Vec3, // Directions along the plane, for the texture coordinates.
+    material: Arc<dyn Material>,
+    normal: Vec3,
+    point: Point3,
+    tangent: Vec3,
+}
+
+impl Plane {
+    pub fn new(point: Point3, normal: Vec3, material: Arc<dyn Material>) -> Plane {
+        let normal = normal.unit_vector();
+        let (tangent, bitangent) = normal.orthonormal_basis();
+        Plane {
+            bitangent,
+            material,
+            normal,
+            point,
+            tangent,
+        }
+    }
+}
+
+impl Object for Plane {
+    // Substitute the ray, P(t)=A+tB, in the plane equation: (A+tB-Q)*n=0. Then t=((Q-A)*n)/(B*n).
+    fn is_hit_by_ray(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitPoint<'_>> {
+        let denominator = ray.direction().dot(self.normal);
+        if denominator.abs() < 1e-12 {
+            return None; // The ray is parallel to the plane.
+        }
+
+        let t = (self.point - ray.origin()).dot(self.normal) / denominator;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+
+        // The texture repeats every unit along the plane.
+        let point = ray.point_at(t);
+        let offset = point - self.point;
+        let u = offset.dot(self.tangent);
+        let v = offset.dot(self.bitangent);
+
+        Some(HitPoint::new(
+            point,
+            t,
+            self.normal,
+            (u - u.floor(), v - v.floor()),
+            ray,
+            &*self.material,
+        ))
+    }
+
+    // No box can contain an infinite plane.
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        None
+    }
+}
@@ -0,0 +1,130 @@
+use std::sync::Arc;
+
+use crate::sampler::Sampler;
+use crate::scene::material::material::Material;
+use crate::scene::object::bounding_box::BoundingBox;
+use crate::scene::object::object::{area_to_solid_angle_pdf, Object};
+use crate::tracer::hit_point::HitPoint;
+use crate::tracer::ray::Ray;
+use crate::tracer::tracer::T_MIN;
+use crate::vec3::{Point3, Vec3};
+
+// A quadrilateral (parallelogram) given by a corner Q and two edges u and v starting from it. A
+// rectangle when the edges are perpendicular. The corners are Q, Q+u, Q+v and Q+u+v, and the front
+// faces the direction of u×v (counter-clockwise from u to v).
+pub struct Quad {
+    corner: Point3,
+    edge_u: Vec3,
+    edge_v: Vec3,
+    material: Arc<dyn Material>,
+    normal: Vec3,
+    w: Vec3, // n/(n*n), where n=u×v. Used to find the planar coordinates of a point.
+}
+
+impl Quad {
+    pub fn new(corner: Point3, edge_u: Vec3, edge_v: Vec3, material: Arc<dyn Material>) -> Quad {
+        let n = edge_u.cross(edge_v);
+        Quad {
+            corner,
+            edge_u,
+            edge_v,
+            material,
+            normal: n.unit_vector(),
+            w: n / n.dot(n),
+        }
+    }
+
+    // A rectangle in an axis plane, between two opposite corners. One of the coordinates must be
+    // the same for both corners, otherwise there is no rectangle (None). The front faces the
+    // positive direction of that axis.
+    pub fn axis_aligned(
+        corner_a: Point3,
+        corner_b: Point3,
+        material: Arc<dyn Material>,
+    ) -> Option<Quad> {
+        let minimum = corner_a.minimum(corner_b);
+        let size = corner_a.maximum(corner_b) - minimum;
+        let (edge_u, edge_v) = if size.x() == 0.0 {
+            (Vec3::new(0.0, size.y(), 0.0), Vec3::new(0.0, 0.0, size.z()))
+        } else if size.y() == 0.0 {
+            (Vec3::new(0.0, 0.0, size.z()), Vec3::new(size.x(), 0.0, 0.0))
+        } else if size.z() == 0.0 {
+            (Vec3::new(size.x(), 0.0, 0.0), Vec3::new(0.0, size.y(), 0.0))
+        } else {
+            return None;
+        };
+
+        Some(Quad::new(minimum, edge_u, edge_v, material))
+    }
+}
+
+impl Object for Quad {
+    // First hit the plane of the quad, like Plane does. Then write the hit point P as
+    // Q + (alpha * u) + (beta * v). The point is inside the quad when both alpha and beta are
+    // between 0 and 1, and they are the texture coordinates too.
+    fn is_hit_by_ray(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitPoint<'_>> {
+        let denominator = ray.direction().dot(self.normal);
+        if denominator.abs() < 1e-12 {
+            return None; // The ray is parallel to the quad.
+        }
+
+        let t = (self.corner - ray.origin()).dot(self.normal) / denominator;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+
+        let point = ray.point_at(t);
+        let planar_point = point - self.corner;
+        let alpha = self.w.dot(planar_point.cross(self.edge_v));
+        let beta = self.w.dot(self.edge_u.cross(planar_point));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+
+        Some(HitPoint::new(
+            point,
+            t,
+            self.normal,
+            (alpha, beta),
+            ray,
+            &*self.material,
+        ))
+    }
+
+    // Padded a little, so that a quad lying in an axis plane doesn't get a flat box.
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        const PADDING: f64 = 0.0001;
+        let padding = Vec3::new(PADDING, PADDING, PADDING);
+
+        let corners = [
+            self.corner,
+            self.corner + self.edge_u,
+            self.corner + self.edge_v,
+            self.corner + self.edge_u + self.edge_v,
+        ];
+        let minimum = corners.iter().fold(corners[0], |a, b| a.minimum(*b));
+        let maximum = corners.iter().fold(corners[0], |a, b| a.maximum(*b));
+        Some(BoundingBox::new(minimum - padding, maximum + padding))
+    }
+
+    fn is_light_source(&self) -> bool {
+        self.material.emits_light()
+    }
+
+    // Towards a random point on the quad, picked uniformly over its area.
+    fn random_direction_from(&self, origin: Point3, _time: f64, sampler: &mut Sampler) -> Vec3 {
+        let point =
+            self.corner + (self.edge_u * sampler.next_f64()) + (self.edge_v * sampler.next_f64());
+        point - origin
+    }
+
+    fn direction_pdf(&self, ray: &Ray) -> f64 {
+        match self.is_hit_by_ray(ray, T_MIN, f64::INFINITY) {
+            Some(hit_point) => {
+                let area = self.edge_u.cross(self.edge_v).length();
+                area_to_solid_angle_pdf(ray, hit_point.t(), self.normal, area)
+            }
+            None => 0.0,
+        }
+    }
+}
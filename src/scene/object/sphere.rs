@@ -1,77 +1,176 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::sampler::Sampler;
 use crate::scene::material::material::Material;
+use crate::scene::object::bounding_box::BoundingBox;
 use crate::scene::object::object::Object;
 use crate::tracer::hit_point::HitPoint;
 use crate::tracer::ray::Ray;
-use crate::vec3::Point3;
+use crate::tracer::tracer::T_MIN;
+use crate::vec3::{Point3, Vec3};
 
 pub struct Sphere {
     center: Point3,              // Sphere center position.
-    material: Box<dyn Material>, // RUST: Box<> allocates the value on the heap. For owned trait objects.
+    material: Arc<dyn Material>, // RUST: Arc<> is a reference counted pointer. The material can be shared by many objects, and threads.
     radius: f64,
 }
 
 impl Sphere {
-    pub fn new(center: Point3, radius: f64, material: Box<dyn Material>) -> Sphere {
+    pub fn new(center: Point3, radius: f64, material: Arc<dyn Material>) -> Sphere {
         Sphere {
             center,
             material,
             radius,
         }
     }
+
+    // Spherical coordinates of a point on the unit sphere, as texture coordinates. u goes around
+    // the sphere (the angle around the Y axis, starting from -X) and v from the bottom (-Y) to the
+    // top (+Y). Like longitude and latitude on a globe.
+    fn texture_coordinates(point: Point3) -> (f64, f64) {
+        let theta = (-point.y()).acos();
+        let phi = (-point.z()).atan2(point.x()) + PI;
+        (phi / (2.0 * PI), theta / PI)
+    }
 }
 
 impl Object for Sphere {
-    // The equation for a sphere in vector form is: (P-C)*(P-C)=r2. Any point P that satisfies this
-    // equation is on the sphere.
-    //
-    // Ray: P(t)=A+tB
-    // Sphere: (P-C)*(P-C)=r2
-    //
-    // If a ray hits the sphere, there is some t for which P(t) satisfies the sphere equation. So we
-    // are looking for any t where this is true: (P(t)-C)*(P(t)-C)=r2. Or in expanded form:
-    // (A+tB-C)*(A+tB-C)=r2
-    //
-    // Having t_min and t_max helps in other parts of the code, to decide when to do certain
-    // calculations or help to avoid visual artifacts due to computational limitations (shadow acne,
-    // etc.).
-    fn is_hit_by_ray(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitPoint> {
-        let ray_direction = ray.direction();
-
-        let oc = ray.origin() - self.center;
-        let a = ray_direction.length_squared();
-        let half_b = oc.dot(ray_direction);
-        let c = oc.length_squared() - (self.radius * self.radius);
-        let discriminant = (half_b * half_b) - (a * c); // It's two, one, or no solutions!
-
-        // The ray touches the sphere in one point (0, tangent) or two points (>0, intersected). Return
-        // the smaller, positive, t (closest). This should be the first intersection when a ray, facing
-        // the sphere, hits it.
-        if discriminant > 0.0 {
-            let discriminant_square_root = discriminant.sqrt();
-
-            let mut t = (-half_b - discriminant_square_root) / a; // Distance (along the ray), t0, to intersection.
-            if t > t_min && t < t_max {
-                // Create a "surface normal" so that we can shade the sphere and get a sense of 3D. This is
-                // an outward surface normal because the ray hits the outside of an _opaque_ sphere.
-                let point = ray.point_at(t);
-                let outward_surface_normal = (point - self.center) / self.radius;
-
-                let hit_point =
-                    HitPoint::new(point, t, outward_surface_normal, &ray, &*self.material);
-                return Some(hit_point);
-            }
-
-            t = (-half_b + discriminant_square_root) / a;
-            if t > t_min && t < t_max {
-                let point = ray.point_at(t);
-                let outward_surface_normal = (point - self.center) / self.radius;
-
-                let hit_point =
-                    HitPoint::new(point, t, outward_surface_normal, &ray, &*self.material);
-                return Some(hit_point);
-            }
+    fn is_hit_by_ray(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitPoint<'_>> {
+        is_sphere_hit_by_ray(self.center, self.radius, &*self.material, ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        let radius = Vec3::new(self.radius, self.radius, self.radius);
+        Some(BoundingBox::new(self.center - radius, self.center + radius))
+    }
+
+    fn is_light_source(&self) -> bool {
+        self.material.emits_light()
+    }
+
+    fn random_direction_from(&self, origin: Point3, _time: f64, sampler: &mut Sampler) -> Vec3 {
+        random_direction_towards_sphere(self.center, self.radius, origin, sampler)
+    }
+
+    fn direction_pdf(&self, ray: &Ray) -> f64 {
+        if self.is_hit_by_ray(ray, T_MIN, f64::INFINITY).is_none() {
+            return 0.0;
         }
+        sphere_direction_pdf(self.center, self.radius, ray.origin())
+    }
+}
 
-        None // (discriminant < 0.0) means that the ray doesn't touch the sphere.
+// Seen from the origin, the sphere covers a cone of directions. Pick one uniformly (by solid angle)
+// within the cone. The cone is narrower the further away the sphere is: the cosine of its half
+// angle is sqrt(1 - r^2/d^2). From inside the sphere every direction hits it, so pick any.
+//
+// Shared with MovingSphere.
+pub fn random_direction_towards_sphere(
+    center: Point3,
+    radius: f64,
+    origin: Point3,
+    sampler: &mut Sampler,
+) -> Vec3 {
+    let to_center = center - origin;
+    let distance_squared = to_center.length_squared();
+    if distance_squared <= radius * radius {
+        return Vec3::random_unit_vector(sampler);
     }
+
+    let cos_theta_max = (1.0 - ((radius * radius) / distance_squared)).sqrt();
+    let cos_theta = 1.0 + (sampler.next_f64() * (cos_theta_max - 1.0));
+    let sin_theta = (1.0 - (cos_theta * cos_theta)).max(0.0).sqrt();
+    let phi = 2.0 * PI * sampler.next_f64();
+
+    let axis = to_center.unit_vector();
+    let (tangent, bitangent) = axis.orthonormal_basis();
+    (tangent * (phi.cos() * sin_theta)) + (bitangent * (phi.sin() * sin_theta)) + (axis * cos_theta)
+}
+
+// The density of random_direction_towards_sphere(): one over the solid angle of the cone, which is
+// 2*pi*(1 - cos(theta_max)). The whole sphere of directions (4*pi) from the inside.
+pub fn sphere_direction_pdf(center: Point3, radius: f64, origin: Point3) -> f64 {
+    let distance_squared = (center - origin).length_squared();
+    if distance_squared <= radius * radius {
+        return 1.0 / (4.0 * PI);
+    }
+
+    let cos_theta_max = (1.0 - ((radius * radius) / distance_squared)).sqrt();
+    1.0 / (2.0 * PI * (1.0 - cos_theta_max))
+}
+
+// The equation for a sphere in vector form is: (P-C)*(P-C)=r2. Any point P that satisfies this
+// equation is on the sphere.
+//
+// Ray: P(t)=A+tB
+// Sphere: (P-C)*(P-C)=r2
+//
+// If a ray hits the sphere, there is some t for which P(t) satisfies the sphere equation. So we
+// are looking for any t where this is true: (P(t)-C)*(P(t)-C)=r2. Or in expanded form:
+// (A+tB-C)*(A+tB-C)=r2
+//
+// Having t_min and t_max helps in other parts of the code, to decide when to do certain
+// calculations or help to avoid visual artifacts due to computational limitations (shadow acne,
+// etc.).
+//
+// Shared with MovingSphere, whose center depends on the time of the ray.
+pub fn is_sphere_hit_by_ray<'a>(
+    center: Point3,
+    radius: f64,
+    material: &'a dyn Material,
+    ray: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<HitPoint<'a>> {
+    let ray_direction = ray.direction();
+
+    let oc = ray.origin() - center;
+    let a = ray_direction.length_squared();
+    let half_b = oc.dot(ray_direction);
+    let c = oc.length_squared() - (radius * radius);
+    let discriminant = (half_b * half_b) - (a * c); // It's two, one, or no solutions!
+
+    // The ray touches the sphere in one point (0, tangent) or two points (>0, intersected). Return
+    // the smaller, positive, t (closest). This should be the first intersection when a ray, facing
+    // the sphere, hits it.
+    if discriminant > 0.0 {
+        let discriminant_square_root = discriminant.sqrt();
+
+        let mut t = (-half_b - discriminant_square_root) / a; // Distance (along the ray), t0, to intersection.
+        if t > t_min && t < t_max {
+            // Create a "surface normal" so that we can shade the sphere and get a sense of 3D. This is
+            // an outward surface normal because the ray hits the outside of an _opaque_ sphere.
+            let point = ray.point_at(t);
+            let outward_surface_normal = (point - center) / radius;
+
+            let hit_point = HitPoint::new(
+                point,
+                t,
+                outward_surface_normal,
+                Sphere::texture_coordinates(outward_surface_normal),
+                ray,
+                material,
+            );
+            return Some(hit_point);
+        }
+
+        t = (-half_b + discriminant_square_root) / a;
+        if t > t_min && t < t_max {
+            let point = ray.point_at(t);
+            let outward_surface_normal = (point - center) / radius;
+
+            let hit_point = HitPoint::new(
+                point,
+                t,
+                outward_surface_normal,
+                Sphere::texture_coordinates(outward_surface_normal),
+                ray,
+                material,
+            );
+            return Some(hit_point);
+        }
+    }
+
+    None // (discriminant < 0.0) means that the ray doesn't touch the sphere.
 }
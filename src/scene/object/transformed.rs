@@ -0,0 +1,215 @@
+use crate::matrix4::{matrices_of_steps, Matrix4, TransformStep};
+use crate::sampler::Sampler;
+use crate::scene::object::bounding_box::BoundingBox;
+use crate::scene::object::object::Object;
+use crate::tracer::hit_point::HitPoint;
+use crate::tracer::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+
+// An object that is moved, rotated and/or scaled. Instead of transforming the object itself, which
+// would mean copying it, the ray is transformed the opposite way into the object's own space
+// ("object space"). The hit point found there is then transformed back into the world.
+//
+// With T = Arc<...>, the same object can be placed in the world many times ("instancing").
+pub struct Transformed<T: Object> {
+    motion: Motion,
+    object: T,
+}
+
+enum Motion {
+    // The same transform at all times.
+    Fixed {
+        object_to_world: Matrix4,
+        world_to_object: Matrix4, // The inverse.
+    },
+    // The transform changes from the start steps at the start time, to the end steps at the end
+    // time. Every step is blended separately, see TransformStep::interpolate().
+    Moving {
+        end: Vec<TransformStep>,
+        start: Vec<TransformStep>,
+        time_end: f64,
+        time_start: f64,
+    },
+}
+
+// How many points in time the bounding box of a moving object is made to contain.
+const MOTION_BOUNDING_BOX_SAMPLES: u32 = 32;
+
+impl<T: Object> Transformed<T> {
+    // None if the transform can't be undone (inverted), like a scaling by zero.
+    pub fn new(object: T, object_to_world: Matrix4) -> Option<Transformed<T>> {
+        Some(Transformed {
+            motion: Motion::Fixed {
+                object_to_world,
+                world_to_object: object_to_world.inverse()?,
+            },
+            object,
+        })
+    }
+
+    // An object with a transform that changes over time, for motion blur. It stays put before
+    // the start time and after the end time. None if the steps don't match up (the same kinds of
+    // steps, in the same order).
+    pub fn moving(
+        object: T,
+        (start, end): (Vec<TransformStep>, Vec<TransformStep>),
+        (time_start, time_end): (f64, f64),
+    ) -> Option<Transformed<T>> {
+        if start.len() != end.len()
+            || start
+                .iter()
+                .zip(&end)
+                .any(|(start_step, end_step)| start_step.interpolate(end_step, 0.0).is_none())
+        {
+            return None;
+        }
+
+        Some(Transformed {
+            motion: Motion::Moving {
+                end,
+                start,
+                time_end,
+                time_start,
+            },
+            object,
+        })
+    }
+
+    // The object to world matrix, and its inverse, at a time.
+    fn matrices_at(&self, time: f64) -> (Matrix4, Matrix4) {
+        match &self.motion {
+            Motion::Fixed {
+                object_to_world,
+                world_to_object,
+            } => (*object_to_world, *world_to_object),
+            Motion::Moving {
+                end,
+                start,
+                time_end,
+                time_start,
+            } => {
+                let fraction = if time_end > time_start {
+                    ((time - time_start) / (time_end - time_start)).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                Transformed::<T>::matrices_at_fraction(start, end, fraction)
+            }
+        }
+    }
+
+    fn matrices_at_fraction(
+        start: &[TransformStep],
+        end: &[TransformStep],
+        fraction: f64,
+    ) -> (Matrix4, Matrix4) {
+        let steps: Vec<TransformStep> = start
+            .iter()
+            .zip(end)
+            .filter_map(|(start_step, end_step)| start_step.interpolate(end_step, fraction))
+            .collect();
+        matrices_of_steps(&steps)
+    }
+}
+
+impl<T: Object> Object for Transformed<T> {
+    fn is_hit_by_ray(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitPoint<'_>> {
+        let (object_to_world, world_to_object) = self.matrices_at(ray.time());
+
+        // The direction isn't normalized, so that t is the same in both spaces.
+        let object_ray = Ray::new(
+            world_to_object.transform_point(ray.origin()),
+            world_to_object.transform_vector(ray.direction()),
+            ray.time(),
+        );
+
+        let mut hit_point = self.object.is_hit_by_ray(&object_ray, t_min, t_max)?;
+        let point = object_to_world.transform_point(hit_point.point());
+        let surface_normal = world_to_object
+            .transform_normal(hit_point.surface_normal())
+            .unit_vector();
+        hit_point.set_point_and_surface_normal(point, surface_normal);
+
+        Some(hit_point)
+    }
+
+    // The box around the transformed corners of the object's box. Not as tight as possible when
+    // rotated, but it does contain the object. A moving object gets the box around its boxes at
+    // many points in time, which (very nearly) contains it at every time.
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        let object_box = self.object.bounding_box()?;
+
+        match &self.motion {
+            Motion::Fixed {
+                object_to_world, ..
+            } => Some(transform_bounding_box(&object_box, object_to_world)),
+            Motion::Moving { end, start, .. } => (0..=MOTION_BOUNDING_BOX_SAMPLES)
+                .map(|sample| {
+                    let fraction = sample as f64 / MOTION_BOUNDING_BOX_SAMPLES as f64;
+                    let (object_to_world, _) =
+                        Transformed::<T>::matrices_at_fraction(start, end, fraction);
+                    transform_bounding_box(&object_box, &object_to_world)
+                })
+                .reduce(BoundingBox::surrounding),
+        }
+    }
+
+    fn is_light_source(&self) -> bool {
+        self.object.is_light_source()
+    }
+
+    // Aim at the object in its own space, from the origin moved there, and move the direction
+    // back into the world.
+    fn random_direction_from(&self, origin: Point3, time: f64, sampler: &mut Sampler) -> Vec3 {
+        let (object_to_world, world_to_object) = self.matrices_at(time);
+        let object_origin = world_to_object.transform_point(origin);
+        let direction = self
+            .object
+            .random_direction_from(object_origin, time, sampler);
+        object_to_world.transform_vector(direction)
+    }
+
+    // The density of the direction in object space, per solid angle there. A rotation doesn't
+    // change solid angles, but a scaling can squeeze directions together in some places and spread
+    // them out in others. Per solid angle in the world, the density is multiplied by how much a
+    // bit of solid angle around the world direction w is stretched in object space, which is
+    // |det(M)| / |M*w|^3 for the world to object matrix M (and a unit vector w).
+    fn direction_pdf(&self, ray: &Ray) -> f64 {
+        let (_, world_to_object) = self.matrices_at(ray.time());
+        let object_ray = Ray::new(
+            world_to_object.transform_point(ray.origin()),
+            world_to_object.transform_vector(ray.direction()),
+            ray.time(),
+        );
+        let pdf = self.object.direction_pdf(&object_ray);
+        if pdf == 0.0 {
+            return 0.0;
+        }
+
+        let stretched = world_to_object
+            .transform_vector(ray.direction().unit_vector())
+            .length();
+        pdf * world_to_object.linear_determinant().abs() / (stretched * stretched * stretched)
+    }
+}
+
+fn transform_bounding_box(bounding_box: &BoundingBox, matrix: &Matrix4) -> BoundingBox {
+    let (minimum, maximum) = (bounding_box.minimum(), bounding_box.maximum());
+
+    let mut corners = (0..8).map(|corner| {
+        let pick = |bit: usize, axis: usize| {
+            if corner & bit == 0 {
+                minimum[axis]
+            } else {
+                maximum[axis]
+            }
+        };
+        matrix.transform_point(Point3::new(pick(1, 0), pick(2, 1), pick(4, 2)))
+    });
+
+    let first = corners.next().unwrap();
+    let (minimum, maximum) = corners.fold((first, first), |(minimum, maximum), corner| {
+        (minimum.minimum(corner), maximum.maximum(corner))
+    });
+    BoundingBox::new(minimum, maximum)
+}
@@ -0,0 +1,224 @@
+use std::sync::Arc;
+
+use crate::sampler::Sampler;
+use crate::scene::material::material::Material;
+use crate::scene::object::bounding_box::BoundingBox;
+use crate::scene::object::object::{area_to_solid_angle_pdf, Object};
+use crate::tracer::hit_point::HitPoint;
+use crate::tracer::ray::Ray;
+use crate::tracer::tracer::T_MIN;
+use crate::vec3::{Point3, Vec3};
+
+// The vertices of a triangle mesh. Triangles refer to them by index, so a vertex shared by many
+// triangles is only stored once.
+pub struct VertexBuffers {
+    pub normals: Vec<Vec3>, // Vertex normals. Can be empty.
+    pub positions: Vec<Point3>,
+    pub texture_coordinates: Vec<[f64; 2]>, // (u, v) of the vertices. Can be empty.
+}
+
+pub struct Triangle {
+    buffers: Arc<VertexBuffers>,
+    material: Arc<dyn Material>,
+    normals: Option<[u32; 3]>, // Indices of the vertex normals. None for a flat triangle.
+    positions: [u32; 3],       // Indices of the vertex positions, counter-clockwise.
+    texture_coordinates: Option<[u32; 3]>, // Indices of the texture coordinates, if any.
+}
+
+impl Triangle {
+    pub fn new(
+        buffers: Arc<VertexBuffers>,
+        positions: [u32; 3],
+        normals: Option<[u32; 3]>,
+        texture_coordinates: Option<[u32; 3]>,
+        material: Arc<dyn Material>,
+    ) -> Triangle {
+        Triangle {
+            buffers,
+            material,
+            normals,
+            positions,
+            texture_coordinates,
+        }
+    }
+
+    // A single, flat, triangle with its own vertices.
+    pub fn from_vertices(vertices: [Point3; 3], material: Arc<dyn Material>) -> Triangle {
+        let buffers = VertexBuffers {
+            normals: Vec::new(),
+            positions: vertices.to_vec(),
+            texture_coordinates: Vec::new(),
+        };
+        Triangle::new(Arc::new(buffers), [0, 1, 2], None, None, material)
+    }
+
+    fn vertex(&self, index: usize) -> Point3 {
+        self.buffers.positions[self.positions[index] as usize]
+    }
+
+    // Perpendicular to the triangle, with a length of twice its area.
+    fn scaled_normal(&self) -> Vec3 {
+        (self.vertex(1) - self.vertex(0)).cross(self.vertex(2) - self.vertex(0))
+    }
+}
+
+impl Object for Triangle {
+    // A "watertight" ray-triangle intersection (Woop, Benthin and Wald, 2013). Simpler algorithms
+    // can let rays slip through the shared edge of two triangles, because of rounding errors. That
+    // shows up as tiny holes (speckles of background) in meshes. Here, a ray that hits an edge
+    // always hits at least one of the two triangles sharing it.
+    //
+    // The trick is to transform the triangle into a space where the ray starts at the origin and
+    // points along the Z axis. The problem is then 2D: is the origin inside the projected triangle?
+    // That's decided by the signs of three "edge functions" (U, V, W), which are computed in the
+    // exact same way for both triangles of a shared edge.
+    fn is_hit_by_ray(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitPoint<'_>> {
+        let direction = ray.direction();
+
+        // Let Z be the axis where the direction is the largest. Swap X and Y if needed to keep the
+        // winding order (which way around the vertices go) of the triangle.
+        let kz = if direction.x().abs() > direction.y().abs() {
+            if direction.x().abs() > direction.z().abs() {
+                0
+            } else {
+                2
+            }
+        } else if direction.y().abs() > direction.z().abs() {
+            1
+        } else {
+            2
+        };
+        let mut kx = (kz + 1) % 3;
+        let mut ky = (kx + 1) % 3;
+        if direction[kz] < 0.0 {
+            std::mem::swap(&mut kx, &mut ky);
+        }
+
+        // Shear constants that make the direction point along Z.
+        let shear_x = direction[kx] / direction[kz];
+        let shear_y = direction[ky] / direction[kz];
+        let shear_z = 1.0 / direction[kz];
+
+        // Vertices relative to the ray origin, sheared.
+        let a = self.vertex(0) - ray.origin();
+        let b = self.vertex(1) - ray.origin();
+        let c = self.vertex(2) - ray.origin();
+        let ax = a[kx] - (shear_x * a[kz]);
+        let ay = a[ky] - (shear_y * a[kz]);
+        let bx = b[kx] - (shear_x * b[kz]);
+        let by = b[ky] - (shear_y * b[kz]);
+        let cx = c[kx] - (shear_x * c[kz]);
+        let cy = c[ky] - (shear_y * c[kz]);
+
+        // Edge functions. The ray hits the triangle when they all have the same sign.
+        let u = (cx * by) - (cy * bx);
+        let v = (ax * cy) - (ay * cx);
+        let w = (bx * ay) - (by * ax);
+        if (u < 0.0 || v < 0.0 || w < 0.0) && (u > 0.0 || v > 0.0 || w > 0.0) {
+            return None;
+        }
+
+        // The ray is parallel to the triangle (or the triangle has no area).
+        let determinant = u + v + w;
+        if determinant == 0.0 {
+            return None;
+        }
+
+        // Distance along the ray, scaled by the determinant.
+        let scaled_t = (u * shear_z * a[kz]) + (v * shear_z * b[kz]) + (w * shear_z * c[kz]);
+        let t = scaled_t / determinant;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+
+        // Barycentric coordinates. How much each vertex "weighs" at the hit point.
+        let barycentric = [u / determinant, v / determinant, w / determinant];
+        let point = ray.point_at(t);
+
+        let geometric_normal = self.scaled_normal().unit_vector();
+
+        // Smooth shading. Blend the vertex normals, so that a mesh of flat triangles looks like a
+        // curved surface. Keep it on the same side as the actual (geometric) normal.
+        let surface_normal = match self.normals {
+            None => geometric_normal,
+            Some(normals) => {
+                let normal = (self.buffers.normals[normals[0] as usize] * barycentric[0])
+                    + (self.buffers.normals[normals[1] as usize] * barycentric[1])
+                    + (self.buffers.normals[normals[2] as usize] * barycentric[2]);
+                let normal = normal.unit_vector();
+                if normal.dot(geometric_normal) < 0.0 {
+                    -normal
+                } else {
+                    normal
+                }
+            }
+        };
+
+        // Blend the texture coordinates of the vertices the same way. Without them, use the
+        // barycentric coordinates, so that the corners are (0, 0), (1, 0) and (0, 1).
+        let texture_coordinates = match self.texture_coordinates {
+            None => (barycentric[1], barycentric[2]),
+            Some(indices) => {
+                let [u0, v0] = self.buffers.texture_coordinates[indices[0] as usize];
+                let [u1, v1] = self.buffers.texture_coordinates[indices[1] as usize];
+                let [u2, v2] = self.buffers.texture_coordinates[indices[2] as usize];
+                (
+                    (u0 * barycentric[0]) + (u1 * barycentric[1]) + (u2 * barycentric[2]),
+                    (v0 * barycentric[0]) + (v1 * barycentric[1]) + (v2 * barycentric[2]),
+                )
+            }
+        };
+
+        Some(HitPoint::new(
+            point,
+            t,
+            surface_normal,
+            texture_coordinates,
+            ray,
+            &*self.material,
+        ))
+    }
+
+    // Padded a little, so that a triangle lying in an axis plane doesn't get a flat box.
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        const PADDING: f64 = 0.0001;
+        let padding = Vec3::new(PADDING, PADDING, PADDING);
+
+        let minimum = self
+            .vertex(0)
+            .minimum(self.vertex(1))
+            .minimum(self.vertex(2));
+        let maximum = self
+            .vertex(0)
+            .maximum(self.vertex(1))
+            .maximum(self.vertex(2));
+        Some(BoundingBox::new(minimum - padding, maximum + padding))
+    }
+
+    fn is_light_source(&self) -> bool {
+        self.material.emits_light()
+    }
+
+    // Towards a random point on the triangle, picked uniformly over its area. Picking barycentric
+    // coordinates directly would bunch the points up at one corner. The square root spreads them
+    // out (Shirley and Chiu, 1997).
+    fn random_direction_from(&self, origin: Point3, _time: f64, sampler: &mut Sampler) -> Vec3 {
+        let square_root = sampler.next_f64().sqrt();
+        let b0 = 1.0 - square_root;
+        let b1 = sampler.next_f64() * square_root;
+        let point =
+            (self.vertex(0) * b0) + (self.vertex(1) * b1) + (self.vertex(2) * (1.0 - b0 - b1));
+        point - origin
+    }
+
+    fn direction_pdf(&self, ray: &Ray) -> f64 {
+        match self.is_hit_by_ray(ray, T_MIN, f64::INFINITY) {
+            Some(hit_point) => {
+                let scaled_normal = self.scaled_normal();
+                let area = scaled_normal.length() / 2.0;
+                area_to_solid_angle_pdf(ray, hit_point.t(), scaled_normal.unit_vector(), area)
+            }
+            None => 0.0,
+        }
+    }
+}
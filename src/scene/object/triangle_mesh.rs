@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use crate::scene::material::material::Material;
+use crate::scene::object::bounding_box::BoundingBox;
+use crate::scene::object::bounding_volume_hierarchy::BoundingVolumeHierarchy;
+use crate::scene::object::object::Object;
+use crate::scene::object::triangle::{Triangle, VertexBuffers};
+use crate::tracer::hit_point::HitPoint;
+use crate::tracer::ray::Ray;
+
+// A triangle of a mesh, described by indices into the vertex buffers of the mesh.
+pub struct Face {
+    pub material: Arc<dyn Material>,
+    pub normals: Option<[u32; 3]>,
+    pub positions: [u32; 3],
+    pub texture_coordinates: Option<[u32; 3]>,
+}
+
+// A surface made out of many triangles sharing vertices, like a model loaded from an OBJ file.
+// The triangles get their own bounding volume hierarchy, so the mesh is a single object in the
+// world no matter how many triangles it has.
+pub struct TriangleMesh {
+    triangles: BoundingVolumeHierarchy<Triangle>,
+}
+
+impl TriangleMesh {
+    pub fn new(buffers: VertexBuffers, faces: Vec<Face>) -> TriangleMesh {
+        let buffers = Arc::new(buffers);
+        let triangles = faces
+            .into_iter()
+            .map(|face| {
+                Triangle::new(
+                    buffers.clone(),
+                    face.positions,
+                    face.normals,
+                    face.texture_coordinates,
+                    face.material,
+                )
+            })
+            .collect();
+
+        TriangleMesh {
+            triangles: BoundingVolumeHierarchy::new(triangles),
+        }
+    }
+}
+
+impl Object for TriangleMesh {
+    fn is_hit_by_ray(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitPoint<'_>> {
+        self.triangles.is_hit_by_ray(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        self.triangles.bounding_box()
+    }
+}
@@ -0,0 +1,38 @@
+use std::sync::Arc;
+
+use crate::scene::material::medium_boundary::MediumBoundary;
+use crate::scene::medium::medium::Medium;
+use crate::scene::object::bounding_box::BoundingBox;
+use crate::scene::object::object::Object;
+use crate::tracer::hit_point::HitPoint;
+use crate::tracer::ray::Ray;
+
+// A medium (smoke, a cloud, see Medium) in the shape of another object, the "boundary". Any
+// closed object will do: a sphere of fog, a box of smoke, a mesh of a cloud. Its material doesn't
+// matter, the surface of the volume lets light straight through. What happens inside is up to the
+// tracer, that keeps track of the medium a ray is in.
+pub struct Volume<T: Object> {
+    boundary: T,
+    material: MediumBoundary,
+}
+
+impl<T: Object> Volume<T> {
+    pub fn new(boundary: T, medium: Arc<dyn Medium>) -> Volume<T> {
+        Volume {
+            boundary,
+            material: MediumBoundary::new(medium),
+        }
+    }
+}
+
+impl<T: Object> Object for Volume<T> {
+    fn is_hit_by_ray(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitPoint<'_>> {
+        let mut hit_point = self.boundary.is_hit_by_ray(ray, t_min, t_max)?;
+        hit_point.set_material(&self.material);
+        Some(hit_point)
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        self.boundary.bounding_box()
+    }
+}
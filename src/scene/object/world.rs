@@ -1,27 +1,88 @@
+use std::sync::Arc;
 use std::vec::Vec;
 
+use crate::sampler::Sampler;
+use crate::scene::object::bounding_box::BoundingBox;
+use crate::scene::object::bounding_volume_hierarchy::BoundingVolumeHierarchy;
 use crate::scene::object::object::Object;
 use crate::tracer::hit_point::HitPoint;
 use crate::tracer::ray::Ray;
+use crate::vec3::{Point3, Vec3};
 
+// Objects with a bounding box are put in a bounding volume hierarchy. Infinite objects can't be
+// put in a box, so they are tested one by one (there are usually only a few of them).
+//
+// The light sources are also kept in a list of their own, for the tracer to aim rays at. That's
+// why the objects are shared (Arc), they are in both places.
 pub struct World<T: Object> {
-    objects: Vec<T>,
+    bounded_objects: BoundingVolumeHierarchy<Arc<T>>,
+    light_sources: Vec<Arc<T>>,
+    unbounded_objects: Vec<Arc<T>>,
 }
 
 impl<T: Object> World<T> {
     pub fn new(objects: Vec<T>) -> World<T> {
-        World { objects }
+        let objects: Vec<Arc<T>> = objects.into_iter().map(Arc::new).collect();
+        let light_sources = objects
+            .iter()
+            .filter(|object| object.is_light_source())
+            .cloned()
+            .collect();
+
+        // RUST: partition() splits an iterator in two collections using a predicate.
+        let (bounded_objects, unbounded_objects): (Vec<Arc<T>>, Vec<Arc<T>>) = objects
+            .into_iter()
+            .partition(|object| object.bounding_box().is_some());
+
+        World {
+            bounded_objects: BoundingVolumeHierarchy::new(bounded_objects),
+            light_sources,
+            unbounded_objects,
+        }
+    }
+
+    pub fn has_light_sources(&self) -> bool {
+        !self.light_sources.is_empty()
+    }
+
+    // A random direction from the origin towards one of the light sources, picked at random. There
+    // must be at least one.
+    pub fn random_direction_towards_light(
+        &self,
+        origin: Point3,
+        time: f64,
+        sampler: &mut Sampler,
+    ) -> Vec3 {
+        let light_source = sampler.index(self.light_sources.len());
+        self.light_sources[light_source].random_direction_from(origin, time, sampler)
+    }
+
+    // The probability density of random_direction_towards_light() picking the direction of the
+    // ray. Every light source is picked as often, so it's the average of their densities.
+    pub fn light_pdf(&self, ray: &Ray) -> f64 {
+        if self.light_sources.is_empty() {
+            return 0.0;
+        }
+        let sum: f64 = self
+            .light_sources
+            .iter()
+            .map(|light_source| light_source.direction_pdf(ray))
+            .sum();
+        sum / self.light_sources.len() as f64
     }
 }
 
 impl<T: Object> Object for World<T> {
-    fn is_hit_by_ray(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitPoint> {
+    fn is_hit_by_ray(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitPoint<'_>> {
         let mut closest_object_so_far = t_max;
-        let mut object_hit_by_ray: Option<HitPoint> = None;
+        let mut object_hit_by_ray = self.bounded_objects.is_hit_by_ray(ray, t_min, t_max);
+        if let Some(hit_point) = &object_hit_by_ray {
+            closest_object_so_far = hit_point.t();
+        }
 
-        for object in &self.objects {
+        for object in &self.unbounded_objects {
             // RUST: Match single pattern: Some.
-            if let Some(hit_point) = object.is_hit_by_ray(&ray, t_min, closest_object_so_far) {
+            if let Some(hit_point) = object.is_hit_by_ray(ray, t_min, closest_object_so_far) {
                 closest_object_so_far = hit_point.t();
                 object_hit_by_ray = Some(hit_point);
             }
@@ -29,4 +90,12 @@ impl<T: Object> Object for World<T> {
 
         object_hit_by_ray
     }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        if self.unbounded_objects.is_empty() {
+            self.bounded_objects.bounding_box()
+        } else {
+            None
+        }
+    }
 }
@@ -0,0 +1,294 @@
+use std::f64::consts::PI;
+
+use crate::sampler::Sampler;
+use crate::vec3::{Color, Vec3};
+
+// A daylight sky, computed from a model of how sunlight is scattered by the air (Preetham, Shirley
+// and Smits, "A Practical Analytic Model for Daylight", 1999). It gives the blue sky, brighter and
+// whiter towards the horizon and around the sun, for any position of the sun. Plus the sun itself,
+// a small and very bright disk, and the ground below the horizon.
+//
+// The model is parameterised by the "turbidity": how hazy the air is. 2 is a very clear day, 3 a
+// clear one, and 6 or more is hazy, with a whiter sky and a redder, weaker, sun.
+//
+// The sun is far brighter than the rest of the sky, so the tracer aims shadow rays at it, like at
+// a light source.
+pub struct Sky {
+    cos_sun_radius: f64, // Cosine of the angular radius of the sun disk.
+    ground: Color,       // Light reflected by the ground, the same in every direction.
+    luminance: Perez,    // The sky color, as CIE xyY: luminance Y, and chromaticities x and y.
+    sun: Color,          // Light from the sun disk.
+    sun_direction: Vec3, // Unit vector, towards the sun.
+    x: Perez,
+    y: Perez,
+}
+
+// The sun is about half a degree across.
+const SUN_ANGULAR_RADIUS: f64 = 0.00465; // Radians.
+
+// The model gives luminance in thousands of candelas per square meter. A sunny day is far too
+// bright for an image without exposure control (anything above 1.0 is white). This scales it down,
+// so that a white surface in the midday sun is about white.
+const SCALE: f64 = 0.025;
+
+// Luminance of the sun outside the atmosphere, in the same units as the model.
+const SUN_LUMINANCE: f64 = 2.0e6;
+
+impl Sky {
+    // The sun direction doesn't have to be a unit vector. The turbidity is 1.7 to 10, and the
+    // ground albedo is the color of the ground.
+    pub fn new(sun_direction: Vec3, turbidity: f64, ground_albedo: Color) -> Sky {
+        let sun_direction = sun_direction.unit_vector();
+
+        // The model isn't made for a sun below the horizon (dusk and night). Keep it just above.
+        let theta_sun = sun_direction
+            .y()
+            .clamp(0.0, 1.0)
+            .acos()
+            .min((PI / 2.0) - 0.01);
+        let t = turbidity;
+
+        // Sky color straight up (at the zenith), then everywhere else relative to it.
+        let chi = ((4.0 / 9.0) - (t / 120.0)) * (PI - (2.0 * theta_sun));
+        let zenith_luminance = (((4.0453 * t) - 4.9710) * chi.tan()) - (0.2155 * t) + 2.4192;
+        let zenith_x = zenith_chromaticity(
+            t,
+            theta_sun,
+            [
+                [0.00166, -0.00375, 0.00209, 0.0],
+                [-0.02903, 0.06377, -0.03202, 0.00394],
+                [0.11693, -0.21196, 0.06052, 0.25886],
+            ],
+        );
+        let zenith_y = zenith_chromaticity(
+            t,
+            theta_sun,
+            [
+                [0.00275, -0.00610, 0.00317, 0.0],
+                [-0.04214, 0.08970, -0.04153, 0.00516],
+                [0.15346, -0.26756, 0.06670, 0.26688],
+            ],
+        );
+
+        let mut sky = Sky {
+            cos_sun_radius: SUN_ANGULAR_RADIUS.cos(),
+            ground: Color::zero(),
+            luminance: Perez::new(
+                zenith_luminance,
+                theta_sun,
+                [
+                    (0.1787 * t) - 1.4630,
+                    (-0.3554 * t) + 0.4275,
+                    (-0.0227 * t) + 5.3251,
+                    (0.1206 * t) - 2.5771,
+                    (-0.0670 * t) + 0.3703,
+                ],
+            ),
+            sun: sun_color(t, theta_sun),
+            sun_direction,
+            x: Perez::new(
+                zenith_x,
+                theta_sun,
+                [
+                    (-0.0193 * t) - 0.2592,
+                    (-0.0665 * t) + 0.0008,
+                    (-0.0004 * t) + 0.2125,
+                    (-0.0641 * t) - 0.8989,
+                    (-0.0033 * t) + 0.0452,
+                ],
+            ),
+            y: Perez::new(
+                zenith_y,
+                theta_sun,
+                [
+                    (-0.0167 * t) - 0.2608,
+                    (-0.0950 * t) + 0.0092,
+                    (-0.0079 * t) + 0.2102,
+                    (-0.0441 * t) - 1.6537,
+                    (-0.0109 * t) + 0.0529,
+                ],
+            ),
+        };
+
+        // The ground is lit by the whole sky and the sun, and reflects it diffusely (see Diffuse).
+        sky.ground = ground_albedo * (sky.irradiance() / PI);
+        sky
+    }
+
+    // The light coming from the direction.
+    pub fn color(&self, direction: Vec3) -> Color {
+        let direction = direction.unit_vector();
+        if direction.y() < 0.0 {
+            return self.ground;
+        }
+
+        let sky = self.sky_color(direction);
+        if self.is_sun_up() && direction.dot(self.sun_direction) >= self.cos_sun_radius {
+            sky + self.sun
+        } else {
+            sky
+        }
+    }
+
+    // Only the sun is worth aiming at. The rest of the sky is too even for it to help.
+    pub fn is_sun_up(&self) -> bool {
+        self.sun_direction.y() > 0.0
+    }
+
+    // A random direction towards the sun disk, uniformly within the cone it covers (like towards a
+    // Sphere).
+    pub fn random_direction(&self, sampler: &mut Sampler) -> Vec3 {
+        let cos_theta = 1.0 + (sampler.next_f64() * (self.cos_sun_radius - 1.0));
+        let sin_theta = (1.0 - (cos_theta * cos_theta)).max(0.0).sqrt();
+        let phi = 2.0 * PI * sampler.next_f64();
+
+        let (tangent, bitangent) = self.sun_direction.orthonormal_basis();
+        (tangent * (phi.cos() * sin_theta))
+            + (bitangent * (phi.sin() * sin_theta))
+            + (self.sun_direction * cos_theta)
+    }
+
+    // One over the solid angle of the sun disk, or zero outside of it.
+    pub fn direction_pdf(&self, direction: Vec3) -> f64 {
+        if !self.is_sun_up()
+            || direction.unit_vector().dot(self.sun_direction) < self.cos_sun_radius
+        {
+            return 0.0;
+        }
+        1.0 / (2.0 * PI * (1.0 - self.cos_sun_radius))
+    }
+
+    // The sky without the sun, converted from CIE xyY to linear (s)RGB.
+    fn sky_color(&self, direction: Vec3) -> Color {
+        let cos_theta = direction.y().max(1e-3);
+        let gamma = direction.dot(self.sun_direction).clamp(-1.0, 1.0).acos();
+
+        let x = self.x.value(cos_theta, gamma);
+        let y = self.y.value(cos_theta, gamma);
+        let luminance = self.luminance.value(cos_theta, gamma);
+        xyy_to_rgb(x, y, luminance) * SCALE
+    }
+
+    // Light arriving on the ground (a horizontal surface) from the sky and the sun, summed up over
+    // the hemisphere with a grid of directions. Every direction counts in proportion to the cosine
+    // of its angle with the normal (straight up).
+    fn irradiance(&self) -> Color {
+        const STEPS_THETA: usize = 32;
+        const STEPS_PHI: usize = 64;
+        let d_theta = (PI / 2.0) / STEPS_THETA as f64;
+        let d_phi = (2.0 * PI) / STEPS_PHI as f64;
+
+        let mut irradiance = Color::zero();
+        for i in 0..STEPS_THETA {
+            let theta = (i as f64 + 0.5) * d_theta;
+            for j in 0..STEPS_PHI {
+                let phi = (j as f64 + 0.5) * d_phi;
+                let direction = Vec3::new(
+                    theta.sin() * phi.cos(),
+                    theta.cos(),
+                    theta.sin() * phi.sin(),
+                );
+                // The solid angle of a grid cell is sin(theta) * d_theta * d_phi.
+                let weight = theta.cos() * theta.sin() * d_theta * d_phi;
+                irradiance = irradiance + (self.sky_color(direction) * weight);
+            }
+        }
+
+        if self.is_sun_up() {
+            let solid_angle = 2.0 * PI * (1.0 - self.cos_sun_radius);
+            irradiance = irradiance + (self.sun * (solid_angle * self.sun_direction.y()));
+        }
+        irradiance
+    }
+}
+
+// The Perez formula, for how a quantity (luminance, or a chromaticity) varies over the sky,
+// relative to its value at the zenith. theta is the angle of a direction with the zenith, and gamma
+// its angle with the sun:
+// F(theta, gamma) = (1 + A*e^(B/cos(theta))) * (1 + C*e^(D*gamma) + E*cos^2(gamma))
+struct Perez {
+    coefficients: [f64; 5], // A, B, C, D and E.
+    scale: f64,             // The value at the zenith, divided by F at the zenith.
+}
+
+impl Perez {
+    fn new(zenith_value: f64, theta_sun: f64, coefficients: [f64; 5]) -> Perez {
+        let mut perez = Perez {
+            coefficients,
+            scale: 1.0,
+        };
+        perez.scale = zenith_value / perez.formula(1.0, theta_sun);
+        perez
+    }
+
+    fn value(&self, cos_theta: f64, gamma: f64) -> f64 {
+        self.scale * self.formula(cos_theta, gamma)
+    }
+
+    fn formula(&self, cos_theta: f64, gamma: f64) -> f64 {
+        let [a, b, c, d, e] = self.coefficients;
+        let cos_gamma = gamma.cos();
+        (1.0 + (a * (b / cos_theta).exp()))
+            * (1.0 + (c * (d * gamma).exp()) + (e * cos_gamma * cos_gamma))
+    }
+}
+
+// A chromaticity at the zenith, as polynomials in the turbidity and the angle of the sun (rows for
+// T^2, T and 1; columns for theta^3, theta^2, theta and 1).
+fn zenith_chromaticity(turbidity: f64, theta_sun: f64, coefficients: [[f64; 4]; 3]) -> f64 {
+    let thetas = [theta_sun.powi(3), theta_sun.powi(2), theta_sun, 1.0];
+    let turbidities = [turbidity * turbidity, turbidity, 1.0];
+    turbidities
+        .iter()
+        .zip(coefficients.iter())
+        .map(|(turbidity, row)| {
+            turbidity
+                * row
+                    .iter()
+                    .zip(thetas)
+                    .map(|(c, theta)| c * theta)
+                    .sum::<f64>()
+        })
+        .sum()
+}
+
+// CIE xyY (chromaticity and luminance) to CIE XYZ, and then to linear sRGB. Colors outside of what
+// sRGB can show get negative components, those are cut off.
+fn xyy_to_rgb(x: f64, y: f64, luminance: f64) -> Color {
+    if y <= 0.0 {
+        return Color::zero();
+    }
+    let big_x = (x / y) * luminance;
+    let big_y = luminance;
+    let big_z = ((1.0 - x - y) / y) * luminance;
+
+    Color::new(
+        ((3.2406 * big_x) - (1.5372 * big_y) - (0.4986 * big_z)).max(0.0),
+        ((-0.9689 * big_x) + (1.8758 * big_y) + (0.0415 * big_z)).max(0.0),
+        ((0.0557 * big_x) - (0.2040 * big_y) + (1.0570 * big_z)).max(0.0),
+    )
+}
+
+// Sunlight after passing through the atmosphere. Air scatters blue light away much more than red
+// (Rayleigh scattering, which is why the sky is blue), and haze scatters all colors about the
+// same. The lower the sun, the more air the light passes through ("air mass"), and the redder the
+// sun gets. Worked out for one wavelength per color: 680 (red), 550 (green) and 440 (blue) nm.
+fn sun_color(turbidity: f64, theta_sun: f64) -> Color {
+    // Air mass relative to straight up, with the curvature of the earth (Kasten and Young).
+    let degrees = theta_sun.to_degrees();
+    let air_mass = 1.0 / (theta_sun.cos() + (0.15 * (93.885 - degrees).powf(-1.253)));
+
+    // Optical depth: Rayleigh, and haze with the Angstrom formula (alpha = 1.3).
+    let beta = (0.04608 * turbidity) - 0.04586;
+    let transmittance = |wavelength: f64| {
+        let rayleigh = 0.008735 * wavelength.powf(-4.08);
+        let haze = beta * wavelength.powf(-1.3);
+        (-(rayleigh + haze) * air_mass).exp()
+    };
+
+    Color::new(
+        transmittance(0.68),
+        transmittance(0.55),
+        transmittance(0.44),
+    ) * (SUN_LUMINANCE * SCALE)
+}
@@ -0,0 +1,32 @@
+use std::sync::Arc;
+
+use crate::scene::texture::texture::Texture;
+use crate::vec3::{Color, Point3};
+
+// A 3D checkerboard. Space is divided into cubes of the given size, alternating between two
+// textures. Being 3D, it doesn't need texture coordinates, and it lines up across objects.
+pub struct Checker {
+    even: Arc<dyn Texture>,
+    odd: Arc<dyn Texture>,
+    size: f64, // Length of the side of a cube.
+}
+
+impl Checker {
+    pub fn new(even: Arc<dyn Texture>, odd: Arc<dyn Texture>, size: f64) -> Checker {
+        Checker { even, odd, size }
+    }
+}
+
+impl Texture for Checker {
+    fn value(&self, u: f64, v: f64, point: Point3) -> Color {
+        let x = (point.x() / self.size).floor() as i64;
+        let y = (point.y() / self.size).floor() as i64;
+        let z = (point.z() / self.size).floor() as i64;
+
+        if (x + y + z) % 2 == 0 {
+            self.even.value(u, v, point)
+        } else {
+            self.odd.value(u, v, point)
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use crate::image::framebuffer::Framebuffer;
+use crate::scene::texture::texture::Texture;
+use crate::vec3::{Color, Point3};
+
+// An image wrapped onto a surface using its texture coordinates. (u, v) = (0, 0) is the bottom
+// left corner of the image and (1, 1) the top right. The nearest pixel is used, no filtering.
+pub struct ImageTexture {
+    height: u16,
+    pixels: Vec<Color>, // Linear colors, rows top to bottom.
+    width: u16,
+}
+
+impl ImageTexture {
+    // The framebuffer of an image that was read (see read_image()).
+    pub fn new(image: &Framebuffer) -> ImageTexture {
+        ImageTexture {
+            height: image.height(),
+            pixels: image.pixels(),
+            width: image.width(),
+        }
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _point: Point3) -> Color {
+        // Coordinates outside 0.0 to 1.0 repeat (wrap around) the image.
+        let u = u - u.floor();
+        let v = 1.0 - (v - v.floor()); // Flip, since the rows go top to bottom.
+
+        let x = ((u * self.width as f64) as usize).min(self.width as usize - 1);
+        let y = ((v * self.height as f64) as usize).min(self.height as usize - 1);
+        self.pixels[(y * self.width as usize) + x]
+    }
+}
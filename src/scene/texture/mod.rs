@@ -0,0 +1,7 @@
+pub mod checker;
+pub mod image_texture;
+pub mod noise;
+pub mod perlin;
+pub mod solid_color;
+#[allow(clippy::module_inception)]
+pub mod texture;
@@ -0,0 +1,51 @@
+use crate::sampler::Sampler;
+use crate::scene::texture::perlin::Perlin;
+use crate::scene::texture::texture::Texture;
+use crate::vec3::{Color, Point3};
+
+const TURBULENCE_OCTAVES: u32 = 7;
+
+// Different looks made out of Perlin noise.
+#[derive(Clone, Copy)]
+pub enum NoiseStyle {
+    Marble,     // Stripes, disturbed by turbulence, like veins in marble.
+    Noise,      // Plain noise. Soft blobs.
+    Turbulence, // Noise with finer and finer detail on top. Like a net or clouds.
+}
+
+// A solid texture made out of Perlin noise, which scales a color between black and the color.
+pub struct NoiseTexture {
+    color: Color,
+    perlin: Perlin,
+    scale: f64, // Frequency. Bigger is more, smaller, features.
+    style: NoiseStyle,
+}
+
+impl NoiseTexture {
+    // The sampler gives the random numbers of the noise. The same seed gives the same noise.
+    pub fn new(style: NoiseStyle, scale: f64, color: Color, sampler: &mut Sampler) -> NoiseTexture {
+        NoiseTexture {
+            color,
+            perlin: Perlin::new(sampler),
+            scale,
+            style,
+        }
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: f64, _v: f64, point: Point3) -> Color {
+        let scaled_point = point * self.scale;
+        let intensity = match self.style {
+            NoiseStyle::Marble => {
+                let phase =
+                    scaled_point.z() + (10.0 * self.perlin.turbulence(point, TURBULENCE_OCTAVES));
+                0.5 * (1.0 + phase.sin())
+            }
+            NoiseStyle::Noise => 0.5 * (1.0 + self.perlin.noise(scaled_point)),
+            NoiseStyle::Turbulence => self.perlin.turbulence(scaled_point, TURBULENCE_OCTAVES),
+        };
+
+        self.color * intensity.clamp(0.0, 1.0)
+    }
+}
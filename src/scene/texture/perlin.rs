@@ -0,0 +1,97 @@
+use crate::sampler::Sampler;
+use crate::vec3::{Point3, Vec3};
+
+const POINT_COUNT: usize = 256;
+
+// Perlin noise is smooth random noise. The same point always gives the same value, and points
+// close together give similar values. It's made by putting a random unit vector (gradient) on every
+// corner of a grid of unit cubes. The value at a point is a blend of the dot products of the
+// gradients of the eight surrounding corners with the directions from those corners to the point.
+//
+// Instead of storing a gradient for every corner of an infinite grid, the corners are "hashed" to
+// one of 256 gradients with random permutations of the numbers 0 to 255, one per axis.
+pub struct Perlin {
+    gradients: Vec<Vec3>,
+    permutation_x: Vec<usize>,
+    permutation_y: Vec<usize>,
+    permutation_z: Vec<usize>,
+}
+
+impl Perlin {
+    pub fn new(sampler: &mut Sampler) -> Perlin {
+        let gradients = (0..POINT_COUNT)
+            .map(|_| Vec3::random_within_range(-1.0, 1.0, sampler).unit_vector())
+            .collect();
+
+        Perlin {
+            gradients,
+            permutation_x: Perlin::permutation(sampler),
+            permutation_y: Perlin::permutation(sampler),
+            permutation_z: Perlin::permutation(sampler),
+        }
+    }
+
+    // Roughly -1.0 to 1.0.
+    pub fn noise(&self, point: Point3) -> f64 {
+        let floor = [point.x().floor(), point.y().floor(), point.z().floor()];
+        let fraction = [
+            point.x() - floor[0],
+            point.y() - floor[1],
+            point.z() - floor[2],
+        ];
+
+        // Hermite smoothing. Without it, the grid shows up as visible lines ("Mach bands").
+        let smoothed = fraction.map(|f| f * f * (3.0 - (2.0 * f)));
+
+        let (i, j, k) = (floor[0] as i64, floor[1] as i64, floor[2] as i64);
+        let mut accumulated = 0.0;
+        for di in 0..2 {
+            for dj in 0..2 {
+                for dk in 0..2 {
+                    let gradient = self.gradients[self.permutation_x[((i + di) & 255) as usize]
+                        ^ self.permutation_y[((j + dj) & 255) as usize]
+                        ^ self.permutation_z[((k + dk) & 255) as usize]];
+                    let to_point = Vec3::new(
+                        fraction[0] - di as f64,
+                        fraction[1] - dj as f64,
+                        fraction[2] - dk as f64,
+                    );
+
+                    // Trilinear interpolation. Weigh every corner by how close the point is to it.
+                    let weight = |d: i64, s: f64| if d == 1 { s } else { 1.0 - s };
+                    accumulated += weight(di, smoothed[0])
+                        * weight(dj, smoothed[1])
+                        * weight(dk, smoothed[2])
+                        * gradient.dot(to_point);
+                }
+            }
+        }
+
+        accumulated
+    }
+
+    // Turbulence is the sum of several "octaves" of noise. Every octave has twice the frequency
+    // and half the amplitude of the one before, adding finer and finer detail. 0.0 to about 1.0.
+    pub fn turbulence(&self, point: Point3, octaves: u32) -> f64 {
+        let mut accumulated = 0.0;
+        let mut point = point;
+        let mut weight = 1.0;
+        for _ in 0..octaves {
+            accumulated += weight * self.noise(point);
+            weight *= 0.5;
+            point = point * 2.0;
+        }
+
+        f64::abs(accumulated)
+    }
+
+    // A random order of the numbers 0 to 255 (Fisher-Yates shuffle).
+    fn permutation(sampler: &mut Sampler) -> Vec<usize> {
+        let mut permutation: Vec<usize> = (0..POINT_COUNT).collect();
+        for i in (1..POINT_COUNT).rev() {
+            let target = sampler.index(i + 1);
+            permutation.swap(i, target);
+        }
+        permutation
+    }
+}
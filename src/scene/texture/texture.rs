@@ -0,0 +1,9 @@
+use crate::vec3::{Color, Point3};
+
+// A texture is a color that varies over a surface. It's looked up with the surface (texture)
+// coordinates (u, v) of the hit point, which both go from 0.0 to 1.0 over the surface, and the
+// hit point itself. Image textures use the former, "solid" textures (like marble, which is the
+// same all the way through the object) use the latter.
+pub trait Texture: Send + Sync {
+    fn value(&self, u: f64, v: f64, point: Point3) -> Color;
+}
@@ -0,0 +1,315 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::scene::json::ParseError;
+use crate::scene::loader::SceneError;
+use crate::scene::material::dielectric::Dielectric;
+use crate::scene::material::diffuse::Diffuse;
+use crate::scene::material::material::Material;
+use crate::scene::material::reflective::Reflective;
+use crate::scene::object::triangle::VertexBuffers;
+use crate::scene::object::triangle_mesh::{Face, TriangleMesh};
+use crate::vec3::{Color, Point3, Vec3};
+
+// Wavefront OBJ is a simple text format for 3D models, that almost every modelling program can
+// export. Every line starts with a keyword:
+// - v x y z: A vertex position.
+// - vn x y z: A vertex normal.
+// - vt u v: A vertex texture coordinate.
+// - f v1/vt1/vn1 v2/vt2/vn2 v3/vt3/vn3 ...: A face (polygon), as indices (starting at 1) into the
+//   positions, texture coordinates and normals. Texture coordinates and normals are optional.
+//   Negative indices count backwards from the most recent vertex.
+// - mtllib file.mtl: Load materials from an MTL file.
+// - usemtl name: Use the named material for the following faces.
+// Everything else (groups, smoothing groups, etc.) is ignored.
+//
+// Faces without a material (from usemtl) get the default material.
+pub fn load_obj(
+    path: &Path,
+    default_material: Arc<dyn Material>,
+) -> Result<TriangleMesh, SceneError> {
+    let text = fs::read_to_string(path).map_err(SceneError::Io)?;
+    let directory = path.parent().unwrap_or(Path::new(""));
+
+    let mut buffers = VertexBuffers {
+        normals: Vec::new(),
+        positions: Vec::new(),
+        texture_coordinates: Vec::new(),
+    };
+    let mut faces = Vec::new();
+    let mut materials: HashMap<String, Arc<dyn Material>> = HashMap::new();
+    let mut material = default_material.clone();
+
+    for (line_index, line) in text.lines().enumerate() {
+        let line_number = line_index + 1;
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let error = |token: &str, message: String| {
+            SceneError::Parse(ParseError::new(
+                line_number,
+                column_of(line, token),
+                message,
+            ))
+        };
+
+        match keyword {
+            "v" => buffers
+                .positions
+                .push(parse_vector(line, line_number, &mut tokens)?),
+            "vn" => buffers
+                .normals
+                .push(parse_vector(line, line_number, &mut tokens)?.unit_vector()),
+            "vt" => {
+                let u = parse_number(line, line_number, &mut tokens)?;
+                let v = parse_number(line, line_number, &mut tokens)?;
+                buffers.texture_coordinates.push([u, v]);
+            }
+            "f" => {
+                let mut vertices = Vec::new();
+                for token in tokens {
+                    vertices.push(
+                        parse_face_vertex(token, &buffers)
+                            .map_err(|message| error(token, message))?,
+                    );
+                }
+                if vertices.len() < 3 {
+                    return Err(error(
+                        keyword,
+                        "a face needs at least three vertices".to_string(),
+                    ));
+                }
+
+                // Split the polygon into a "fan" of triangles, that all share the first vertex.
+                for i in 1..(vertices.len() - 1) {
+                    let corners = [vertices[0], vertices[i], vertices[i + 1]];
+                    faces.push(Face {
+                        material: material.clone(),
+                        normals: all_or_none(corners.map(|corner| corner.normal)),
+                        positions: corners.map(|corner| corner.position),
+                        texture_coordinates: all_or_none(
+                            corners.map(|corner| corner.texture_coordinate),
+                        ),
+                    });
+                }
+            }
+            "mtllib" => {
+                let Some(file) = tokens.next() else {
+                    return Err(error(keyword, "missing file name".to_string()));
+                };
+                let loaded = load_mtl(&directory.join(file)).map_err(|load_error| {
+                    error(file, format!("couldn't load {}: {}", file, load_error))
+                })?;
+                materials.extend(loaded);
+            }
+            "usemtl" => {
+                let Some(name) = tokens.next() else {
+                    return Err(error(keyword, "missing material name".to_string()));
+                };
+                material = match materials.get(name) {
+                    Some(named_material) => named_material.clone(),
+                    None => return Err(error(name, format!("unknown material \"{}\"", name))),
+                };
+            }
+            _ => {}
+        }
+    }
+
+    Ok(TriangleMesh::new(buffers, faces))
+}
+
+// MTL files describe the materials of an OBJ file. A material starts with "newmtl name" and is
+// followed by its properties. Only some of them can be represented by our materials:
+// - Kd r g b: Diffuse color.
+// - Ks r g b: Specular (mirror) color.
+// - Ns exponent: Specular exponent (shininess), 0 to 1000.
+// - Ni index: Refractive index.
+// - d alpha / Tr transparency: How opaque / transparent the material is.
+// - illum model: Illumination model. 3 and 5 are mirrors, 4, 6, 7 and 9 are glass.
+//
+// Glass (transparent or a glass illumination model) becomes Dielectric. Mirrors (a mirror
+// illumination model, or a specular color without a diffuse one) become Reflective. Everything
+// else becomes Diffuse.
+fn load_mtl(path: &Path) -> Result<HashMap<String, Arc<dyn Material>>, SceneError> {
+    let text = fs::read_to_string(path).map_err(SceneError::Io)?;
+
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, MtlProperties)> = None;
+
+    for (line_index, line) in text.lines().enumerate() {
+        let line_number = line_index + 1;
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+
+        if keyword == "newmtl" {
+            if let Some((name, properties)) = current.take() {
+                materials.insert(name, properties.into_material());
+            }
+            let name = tokens.collect::<Vec<&str>>().join(" ");
+            current = Some((name, MtlProperties::default()));
+            continue;
+        }
+
+        let Some((_, properties)) = current.as_mut() else {
+            continue; // Properties before the first material.
+        };
+        match keyword {
+            "Kd" => properties.diffuse = parse_vector(line, line_number, &mut tokens)?,
+            "Ks" => properties.specular = parse_vector(line, line_number, &mut tokens)?,
+            "Ns" => properties.shininess = parse_number(line, line_number, &mut tokens)?,
+            "Ni" => properties.refractive_index = parse_number(line, line_number, &mut tokens)?,
+            "d" => properties.transparency = 1.0 - parse_number(line, line_number, &mut tokens)?,
+            "Tr" => properties.transparency = parse_number(line, line_number, &mut tokens)?,
+            "illum" => {
+                properties.illumination_model = parse_number(line, line_number, &mut tokens)? as u8
+            }
+            _ => {}
+        }
+    }
+
+    if let Some((name, properties)) = current.take() {
+        materials.insert(name, properties.into_material());
+    }
+
+    Ok(materials)
+}
+
+struct MtlProperties {
+    diffuse: Color,
+    illumination_model: u8,
+    refractive_index: f64,
+    shininess: f64,
+    specular: Color,
+    transparency: f64,
+}
+
+impl Default for MtlProperties {
+    fn default() -> MtlProperties {
+        MtlProperties {
+            diffuse: Color::new(0.8, 0.8, 0.8),
+            illumination_model: 2,
+            refractive_index: 1.5,
+            shininess: 0.0,
+            specular: Color::zero(),
+            transparency: 0.0,
+        }
+    }
+}
+
+impl MtlProperties {
+    fn into_material(self) -> Arc<dyn Material> {
+        let is_glass = matches!(self.illumination_model, 4 | 6 | 7 | 9) || self.transparency > 0.0;
+        let is_mirror = matches!(self.illumination_model, 3 | 5)
+            || (self.diffuse.is_zero() && !self.specular.is_zero());
+
+        if is_glass {
+            Arc::new(Dielectric::new(self.refractive_index))
+        } else if is_mirror {
+            // A high shininess is a sharp reflection. Map the exponent to our fuzz (0.0 to 1.0).
+            let fuzz = (2.0 / (self.shininess + 2.0)).sqrt().min(1.0);
+            Arc::new(Reflective::new(self.specular, fuzz))
+        } else {
+            Arc::new(Diffuse::new(self.diffuse))
+        }
+    }
+}
+
+// Position (1-based) of the token in the line. The token is a slice of the line, so the column
+// follows from how far apart they are in memory.
+fn column_of(line: &str, token: &str) -> usize {
+    (token.as_ptr() as usize).saturating_sub(line.as_ptr() as usize) + 1
+}
+
+fn parse_number<'a>(
+    line: &'a str,
+    line_number: usize,
+    tokens: &mut impl Iterator<Item = &'a str>,
+) -> Result<f64, SceneError> {
+    let Some(token) = tokens.next() else {
+        return Err(SceneError::Parse(ParseError::new(
+            line_number,
+            line.len() + 1,
+            "expected a number",
+        )));
+    };
+    token.parse::<f64>().map_err(|_| {
+        SceneError::Parse(ParseError::new(
+            line_number,
+            column_of(line, token),
+            format!("invalid number \"{}\"", token),
+        ))
+    })
+}
+
+fn parse_vector<'a>(
+    line: &'a str,
+    line_number: usize,
+    tokens: &mut impl Iterator<Item = &'a str>,
+) -> Result<Vec3, SceneError> {
+    let x = parse_number(line, line_number, tokens)?;
+    let y = parse_number(line, line_number, tokens)?;
+    let z = parse_number(line, line_number, tokens)?;
+    Ok(Point3::new(x, y, z))
+}
+
+// The (zero-based) indices of a corner of a face.
+#[derive(Clone, Copy)]
+struct FaceVertex {
+    normal: Option<u32>,
+    position: u32,
+    texture_coordinate: Option<u32>,
+}
+
+// A face vertex is "v", "v/vt", "v//vn" or "v/vt/vn".
+fn parse_face_vertex(token: &str, buffers: &VertexBuffers) -> Result<FaceVertex, String> {
+    let mut indices = token.split('/');
+    let position = indices.next().unwrap_or("");
+    let texture_coordinate = indices.next().filter(|index| !index.is_empty());
+    let normal = indices.next().filter(|index| !index.is_empty());
+
+    // RUST: map() and transpose() turn an Option<Result<..>> into a Result<Option<..>>, so that ?
+    // can be used.
+    Ok(FaceVertex {
+        normal: normal
+            .map(|index| resolve_index(index, buffers.normals.len()))
+            .transpose()?,
+        position: resolve_index(position, buffers.positions.len())?,
+        texture_coordinate: texture_coordinate
+            .map(|index| resolve_index(index, buffers.texture_coordinates.len()))
+            .transpose()?,
+    })
+}
+
+// A triangle only gets normals (or texture coordinates) if all of its corners have them.
+fn all_or_none(indices: [Option<u32>; 3]) -> Option<[u32; 3]> {
+    match indices {
+        [Some(a), Some(b), Some(c)] => Some([a, b, c]),
+        _ => None,
+    }
+}
+
+// OBJ indices start at 1. Negative indices count from the end (-1 is the most recent).
+fn resolve_index(index: &str, count: usize) -> Result<u32, String> {
+    let parsed = index
+        .parse::<i64>()
+        .map_err(|_| format!("invalid index \"{}\"", index))?;
+
+    let resolved = if parsed > 0 {
+        parsed - 1
+    } else {
+        count as i64 + parsed
+    };
+    if parsed == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(format!(
+            "index {} is out of range, there are {} so far",
+            parsed, count
+        ));
+    }
+
+    Ok(resolved as u32)
+}
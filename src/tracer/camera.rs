@@ -1,3 +1,4 @@
+use crate::sampler::Sampler;
 use crate::tracer::ray::Ray;
 use crate::vec3::{Point3, Vec3};
 
@@ -7,6 +8,8 @@ pub struct Camera {
     lens_radius: f64,
     lower_left_corner: Point3,
     origin: Point3,
+    shutter_close: f64,
+    shutter_open: f64,
     u: Vec3,
     v: Vec3,
 }
@@ -19,6 +22,7 @@ impl Camera {
         aspect_ratio: f64,
         aperture: f64,
         focus_distance: f64,
+        (shutter_open, shutter_close): (f64, f64),
     ) -> Camera {
         let view_up: Vec3 = Vec3::new(0.0, 1.0, 0.0); // View-up vector. Used to describe the rotation of the camera at the look_from point. In this case, we keep it horizontally leveled.
 
@@ -55,6 +59,8 @@ impl Camera {
             lens_radius: aperture / 2.0,
             lower_left_corner,
             origin: look_from,
+            shutter_close,
+            shutter_open,
             u,
             v,
         }
@@ -63,12 +69,12 @@ impl Camera {
     // In real life, light rays originate from a light source. They bounce off objects in the world
     // and then and go into our eyes. With ray tracing, we shoot out rays into the world instead. We
     // use them to scan the world for objects, and then color those objects using hit points.
-    pub fn shoot_ray_at(&self, s: f64, t: f64) -> Ray {
+    pub fn shoot_ray_at(&self, s: f64, t: f64, sampler: &mut Sampler) -> Ray {
         // Normally, all rays originate at the look_from / origin point. To simulate depth of field, we
         // generate random rays from inside a unit disk centered at the look_from / origin point. The
         // intensity of the randomness is controlled by the lens radius. The larger the radius, the
         // greater the defocus blur (and vice versa).
-        let random_in_unit_disk = Vec3::random_in_unit_disk() * self.lens_radius;
+        let random_in_unit_disk = Vec3::random_in_unit_disk(sampler) * self.lens_radius;
         let offset = (random_in_unit_disk.x() * self.u) + (random_in_unit_disk.y() * self.v);
 
         let ray_origin = offset + self.origin;
@@ -78,6 +84,15 @@ impl Camera {
             - self.origin
             - offset;
 
-        Ray::new(ray_origin, ray_direction)
+        // A real camera lets light in while its shutter is open. Anything that moves during that
+        // time is smeared out over the image (motion blur). So every ray is sent at a random time
+        // between the opening and closing of the shutter.
+        let time = if self.shutter_close > self.shutter_open {
+            sampler.range(self.shutter_open..self.shutter_close)
+        } else {
+            self.shutter_open
+        };
+
+        Ray::new(ray_origin, ray_direction, time)
     }
 }
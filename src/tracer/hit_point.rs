@@ -10,6 +10,8 @@ pub struct HitPoint<'a> {
     point: Point3,              // Position of intersection point / hit point.
     surface_normal: Vec3, // "Normal" vector (perpendicular to surface). Not the same as unit vector (normalized)!
     t: f64,               // Distance along the ray to the intersection point.
+    u: f64,               // Surface (texture) coordinates of the hit point. 0.0 to 1.0.
+    v: f64,
 }
 
 impl<'a> HitPoint<'a> {
@@ -17,6 +19,7 @@ impl<'a> HitPoint<'a> {
         point: Point3,
         t: f64,
         surface_normal: Vec3,
+        (u, v): (f64, f64),
         ray: &Ray,
         material: &'a dyn Material,
     ) -> HitPoint<'a> {
@@ -26,9 +29,11 @@ impl<'a> HitPoint<'a> {
             surface_normal,
             t,
             material,
+            u,
+            v,
         };
 
-        hit_point.set_front_facing(&ray);
+        hit_point.set_front_facing(ray);
 
         hit_point
     }
@@ -53,6 +58,33 @@ impl<'a> HitPoint<'a> {
         self.t
     }
 
+    pub fn u(&self) -> f64 {
+        self.u
+    }
+
+    pub fn v(&self) -> f64 {
+        self.v
+    }
+
+    // Replace the material, like when the object is the boundary of a Volume.
+    pub fn set_material(&mut self, material: &'a dyn Material) {
+        self.material = material;
+    }
+
+    // Turn going into the object into coming out of it, and the other way around. Like for the
+    // surface of a hole cut out of an object (see Csg): going into the hole is coming out of the
+    // object. The surface normal still faces the ray.
+    pub fn flip_front_facing(&mut self) {
+        self.front_facing = !self.front_facing;
+    }
+
+    // Move the hit point, like when the object it's on is transformed. The surface normal must
+    // still face the ray; whether the front of the surface was hit doesn't change.
+    pub fn set_point_and_surface_normal(&mut self, point: Point3, surface_normal: Vec3) {
+        self.point = point;
+        self.surface_normal = surface_normal;
+    }
+
     fn set_front_facing(&mut self, ray: &Ray) {
         let ray_direction = ray.direction();
         let surface_normal_faces_ray = ray_direction.dot(self.surface_normal) < 0.0;
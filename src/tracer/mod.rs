@@ -1,4 +1,5 @@
 pub mod camera;
 pub mod hit_point;
 pub mod ray;
+#[allow(clippy::module_inception)]
 pub mod tracer;
@@ -1,14 +1,22 @@
 use crate::vec3::{Color, Point3, Vec3};
 
 // A light ray. Photon.
+//
+// RUST: Copy lets a ray be copied by assignment, like a number. It's only a few numbers, after all.
+#[derive(Clone, Copy)]
 pub struct Ray {
     direction: Vec3,
     origin: Point3,
+    time: f64, // When the ray exists, within the shutter interval of the camera. For motion blur.
 }
 
 impl Ray {
-    pub fn new(origin: Point3, direction: Vec3) -> Ray {
-        Ray { direction, origin }
+    pub fn new(origin: Point3, direction: Vec3, time: f64) -> Ray {
+        Ray {
+            direction,
+            origin,
+            time,
+        }
     }
 
     pub fn direction(&self) -> Vec3 {
@@ -19,6 +27,10 @@ impl Ray {
         self.origin
     }
 
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
     // Returns a position along the ray (position at t).
     pub fn point_at(&self, t: f64) -> Point3 {
         self.origin + (t * self.direction)
@@ -27,23 +39,65 @@ impl Ray {
 
 // A light ray that has bounced of an object. Some light might have been absorbed (light
 // attenuation).
+//
+// The material picked the direction at random, with some probability density (pdf). The light
+// attenuation already accounts for it: it's the BSDF times the cosine, divided by the density (see
+// Material). Directions that are picked more often count for less each time.
 pub struct ScatteredRay {
+    is_specular: bool,
     light_attenuation: Color,
+    pdf: f64,
     ray: Ray,
 }
 
 impl ScatteredRay {
-    pub fn new(origin: Point3, direction: Vec3, light_attenuation: Color) -> ScatteredRay {
+    // The time is the time of the ray that was scattered. A bounce is instant.
+    pub fn new(
+        origin: Point3,
+        direction: Vec3,
+        time: f64,
+        light_attenuation: Color,
+        pdf: f64,
+    ) -> ScatteredRay {
+        ScatteredRay {
+            is_specular: false,
+            light_attenuation,
+            pdf,
+            ray: Ray::new(origin, direction, time),
+        }
+    }
+
+    // Scattered in an exact direction, like by a mirror or glass. The density of such a direction
+    // is infinite (all of the probability in a single direction), so it has no pdf.
+    pub fn specular(
+        origin: Point3,
+        direction: Vec3,
+        time: f64,
+        light_attenuation: Color,
+    ) -> ScatteredRay {
         ScatteredRay {
+            is_specular: true,
             light_attenuation,
-            ray: Ray::new(origin, direction),
+            pdf: 0.0,
+            ray: Ray::new(origin, direction, time),
         }
     }
 
+    // Specular rays can't be compared with light sampling. Material::evaluate() and
+    // Material::pdf() don't know about their direction.
+    pub fn is_specular(&self) -> bool {
+        self.is_specular
+    }
+
     pub fn light_attenuation(&self) -> Color {
         self.light_attenuation
     }
 
+    // The probability density (per solid angle) of the direction. 0.0 for specular rays.
+    pub fn pdf(&self) -> f64 {
+        self.pdf
+    }
+
     pub fn ray(&self) -> &Ray {
         &self.ray
     }
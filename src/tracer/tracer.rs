@@ -1,35 +1,420 @@
-use crate::scene::background::background_color;
+use crate::sampler::Sampler;
+use crate::scene::background::Background;
+use crate::scene::light::light::Light;
+use crate::scene::material::material::Material;
+use crate::scene::medium::medium::{Interaction, Medium};
 use crate::scene::object::object::Object;
+use crate::scene::object::world::World;
+use crate::tracer::hit_point::HitPoint;
 use crate::tracer::ray::Ray;
 use crate::vec3::Color;
 
-pub fn trace_ray_in_world<T: Object>(ray: &Ray, world: &T, maximum_ray_bounce_depth: u8) -> Color {
+// Shadow acne is a visual artefact that appears in the shape of small black dots on the surface
+// of objects. This is because some of the reflected rays aren't reflecting of at t=0 but at an
+// approximation, such as -0.0000001 or 0.0000001. Therefore we need to ignore hits very near to
+// zero.
+pub const T_MIN: f64 = 0.001;
+const T_MAX: f64 = f64::INFINITY;
+
+// Follows the ray as it bounces around the world, and adds up the light that reaches the camera
+// along the way ("path tracing").
+//
+// Light only comes from light sources (and the background). A ray that bounces around at random
+// rarely hits a small light source, so it would take a huge number of samples for the noise to go
+// away. Instead, at every hit on a diffuse surface, a "shadow ray" is sent straight towards a light
+// source ("next-event estimation"). If nothing is in the way, the light it brings is added right
+// away.
+//
+// That finds the same light twice though: once through the shadow ray, and once more when the
+// scattered ray happens to hit the light. Both are kept, but weighted so that the weights add up
+// to one ("multiple importance sampling", Veach 1997). Each gets more weight where it's the more
+// likely way to find the light: shadow rays for small, bright, lights, and scattered rays for large
+// lights, and shiny surfaces that scatter into a narrow range of directions.
+//
+// An environment map (see Background) is treated like a light source too. Lights without a size
+// (point, spot and directional lights, see Light) can only be found with shadow rays, so there is
+// nothing to weigh them against.
+//
+// The path is followed in a loop instead of recursively. The light that the path has let through
+// so far is the "throughput". Every bounce multiplies it by the light attenuation.
+//
+// Rays also go through media: fog everywhere, and the insides of Volumes (see Medium). The tracer
+// keeps a stack of the media the ray is in, the innermost on top, entering and leaving them at
+// the boundaries of volumes. In a medium, the ray may run into a particle before it gets to the
+// next surface. That point scatters light like a hit point does, with the phase function of the
+// medium as its material. Shadow rays go through the boundaries too, and lose the light that the
+// media on the way take out of them.
+//
+// Objects that let light in, like colored glass, can absorb some of it inside (see
+// Material::absorption()). Like the media, the tracer keeps a stack of the objects the ray went
+// into, through their surface, and takes them off when it comes back out. Between two hits, the
+// object on top absorbs light over the distance. A ray that hits the inside of a surface it never
+// went through, like the back of a plane, or the camera inside of glass, absorbs nothing.
+pub fn trace_ray_in_world<T: Object>(
+    ray: &Ray,
+    world: &World<T>,
+    lights: &[Box<dyn Light>],
+    background: &Background,
+    fog: Option<&dyn Medium>,
+    maximum_ray_bounce_depth: u8,
+    sampler: &mut Sampler,
+) -> Color {
+    let mut color = Color::zero();
+    let mut throughput = Color::new(1.0, 1.0, 1.0);
+
+    let mut ray = *ray;
+
+    // The probability density of the last bounce scattering in the direction of the ray. None for
+    // rays from the camera and from specular bounces, which didn't do next-event estimation. Light
+    // they hit gets the full weight.
+    let mut scattering_pdf: Option<f64> = None;
+
+    // The ray as it left the last bounce. The ray itself starts over at every volume boundary it
+    // goes through, but the density of a light source being hit depends on where it came from.
+    let mut scattered_ray = ray;
+
+    // The camera is in the fog (if there is any), but not inside of a volume.
+    let mut media: Vec<&dyn Medium> = fog.into_iter().collect();
+
+    // The materials of the objects the ray went into, the innermost on top.
+    let mut insides: Vec<&dyn Material> = Vec::new();
+
     // No light at the maximum bounce depth. Could be a ray bouncing around inside of a crack of the
-    // object.
-    if maximum_ray_bounce_depth <= 0 {
-        return Color::zero();
+    // object. Going through the boundary of a volume counts as a bounce too.
+    for _ in 0..maximum_ray_bounce_depth {
+        let hit = world.is_hit_by_ray(&ray, T_MIN, T_MAX);
+
+        // Not hitting anything at all, the ray is outside of every volume. If it thought it was
+        // inside one, it slipped out: a ray that grazes a volume can go in and out again too
+        // close together to tell apart (see T_MIN).
+        if hit.is_none() {
+            media.truncate(usize::from(fog.is_some()));
+            insides.clear();
+        }
+
+        // In a medium, the ray may run into a particle before it gets to the surface.
+        if let Some(medium) = media.last() {
+            let t_max = hit.as_ref().map_or(T_MAX, |hit_point| hit_point.t());
+            match medium.sample_interaction(&ray, t_max, sampler) {
+                Interaction::Passed {
+                    emitted,
+                    light_attenuation,
+                } => {
+                    color = color + (throughput * emitted);
+                    throughput = throughput * light_attenuation;
+                }
+                Interaction::Scattered {
+                    emitted,
+                    light_attenuation,
+                    t,
+                } => {
+                    color = color + (throughput * emitted);
+                    throughput = throughput * light_attenuation;
+                    if let Some(inside) = insides.last() {
+                        throughput = throughput * absorbed(*inside, &ray, t);
+                    }
+
+                    // The point in the medium, as a hit point. It has no surface, so the "normal"
+                    // just faces the ray.
+                    let phase_function = medium.phase_function();
+                    let medium_point = HitPoint::new(
+                        ray.point_at(t),
+                        t,
+                        -ray.direction().unit_vector(),
+                        (0.0, 0.0),
+                        &ray,
+                        phase_function,
+                    );
+                    let Some(scatter) = phase_function.scatter(&ray, &medium_point, sampler) else {
+                        break;
+                    };
+
+                    let direct_light =
+                        light_from_lights(&ray, &medium_point, world, lights, &media, sampler)
+                            + light_from_light_sources(&ray, &medium_point, world, &media, sampler)
+                            + light_from_background(
+                                &ray,
+                                &medium_point,
+                                world,
+                                background,
+                                &media,
+                                sampler,
+                            );
+                    color = color + (throughput * direct_light);
+
+                    scattering_pdf = Some(scatter.pdf());
+                    throughput = throughput * scatter.light_attenuation();
+                    ray = *scatter.ray();
+                    scattered_ray = ray;
+                    continue;
+                }
+            }
+        }
+
+        // RUST: let-else. Either there is a hit point, or the ray escaped to the background.
+        let Some(hit_point) = hit else {
+            // An environment map is sampled like a light source, so weigh it the same way.
+            let weight = match scattering_pdf {
+                Some(scattering_pdf) => {
+                    power_heuristic(scattering_pdf, background.direction_pdf(ray.direction()))
+                }
+                None => 1.0,
+            };
+            color = color + (throughput * background.color(&ray) * weight);
+            break;
+        };
+        let material = hit_point.material();
+
+        // Light absorbed on the way here, inside of an object.
+        if let Some(inside) = insides.last() {
+            throughput = throughput * absorbed(*inside, &ray, hit_point.t());
+        }
+
+        // The boundary of a volume. Go into, or out of, its medium, and carry on.
+        if let Some(medium) = material.medium() {
+            enter_or_leave(&mut media, medium, hit_point.front_facing());
+            ray = Ray::new(hit_point.point(), ray.direction(), ray.time());
+            continue;
+        }
+
+        // The light emitted by the object itself, weighted against the shadow rays that could have
+        // found it too.
+        let emitted = material.emitted(&hit_point);
+        if !emitted.is_zero() {
+            let weight = match scattering_pdf {
+                Some(scattering_pdf) => {
+                    power_heuristic(scattering_pdf, world.light_pdf(&scattered_ray))
+                }
+                None => 1.0,
+            };
+            color = color + (throughput * emitted * weight);
+        }
+
+        // All light is absorbed (by a light source, for example). No need to trace any further.
+        let Some(scatter) = material.scatter(&ray, &hit_point, sampler) else {
+            break;
+        };
+        let light_attenuation = scatter.light_attenuation();
+        if light_attenuation.is_zero() {
+            break;
+        }
+
+        // Next-event estimation. Specular bounces can't do it, see Material.
+        scattering_pdf = None;
+        if !scatter.is_specular() {
+            let direct_light = light_from_lights(&ray, &hit_point, world, lights, &media, sampler)
+                + light_from_light_sources(&ray, &hit_point, world, &media, sampler)
+                + light_from_background(&ray, &hit_point, world, background, &media, sampler);
+            color = color + (throughput * direct_light);
+            scattering_pdf = Some(scatter.pdf());
+        }
+
+        // Through the surface, into the object or out of it. The surface normal faces the ray,
+        // so a scattered ray on the other side goes the other way.
+        if scatter.ray().direction().dot(hit_point.surface_normal()) < 0.0 {
+            enter_or_leave(&mut insides, material, hit_point.front_facing());
+        }
+
+        throughput = throughput * light_attenuation;
+        ray = *scatter.ray();
+        scattered_ray = ray;
     }
 
-    // Shadow acne is a visual artefact that appears in the shape of small black dots on the surface
-    // of objects. This is because some of the reflected rays aren't reflecting of at t=0 but at an
-    // approximation, such as -0.0000001 or 0.0000001. Therefore we need to ignore hits very near to
-    // zero.
-    const T_MIN: f64 = 0.001;
-    const T_MAX: f64 = f64::INFINITY;
-    match world.is_hit_by_ray(&ray, T_MIN, T_MAX) {
-        // RUST: Match multiple patterns: None, Some.
-        None => {}
+    color
+}
+
+// Going into a volume puts its medium on top of the stack. Going out takes it off, wherever it is
+// in the stack: volumes can overlap without one being inside of the other. The same for the
+// materials of objects the ray goes into.
+//
+// RUST: T: ?Sized allows trait objects, like dyn Medium, which don't have a size known at compile
+// time.
+fn enter_or_leave<'a, T: ?Sized>(stack: &mut Vec<&'a T>, item: &'a T, enter: bool) {
+    if enter {
+        stack.push(item);
+        return;
+    }
 
-        Some(hit_point) => {
-            let ray_bounce_depth = maximum_ray_bounce_depth - 1;
+    // RUST: Comparing the addresses (as thin pointers) tells if two references are to the same
+    // medium or material.
+    let address = item as *const T as *const ();
+    if let Some(index) = stack
+        .iter()
+        .rposition(|other| *other as *const T as *const () == address)
+    {
+        stack.remove(index);
+    }
+}
 
-            let scatter = hit_point.material().scatter(&ray, &hit_point);
-            let scattered_ray = scatter.ray();
-            let light_attenuation = scatter.light_attenuation();
+// Follow a shadow ray up to t_max, through the boundaries of volumes, and the media on the way.
+// Returns the fraction of light that gets through the media, and the first real surface the
+// shadow ray hits, if any.
+fn trace_shadow_ray<'a, T: Object>(
+    shadow_ray: &Ray,
+    t_max: f64,
+    world: &'a World<T>,
+    media: &[&dyn Medium],
+    sampler: &mut Sampler,
+) -> (Color, Option<HitPoint<'a>>) {
+    let mut media = media.to_vec();
+    let mut transmittance = Color::new(1.0, 1.0, 1.0);
+    let mut ray = *shadow_ray;
+    let mut t_max = t_max;
 
-            return trace_ray_in_world(&scattered_ray, world, ray_bounce_depth) * light_attenuation;
+    loop {
+        let hit = world.is_hit_by_ray(&ray, T_MIN, t_max);
+        if let Some(medium) = media.last() {
+            let t_end = hit.as_ref().map_or(t_max, |hit_point| hit_point.t());
+            transmittance = transmittance * medium.transmittance(&ray, t_end, sampler);
         }
+
+        let Some(hit_point) = hit else {
+            return (transmittance, None);
+        };
+        let Some(medium) = hit_point.material().medium() else {
+            return (transmittance, Some(hit_point));
+        };
+        if transmittance.is_zero() {
+            return (transmittance, None);
+        }
+
+        // Start over at the boundary, for the rest of the way.
+        enter_or_leave(&mut media, medium, hit_point.front_facing());
+        ray = Ray::new(hit_point.point(), ray.direction(), ray.time());
+        t_max -= hit_point.t();
+    }
+}
+
+// The fraction of light that is left after going along the ray up to t, inside of an object made
+// of the material.
+fn absorbed(material: &dyn Material, ray: &Ray, t: f64) -> Color {
+    transmittance(material.absorption(), t * ray.direction().length())
+}
+
+// The fraction of light that is left after going the distance through a material that absorbs
+// light (the Beer-Lambert law).
+fn transmittance(absorption: Color, distance: f64) -> Color {
+    Color::new(
+        (-absorption.x() * distance).exp(),
+        (-absorption.y() * distance).exp(),
+        (-absorption.z() * distance).exp(),
+    )
+}
+
+// Light from the point, spot and directional lights, that is scattered back along the ray.
+fn light_from_lights<T: Object>(
+    ray: &Ray,
+    hit_point: &HitPoint,
+    world: &World<T>,
+    lights: &[Box<dyn Light>],
+    media: &[&dyn Medium],
+    sampler: &mut Sampler,
+) -> Color {
+    let mut color = Color::zero();
+    for light in lights {
+        let Some(illumination) = light.illuminate(hit_point.point()) else {
+            continue;
+        };
+        let scattered_light = hit_point
+            .material()
+            .evaluate(ray, hit_point, illumination.direction);
+        if scattered_light.is_zero() {
+            continue;
+        }
+
+        // In the shadow if anything is in between.
+        let shadow_ray = Ray::new(hit_point.point(), illumination.direction, ray.time());
+        let (transmittance, hit) =
+            trace_shadow_ray(&shadow_ray, illumination.distance, world, media, sampler);
+        if hit.is_none() {
+            color = color + (scattered_light * illumination.light * transmittance);
+        }
+    }
+    color
+}
+
+// Light from a randomly picked light source (an object), weighted against the scattered ray. The
+// shadow ray hits the light source, or whatever is in the way. Both are fine: an object in the way
+// emits nothing (casts a shadow), and the light it does emit would have been found by a scattered
+// ray too.
+fn light_from_light_sources<T: Object>(
+    ray: &Ray,
+    hit_point: &HitPoint,
+    world: &World<T>,
+    media: &[&dyn Medium],
+    sampler: &mut Sampler,
+) -> Color {
+    if !world.has_light_sources() {
+        return Color::zero();
+    }
+
+    let material = hit_point.material();
+    let direction = world
+        .random_direction_towards_light(hit_point.point(), ray.time(), sampler)
+        .unit_vector();
+    let shadow_ray = Ray::new(hit_point.point(), direction, ray.time());
+    let light_pdf = world.light_pdf(&shadow_ray);
+    let scattered_light = material.evaluate(ray, hit_point, direction);
+    if light_pdf <= 0.0 || scattered_light.is_zero() {
+        return Color::zero();
     }
 
-    background_color(&ray)
+    let (transmittance, light_hit_point) =
+        trace_shadow_ray(&shadow_ray, T_MAX, world, media, sampler);
+    let Some(light_hit_point) = light_hit_point else {
+        return Color::zero();
+    };
+    let light = light_hit_point.material().emitted(&light_hit_point) * transmittance;
+    let weight = power_heuristic(light_pdf, material.pdf(ray, hit_point, direction));
+    scattered_light * light * (weight / light_pdf)
+}
+
+// Light from a random direction of the background (an environment map), weighted against the
+// scattered ray. It only arrives if nothing at all is in the way.
+fn light_from_background<T: Object>(
+    ray: &Ray,
+    hit_point: &HitPoint,
+    world: &World<T>,
+    background: &Background,
+    media: &[&dyn Medium],
+    sampler: &mut Sampler,
+) -> Color {
+    if !background.can_be_sampled() {
+        return Color::zero();
+    }
+
+    let material = hit_point.material();
+    let direction = background.random_direction(sampler).unit_vector();
+    let background_pdf = background.direction_pdf(direction);
+    let scattered_light = material.evaluate(ray, hit_point, direction);
+    if background_pdf <= 0.0 || scattered_light.is_zero() {
+        return Color::zero();
+    }
+
+    let shadow_ray = Ray::new(hit_point.point(), direction, ray.time());
+    let (transmittance, hit) = trace_shadow_ray(&shadow_ray, T_MAX, world, media, sampler);
+    if hit.is_some() {
+        return Color::zero();
+    }
+    let weight = power_heuristic(background_pdf, material.pdf(ray, hit_point, direction));
+    scattered_light * background.color(&shadow_ray) * transmittance * (weight / background_pdf)
+}
+
+// The weight of a sample picked with one density, when it could also have been picked with the
+// other. Squaring the densities favors the more likely way even more than just comparing them
+// (the "balance heuristic"), which usually gives less noise. If the other way can't pick it at
+// all, it gets the full weight.
+//
+// A light source so far away that the directions towards it are too few to tell apart (like from a
+// point deep in fog) has an infinite density. Only aiming at it can find it then.
+fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    if pdf.is_infinite() {
+        return 1.0;
+    }
+    let squared = pdf * pdf;
+    let other_squared = other_pdf * other_pdf;
+    if other_squared == 0.0 {
+        return 1.0;
+    }
+    squared / (squared + other_squared)
 }
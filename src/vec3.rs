@@ -1,6 +1,7 @@
-use rand::{random, thread_rng, Rng};
 use std::f64::consts::PI;
-use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::ops::{Add, Div, Index, Mul, Neg, Sub};
+
+use crate::sampler::Sampler;
 
 #[derive(Clone, Copy)] // RUST: Enable "copy semantics". Copy, instead of move, when reassigning a variable.
 pub struct Vec3 {
@@ -19,18 +20,18 @@ impl Vec3 {
         }
     }
 
-    pub fn random() -> Vec3 {
-        let c1 = random::<f64>();
-        let c2 = random::<f64>();
-        let c3 = random::<f64>();
+    pub fn random(sampler: &mut Sampler) -> Vec3 {
+        let c1 = sampler.next_f64();
+        let c2 = sampler.next_f64();
+        let c3 = sampler.next_f64();
 
         Vec3 {
             components: [c1, c2, c3],
         }
     }
 
-    // pub fn random_in_hemisphere(surface_normal: Vec3) -> Vec3 {
-    //   let random_in_unit_sphere = Vec3::random_in_unit_sphere();
+    // pub fn random_in_hemisphere(surface_normal: Vec3, sampler: &mut Sampler) -> Vec3 {
+    //   let random_in_unit_sphere = Vec3::random_in_unit_sphere(sampler);
     //   // In the same hemisphere as the surface normal.
     //   if random_in_unit_sphere.dot(surface_normal) > 0.0 {
     //     return random_in_unit_sphere;
@@ -38,12 +39,10 @@ impl Vec3 {
     //   return -random_in_unit_sphere;
     // }
 
-    pub fn random_in_unit_disk() -> Vec3 {
-        let mut rng = thread_rng();
-
+    pub fn random_in_unit_disk(sampler: &mut Sampler) -> Vec3 {
         loop {
-            let c1 = rng.gen_range(-1.0..1.0);
-            let c2 = rng.gen_range(-1.0..1.0);
+            let c1 = sampler.range(-1.0..1.0);
+            let c2 = sampler.range(-1.0..1.0);
             let c3 = 0.0;
 
             let random_point = Vec3::new(c1, c2, c3);
@@ -53,9 +52,9 @@ impl Vec3 {
         }
     }
 
-    pub fn random_in_unit_sphere() -> Vec3 {
+    pub fn random_in_unit_sphere(sampler: &mut Sampler) -> Vec3 {
         loop {
-            let random_point = Vec3::random_within_range(-1.0, 1.0);
+            let random_point = Vec3::random_within_range(-1.0, 1.0, sampler);
             // 1.0 is the radius of the unit sphere.
             if random_point.length_squared() < 1.0 {
                 break random_point;
@@ -63,12 +62,10 @@ impl Vec3 {
         }
     }
 
-    pub fn random_unit_vector() -> Vec3 {
-        let mut rng = thread_rng();
-
-        let a = rng.gen_range(0.0..PI * 2.0);
-        let z = rng.gen_range(-1.0..1.0);
-        let r = ((1.0 - (z * z)) as f64).sqrt();
+    pub fn random_unit_vector(sampler: &mut Sampler) -> Vec3 {
+        let a = sampler.range(0.0..PI * 2.0);
+        let z: f64 = sampler.range(-1.0..1.0);
+        let r = (1.0 - (z * z)).sqrt();
 
         let c1 = a.cos() * r;
         let c2 = a.sin() * r;
@@ -79,12 +76,10 @@ impl Vec3 {
         }
     }
 
-    pub fn random_within_range(min: f64, max: f64) -> Vec3 {
-        let mut rng = thread_rng();
-
-        let c1 = rng.gen_range(min..max);
-        let c2 = rng.gen_range(min..max);
-        let c3 = rng.gen_range(min..max);
+    pub fn random_within_range(min: f64, max: f64, sampler: &mut Sampler) -> Vec3 {
+        let c1 = sampler.range(min..max);
+        let c2 = sampler.range(min..max);
+        let c3 = sampler.range(min..max);
 
         Vec3 {
             components: [c1, c2, c3],
@@ -130,6 +125,10 @@ impl Vec3 {
             + (self.components[2] * vector.components[2])
     }
 
+    pub fn is_zero(&self) -> bool {
+        self.components == [0.0, 0.0, 0.0]
+    }
+
     pub fn length(&self) -> f64 {
         self.length_squared().sqrt()
     }
@@ -140,6 +139,45 @@ impl Vec3 {
             + (self.components[2] * self.components[2])
     }
 
+    // Component-wise largest of the two vectors.
+    pub fn maximum(&self, vector: Vec3) -> Vec3 {
+        Vec3 {
+            components: [
+                self.components[0].max(vector.components[0]),
+                self.components[1].max(vector.components[1]),
+                self.components[2].max(vector.components[2]),
+            ],
+        }
+    }
+
+    // Component-wise smallest of the two vectors.
+    pub fn minimum(&self, vector: Vec3) -> Vec3 {
+        Vec3 {
+            components: [
+                self.components[0].min(vector.components[0]),
+                self.components[1].min(vector.components[1]),
+                self.components[2].min(vector.components[2]),
+            ],
+        }
+    }
+
+    // Two unit vectors that, together with this (unit) vector, are all perpendicular to each
+    // other. Useful as the "tangent" directions of a surface with this as its normal. Branchless
+    // method by Duff et al. (2017).
+    pub fn orthonormal_basis(&self) -> (Vec3, Vec3) {
+        let sign = 1.0_f64.copysign(self.z());
+        let a = -1.0 / (sign + self.z());
+        let b = self.x() * self.y() * a;
+
+        let tangent = Vec3::new(
+            1.0 + (sign * self.x() * self.x() * a),
+            sign * b,
+            -sign * self.x(),
+        );
+        let bitangent = Vec3::new(b, sign + (self.y() * self.y() * a), -self.y());
+        (tangent, bitangent)
+    }
+
     // Return vector normalized to magnitude (length) of 1.
     pub fn unit_vector(&self) -> Vec3 {
         Vec3 {
@@ -175,6 +213,14 @@ impl Div<f64> for Vec3 {
     }
 }
 
+// Index operator: []
+impl Index<usize> for Vec3 {
+    type Output = f64;
+    fn index(&self, index: usize) -> &f64 {
+        &self.components[index]
+    }
+}
+
 // Multiplication operator: *
 impl Mul for Vec3 {
     type Output = Vec3;